@@ -0,0 +1,5 @@
+DROP TABLE IF EXISTS alert_events;
+
+DROP TABLE IF EXISTS alert_rules;
+
+DROP TABLE IF EXISTS topic_post_counts;
@@ -0,0 +1,36 @@
+CREATE TABLE IF NOT EXISTS "topic_post_counts" (
+    "topic_id" INTEGER NOT NULL,
+    "minute" DATETIME NOT NULL,
+    "count" INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY ("topic_id", "minute"),
+    FOREIGN KEY ("topic_id") REFERENCES "topics" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS "alert_rules" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "created_at" DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    "topic_id" INTEGER NOT NULL,
+    "name" TEXT NOT NULL,
+    "enabled" BOOLEAN NOT NULL DEFAULT TRUE,
+    "condition" BLOB NOT NULL,
+    "channels" BLOB NOT NULL,
+    "state" TEXT NOT NULL DEFAULT 'resolved',
+    "state_changed_at" DATETIME DEFAULT NULL,
+    "last_evaluated_at" DATETIME DEFAULT NULL,
+    FOREIGN KEY ("topic_id") REFERENCES "topics" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS "alert_events" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "created_at" DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    "rule_id" INTEGER NOT NULL,
+    "state" TEXT NOT NULL,
+    "value" REAL NOT NULL,
+    "baseline" REAL DEFAULT NULL,
+    "message" TEXT NOT NULL,
+    FOREIGN KEY ("rule_id") REFERENCES "alert_rules" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_alert_rules_topic_id ON alert_rules (topic_id);
+
+CREATE INDEX IF NOT EXISTS idx_alert_events_rule_id ON alert_events (rule_id);
@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS "topic_post_counts" (
+    "topic_id" INTEGER NOT NULL,
+    "minute" DATETIME NOT NULL,
+    "count" INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY ("topic_id", "minute"),
+    FOREIGN KEY ("topic_id") REFERENCES "topics" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+INSERT INTO
+    topic_post_counts (topic_id, minute, count)
+SELECT topic_id, bucket_start, count
+FROM topic_post_rollups
+WHERE
+    bucket = '1m'
+    AND dimension = 'total';
+
+DROP TABLE IF EXISTS topic_post_rollups;
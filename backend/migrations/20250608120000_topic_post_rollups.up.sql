@@ -0,0 +1,77 @@
+CREATE TABLE IF NOT EXISTS "topic_post_rollups" (
+    "topic_id" INTEGER NOT NULL,
+    "bucket" TEXT NOT NULL,
+    "bucket_start" DATETIME NOT NULL,
+    "dimension" TEXT NOT NULL,
+    "dimension_value" TEXT NOT NULL DEFAULT '',
+    "count" INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (
+        "topic_id",
+        "bucket",
+        "dimension",
+        "dimension_value",
+        "bucket_start"
+    ),
+    FOREIGN KEY ("topic_id") REFERENCES "topics" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- Per minute counts used by alerts are kept as the `1m` bucket
+INSERT INTO
+    topic_post_rollups (
+        topic_id,
+        bucket,
+        bucket_start,
+        dimension,
+        count
+    )
+SELECT topic_id, '1m', minute, 'total', count
+FROM topic_post_counts;
+
+DROP TABLE IF EXISTS topic_post_counts;
+
+-- Backfill existing posts from their creation time, keyword splits are only available for posts
+-- ingested from now on
+WITH
+    buckets (bucket, seconds) AS (
+        VALUES ('5m', 300),
+            ('1h', 3600),
+            ('1d', 86400)
+    ),
+    post_buckets AS (
+        SELECT post_topics.topic_id, posts.langs, buckets.bucket, datetime(
+                (
+                    CAST(
+                        strftime('%s', posts.created_at) AS INTEGER
+                    ) / buckets.seconds
+                ) * buckets.seconds, 'unixepoch'
+            ) AS bucket_start
+        FROM
+            posts
+            JOIN post_topics ON posts.id = post_topics.post_id
+            CROSS JOIN buckets
+        WHERE
+            strftime('%s', posts.created_at) IS NOT NULL
+    )
+INSERT INTO
+    topic_post_rollups (
+        topic_id,
+        bucket,
+        bucket_start,
+        dimension,
+        dimension_value,
+        count
+    )
+SELECT topic_id, bucket, bucket_start, 'total', '', COUNT(*)
+FROM post_buckets
+GROUP BY
+    topic_id,
+    bucket,
+    bucket_start
+UNION ALL
+SELECT topic_id, bucket, bucket_start, 'language', lang.value, COUNT(*)
+FROM post_buckets, json_each(CAST(post_buckets.langs AS TEXT)) AS lang
+GROUP BY
+    topic_id,
+    bucket,
+    bucket_start,
+    lang.value;
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS "accounts" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "created_at" DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    "username" TEXT NOT NULL UNIQUE,
+    "password_hash" TEXT NOT NULL,
+    "role" TEXT NOT NULL DEFAULT 'viewer',
+    "enabled" BOOLEAN NOT NULL DEFAULT TRUE,
+    "last_login_at" DATETIME DEFAULT NULL
+);
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS "sessions" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "token_hash" TEXT NOT NULL UNIQUE,
+    "account_id" INTEGER NOT NULL,
+    "created_at" DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    "last_seen_at" DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    "expires_at" DATETIME NOT NULL,
+    "user_agent" TEXT DEFAULT NULL,
+    "ip" TEXT DEFAULT NULL,
+    FOREIGN KEY ("account_id") REFERENCES "accounts" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_sessions_account_id ON sessions (account_id);
+
+CREATE INDEX IF NOT EXISTS idx_sessions_expires_at ON sessions (expires_at);
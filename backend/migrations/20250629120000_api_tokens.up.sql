@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS "api_tokens" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "created_at" DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    "account_id" INTEGER NOT NULL,
+    "name" TEXT NOT NULL,
+    "token_hash" TEXT NOT NULL UNIQUE,
+    "scopes" BLOB NOT NULL,
+    "expires_at" DATETIME DEFAULT NULL,
+    "last_used_at" DATETIME DEFAULT NULL,
+    "revoked_at" DATETIME DEFAULT NULL,
+    FOREIGN KEY ("account_id") REFERENCES "accounts" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_tokens_account_id ON api_tokens (account_id);
@@ -0,0 +1,3 @@
+ALTER TABLE accounts ADD COLUMN "oidc_subject" TEXT DEFAULT NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_accounts_oidc_subject ON accounts (oidc_subject);
@@ -0,0 +1,30 @@
+CREATE TABLE IF NOT EXISTS "audit_log" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "created_at" DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    "actor_account_id" INTEGER DEFAULT NULL,
+    "actor_username" TEXT DEFAULT NULL,
+    "actor_api_token_id" INTEGER DEFAULT NULL,
+    "action" TEXT NOT NULL,
+    "target_type" TEXT NOT NULL,
+    "target_id" INTEGER DEFAULT NULL,
+    "before" BLOB DEFAULT NULL,
+    "after" BLOB DEFAULT NULL,
+    "ip" TEXT DEFAULT NULL,
+    "user_agent" TEXT DEFAULT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_created_at ON audit_log (created_at);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor_account_id ON audit_log (actor_account_id);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_target ON audit_log (target_type, target_id);
+
+CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit_log is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit_log is append-only');
+END;
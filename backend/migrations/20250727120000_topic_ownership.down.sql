@@ -0,0 +1,9 @@
+DROP INDEX IF EXISTS idx_topic_shares_account_id;
+
+DROP TABLE IF EXISTS topic_shares;
+
+DROP INDEX IF EXISTS idx_topics_owner_id;
+
+ALTER TABLE topics DROP COLUMN "visibility";
+
+ALTER TABLE topics DROP COLUMN "owner_id";
@@ -0,0 +1,17 @@
+ALTER TABLE topics ADD COLUMN "owner_id" INTEGER DEFAULT NULL REFERENCES accounts (id) ON DELETE SET NULL;
+
+ALTER TABLE topics ADD COLUMN "visibility" TEXT NOT NULL DEFAULT 'public';
+
+CREATE INDEX IF NOT EXISTS idx_topics_owner_id ON topics (owner_id);
+
+CREATE TABLE IF NOT EXISTS "topic_shares" (
+    "topic_id" INTEGER NOT NULL,
+    "account_id" INTEGER NOT NULL,
+    "created_at" DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    "role" TEXT NOT NULL DEFAULT 'viewer',
+    PRIMARY KEY ("topic_id", "account_id"),
+    FOREIGN KEY ("topic_id") REFERENCES "topics" ("id") ON DELETE CASCADE,
+    FOREIGN KEY ("account_id") REFERENCES "accounts" ("id") ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_topic_shares_account_id ON topic_shares (account_id);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_share_links_topic_id;
+
+DROP TABLE IF EXISTS share_links;
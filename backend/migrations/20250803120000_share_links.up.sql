@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS "share_links" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "created_at" DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    "topic_id" INTEGER NOT NULL,
+    "created_by" INTEGER DEFAULT NULL,
+    "name" TEXT NOT NULL,
+    "token_hash" TEXT NOT NULL UNIQUE,
+    "expires_at" DATETIME DEFAULT NULL,
+    "last_used_at" DATETIME DEFAULT NULL,
+    "revoked_at" DATETIME DEFAULT NULL,
+    FOREIGN KEY ("topic_id") REFERENCES "topics" ("id") ON DELETE CASCADE,
+    FOREIGN KEY ("created_by") REFERENCES "accounts" ("id") ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_share_links_topic_id ON share_links (topic_id);
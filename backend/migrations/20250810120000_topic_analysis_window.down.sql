@@ -0,0 +1,5 @@
+ALTER TABLE topics DROP COLUMN "last_analysis_post_count";
+
+ALTER TABLE topics DROP COLUMN "last_analysis_until";
+
+ALTER TABLE topics DROP COLUMN "last_analysis_since";
@@ -0,0 +1,5 @@
+ALTER TABLE topics ADD COLUMN "last_analysis_since" DATETIME DEFAULT NULL;
+
+ALTER TABLE topics ADD COLUMN "last_analysis_until" DATETIME DEFAULT NULL;
+
+ALTER TABLE topics ADD COLUMN "last_analysis_post_count" INTEGER DEFAULT NULL;
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_analyses_topic_id_created_at;
+
+DROP TABLE IF EXISTS analyses;
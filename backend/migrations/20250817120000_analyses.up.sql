@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS "analyses" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "created_at" DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    "topic_id" INTEGER NOT NULL,
+    "created_by" INTEGER DEFAULT NULL,
+    "since" DATETIME NOT NULL,
+    "until" DATETIME NOT NULL,
+    "model" TEXT NOT NULL,
+    "prompt_version" INTEGER NOT NULL,
+    "matched_post_count" INTEGER NOT NULL,
+    "post_count" INTEGER NOT NULL,
+    "result" TEXT NOT NULL,
+    "prompt_tokens" INTEGER NOT NULL DEFAULT 0,
+    "output_tokens" INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY ("topic_id") REFERENCES "topics" ("id") ON DELETE CASCADE,
+    FOREIGN KEY ("created_by") REFERENCES "accounts" ("id") ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_analyses_topic_id_created_at ON analyses (topic_id, created_at);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_analysis_schedules_next_run_at;
+
+DROP TABLE IF EXISTS analysis_schedules;
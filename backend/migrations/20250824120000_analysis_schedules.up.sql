@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS "analysis_schedules" (
+    "topic_id" INTEGER PRIMARY KEY NOT NULL,
+    "created_at" DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    "created_by" INTEGER DEFAULT NULL,
+    "enabled" BOOLEAN NOT NULL DEFAULT TRUE,
+    "frequency" TEXT NOT NULL,
+    "time" TEXT DEFAULT NULL,
+    "timezone" TEXT NOT NULL DEFAULT 'UTC',
+    "next_run_at" DATETIME NOT NULL,
+    "last_run_at" DATETIME DEFAULT NULL,
+    "last_status" TEXT DEFAULT NULL,
+    "last_error" TEXT DEFAULT NULL,
+    "last_analysis_id" INTEGER DEFAULT NULL,
+    FOREIGN KEY ("topic_id") REFERENCES "topics" ("id") ON DELETE CASCADE,
+    FOREIGN KEY ("created_by") REFERENCES "accounts" ("id") ON DELETE SET NULL,
+    FOREIGN KEY ("last_analysis_id") REFERENCES "analyses" ("id") ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_analysis_schedules_next_run_at ON analysis_schedules (next_run_at);
@@ -0,0 +1 @@
+ALTER TABLE "analyses" ADD COLUMN "structured_result" TEXT DEFAULT NULL;
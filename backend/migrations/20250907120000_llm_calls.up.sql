@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS "llm_calls" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "created_at" DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    "purpose" TEXT NOT NULL,
+    "model" TEXT NOT NULL,
+    "prompt_tokens" INTEGER NOT NULL DEFAULT 0,
+    "output_tokens" INTEGER NOT NULL DEFAULT 0,
+    "cost" REAL NOT NULL DEFAULT 0,
+    "latency_ms" INTEGER NOT NULL,
+    "outcome" TEXT NOT NULL,
+    "error" TEXT DEFAULT NULL,
+    "account_id" INTEGER DEFAULT NULL,
+    "topic_id" INTEGER DEFAULT NULL,
+    FOREIGN KEY ("account_id") REFERENCES "accounts" ("id") ON DELETE SET NULL,
+    FOREIGN KEY ("topic_id") REFERENCES "topics" ("id") ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_llm_calls_created_at ON llm_calls (created_at);
@@ -0,0 +1,5 @@
+ALTER TABLE "topic_post_rollups" DROP COLUMN "sentiment_sum";
+
+DELETE FROM "topic_post_rollups" WHERE "dimension" = 'sentiment';
+
+ALTER TABLE "posts" DROP COLUMN "sentiment";
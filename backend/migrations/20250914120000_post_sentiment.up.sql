@@ -0,0 +1,4 @@
+ALTER TABLE "posts" ADD COLUMN "sentiment" REAL DEFAULT NULL;
+
+-- Sum of the sentiment scores of the posts counted in `sentiment` rollups, to average them
+ALTER TABLE "topic_post_rollups" ADD COLUMN "sentiment_sum" REAL NOT NULL DEFAULT 0;
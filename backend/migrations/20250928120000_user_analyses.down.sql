@@ -0,0 +1,5 @@
+ALTER TABLE users ADD COLUMN "last_analysis" TEXT DEFAULT NULL;
+
+ALTER TABLE users ADD COLUMN "last_analysis_at" DATETIME DEFAULT NULL;
+
+DROP TABLE IF EXISTS user_analyses;
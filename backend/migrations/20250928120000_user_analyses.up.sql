@@ -0,0 +1,20 @@
+-- Users are analyzed from the topics visible to whoever asks, so their analyses are only returned
+-- to the account which requested them
+CREATE TABLE IF NOT EXISTS "user_analyses" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    "created_at" DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    "user_id" INTEGER NOT NULL,
+    "created_by" INTEGER DEFAULT NULL,
+    "matched_post_count" INTEGER NOT NULL,
+    "post_count" INTEGER NOT NULL,
+    "result" TEXT NOT NULL,
+    FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE,
+    FOREIGN KEY ("created_by") REFERENCES "accounts" ("id") ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_analyses_user_id_created_by ON user_analyses (user_id, created_by);
+
+-- Analyses stored on users were returned to everyone, whatever the topics they were made from
+ALTER TABLE users DROP COLUMN "last_analysis_at";
+
+ALTER TABLE users DROP COLUMN "last_analysis";
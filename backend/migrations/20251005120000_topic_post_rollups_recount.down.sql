@@ -0,0 +1 @@
+-- The previous counts can't be restored, the recounted ones are kept
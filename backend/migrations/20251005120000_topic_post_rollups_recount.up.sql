@@ -0,0 +1,55 @@
+-- Recount the totals and languages of existing posts from their creation time capped to now, like
+-- posts ingested from now on. Keyword and sentiment splits can't be recounted and are kept
+DELETE FROM topic_post_rollups
+WHERE
+    dimension IN ('total', 'language');
+
+WITH
+    buckets (bucket, seconds) AS (
+        VALUES ('1m', 60),
+            ('5m', 300),
+            ('1h', 3600),
+            ('1d', 86400)
+    ),
+    post_buckets AS (
+        SELECT post_topics.topic_id, posts.langs, buckets.bucket, datetime(
+                (
+                    MIN(
+                        CAST(
+                            strftime('%s', posts.created_at) AS INTEGER
+                        ), CAST(strftime('%s', 'now') AS INTEGER)
+                    ) / buckets.seconds
+                ) * buckets.seconds, 'unixepoch'
+            ) AS bucket_start
+        FROM
+            posts
+            JOIN post_topics ON posts.id = post_topics.post_id
+            CROSS JOIN buckets
+        WHERE
+            strftime('%s', posts.created_at) IS NOT NULL
+    )
+INSERT INTO
+    topic_post_rollups (
+        topic_id,
+        bucket,
+        bucket_start,
+        dimension,
+        dimension_value,
+        count
+    )
+SELECT topic_id, bucket, bucket_start, 'total', '', COUNT(*)
+FROM post_buckets
+GROUP BY
+    topic_id,
+    bucket,
+    bucket_start
+UNION ALL
+SELECT topic_id, bucket, bucket_start, 'language', lang.value, COUNT(*)
+FROM post_buckets, json_each(CAST(post_buckets.langs AS TEXT)) AS lang
+WHERE
+    bucket != '1m'
+GROUP BY
+    topic_id,
+    bucket,
+    bucket_start,
+    lang.value;
@@ -0,0 +1 @@
+-- The deleted sessions can't be restored
@@ -0,0 +1,5 @@
+-- Sessions created before CSRF tokens have none to send their mutations with, they need to log in
+-- again
+DELETE FROM sessions
+WHERE
+    csrf_token = '';
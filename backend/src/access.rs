@@ -0,0 +1,222 @@
+use axum::{extract::FromRequestParts, http::request::Parts};
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    Error, Result, db,
+    models::{
+        account::{Account, Role},
+        topic::{ShareRole, Topic, Visibility},
+    },
+    state::AppState,
+};
+
+/// What the caller can do with a topic, each level including the previous ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TopicAccess {
+    None,
+    /// Read the topic, its posts, stats and alerts
+    View,
+    /// Update and analyze the topic, and manage its alerts
+    Edit,
+    /// Delete the topic, change its visibility and who it's shared with
+    Manage,
+}
+
+impl TopicAccess {
+    pub fn resolve(
+        auth_enabled: bool,
+        account: Option<&Account>,
+        owner_id: Option<i64>,
+        visibility: Visibility,
+        share: Option<ShareRole>,
+    ) -> Self {
+        if !auth_enabled {
+            return Self::Manage;
+        }
+
+        if let Some(account) = account {
+            // Topics created before ownership existed, or whose owner was deleted, stay
+            // manageable by every editor unless they're private, which only admins and the
+            // accounts they're shared with keep access to
+            let owns = owner_id.map_or(
+                account.role >= Role::Editor && visibility != Visibility::Private,
+                |id| id == account.id,
+            );
+            if owns || account.role == Role::Admin {
+                return Self::Manage;
+            }
+        }
+
+        match (share, visibility, account) {
+            (Some(ShareRole::Editor), _, _) => Self::Edit,
+            (Some(ShareRole::Viewer), _, _) | (_, Visibility::Public, _) => Self::View,
+            (_, Visibility::Team, Some(_)) => Self::View,
+            _ => Self::None,
+        }
+    }
+}
+
+/// Topic access of the caller, from the account added to the request extensions by the auth
+/// middlewares and the topics shared with it
+pub struct TopicPermissions {
+    auth_enabled: bool,
+    account: Option<Account>,
+    shares: HashMap<i64, ShareRole>,
+}
+
+impl FromRequestParts<AppState> for TopicPermissions {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self> {
+        let account = parts.extensions.get::<Account>().cloned();
+
+        let shares = match &account {
+            Some(account) => db::get_account_topic_shares(&state.pool, account.id)
+                .await?
+                .into_iter()
+                .collect(),
+            None => HashMap::new(),
+        };
+
+        Ok(Self {
+            auth_enabled: state.config.server.auth.enabled,
+            account,
+            shares,
+        })
+    }
+}
+
+impl TopicPermissions {
+    pub fn access(
+        &self,
+        topic_id: i64,
+        owner_id: Option<i64>,
+        visibility: Visibility,
+    ) -> TopicAccess {
+        TopicAccess::resolve(
+            self.auth_enabled,
+            self.account.as_ref(),
+            owner_id,
+            visibility,
+            self.shares.get(&topic_id).copied(),
+        )
+    }
+
+    pub fn account_id(&self) -> Option<i64> {
+        self.account.as_ref().map(|account| account.id)
+    }
+
+    /// Topic with at least the `required` access, topics that can't be seen being reported as not
+    /// found to not leak their existence
+    pub async fn require_topic(
+        &self,
+        state: &AppState,
+        id: i64,
+        required: TopicAccess,
+    ) -> Result<Topic> {
+        let not_found = || Error::NotFound(format!("Topic with id {id} not found"));
+
+        if !db::topic_exists(&state.pool, id).await? {
+            return Err(not_found());
+        }
+
+        let topic = db::get_topic(&state.pool, id).await?;
+        let access = self.access(topic.id, topic.owner_id, topic.visibility);
+
+        if access == TopicAccess::None {
+            return Err(not_found());
+        }
+
+        if access < required {
+            return Err(Error::Forbidden(format!(
+                "Topic with id {id} requires {required:?} access"
+            )));
+        }
+
+        Ok(topic)
+    }
+
+    pub async fn visible_topic_ids(&self, state: &AppState) -> Result<BTreeSet<i64>> {
+        let topics = db::get_all_topics(&state.pool).await?;
+
+        Ok(topics
+            .into_iter()
+            .filter(|topic| {
+                self.access(topic.id, topic.owner_id, topic.visibility) >= TopicAccess::View
+            })
+            .map(|topic| topic.id)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    fn account(id: i64, role: Role) -> Account {
+        Account {
+            id,
+            created_at: NaiveDateTime::default(),
+            username: format!("account-{id}"),
+            role,
+            enabled: true,
+            last_login_at: None,
+        }
+    }
+
+    #[test]
+    fn test_given_private_topic_when_resolve_return_access_of_owner_and_shares_only() {
+        let owner = account(1, Role::Editor);
+        let other = account(2, Role::Editor);
+        let admin = account(3, Role::Admin);
+
+        let resolve = |account, share| {
+            TopicAccess::resolve(true, account, Some(1), Visibility::Private, share)
+        };
+
+        assert_eq!(resolve(Some(&owner), None), TopicAccess::Manage);
+        assert_eq!(resolve(Some(&admin), None), TopicAccess::Manage);
+        assert_eq!(resolve(Some(&other), None), TopicAccess::None);
+        assert_eq!(resolve(None, None), TopicAccess::None);
+        assert_eq!(
+            resolve(Some(&other), Some(ShareRole::Viewer)),
+            TopicAccess::View
+        );
+        assert_eq!(
+            resolve(Some(&other), Some(ShareRole::Editor)),
+            TopicAccess::Edit
+        );
+    }
+
+    #[test]
+    fn test_given_visibility_when_resolve_return_view_for_allowed_callers() {
+        let viewer = account(2, Role::Viewer);
+
+        let resolve =
+            |account, visibility| TopicAccess::resolve(true, account, Some(1), visibility, None);
+
+        assert_eq!(resolve(Some(&viewer), Visibility::Team), TopicAccess::View);
+        assert_eq!(resolve(None, Visibility::Team), TopicAccess::None);
+        assert_eq!(resolve(None, Visibility::Public), TopicAccess::View);
+    }
+
+    #[test]
+    fn test_given_unowned_topic_when_resolve_return_manage_for_editors_unless_private() {
+        let editor = account(2, Role::Editor);
+        let viewer = account(3, Role::Viewer);
+
+        let resolve = |account| TopicAccess::resolve(true, account, None, Visibility::Public, None);
+
+        assert_eq!(resolve(Some(&editor)), TopicAccess::Manage);
+        assert_eq!(resolve(Some(&viewer)), TopicAccess::View);
+        assert_eq!(
+            TopicAccess::resolve(true, Some(&editor), None, Visibility::Private, None),
+            TopicAccess::None
+        );
+        assert_eq!(
+            TopicAccess::resolve(false, None, Some(1), Visibility::Private, None),
+            TopicAccess::Manage
+        );
+    }
+}
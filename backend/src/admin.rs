@@ -0,0 +1,418 @@
+use backend::{
+    Error, Result, auth, config, db,
+    models::{
+        account::Role,
+        admin::{PruneResult, TopicExport},
+        api_token::{CreateApiToken, Scope},
+        topic::{CreateTopic, UpdateTopic},
+    },
+    slug::slugify,
+};
+use chrono::{TimeDelta, Utc};
+use clap::{Parser, Subcommand, ValueEnum};
+use sqlx::SqlitePool;
+use std::{
+    fs,
+    io::{self, BufRead, IsTerminal, Read, Write},
+    path::PathBuf,
+};
+
+/// Administration of a Bluflare instance, against the database of the configuration
+#[derive(Parser)]
+#[command(name = "admin")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Hash and verify passwords, read from stdin
+    Password {
+        #[command(subcommand)]
+        command: PasswordCommand,
+    },
+    /// Manage accounts
+    Accounts {
+        #[command(subcommand)]
+        command: AccountsCommand,
+    },
+    /// Issue and revoke API tokens
+    Tokens {
+        #[command(subcommand)]
+        command: TokensCommand,
+    },
+    /// Export and import topic definitions as JSON
+    Topics {
+        #[command(subcommand)]
+        command: TopicsCommand,
+    },
+    /// Run pending database migrations
+    Migrate,
+    /// Delete posts, and what only they referenced, older than a number of days
+    Prune {
+        #[arg(long)]
+        older_than_days: i64,
+    },
+    /// Print counts of what's stored in the database
+    Stats,
+}
+
+#[derive(Subcommand)]
+enum PasswordCommand {
+    /// Print the hash of the password
+    Hash,
+    /// Check that the password matches a hash
+    Verify { hash: String },
+}
+
+#[derive(Subcommand)]
+enum AccountsCommand {
+    /// List accounts
+    List,
+    /// Create an account with the password read from stdin
+    Create {
+        username: String,
+        #[arg(long, value_enum, default_value_t = RoleArg::Viewer)]
+        role: RoleArg,
+    },
+    /// Disable an account, revoking its sessions
+    Disable { username: String },
+    /// Enable a disabled account
+    Enable { username: String },
+}
+
+#[derive(Subcommand)]
+enum TokensCommand {
+    /// Issue an API token for an account, printing it once
+    Issue {
+        username: String,
+        #[arg(long)]
+        name: String,
+        #[arg(long = "scope", value_enum, required = true)]
+        scopes: Vec<ScopeArg>,
+        #[arg(long)]
+        expires_in_days: Option<i64>,
+    },
+    /// Revoke an API token of an account
+    Revoke { username: String, id: i64 },
+}
+
+#[derive(Subcommand)]
+enum TopicsCommand {
+    /// Write every topic to a file, or stdout
+    Export {
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Create the topics of a file, or stdin, skipping those whose slug already exists
+    Import {
+        #[arg(long)]
+        input: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum RoleArg {
+    Viewer,
+    Editor,
+    Admin,
+}
+
+impl From<RoleArg> for Role {
+    fn from(role: RoleArg) -> Self {
+        match role {
+            RoleArg::Viewer => Role::Viewer,
+            RoleArg::Editor => Role::Editor,
+            RoleArg::Admin => Role::Admin,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ScopeArg {
+    #[value(name = "topics:read")]
+    TopicsRead,
+    #[value(name = "topics:write")]
+    TopicsWrite,
+    #[value(name = "analysis:run")]
+    AnalysisRun,
+}
+
+impl From<ScopeArg> for Scope {
+    fn from(scope: ScopeArg) -> Self {
+        match scope {
+            ScopeArg::TopicsRead => Scope::TopicsRead,
+            ScopeArg::TopicsWrite => Scope::TopicsWrite,
+            ScopeArg::AnalysisRun => Scope::AnalysisRun,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run(Cli::parse()).await {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let command = match cli.command {
+        Command::Password { command } => return password(command),
+        command => command,
+    };
+
+    let config = config::Config::new()?;
+    let pool = db::connect(&config.database.url).await?;
+
+    match command {
+        Command::Migrate => {
+            db::run_migrations(&pool).await?;
+            println!("Migrations applied");
+            Ok(())
+        }
+        Command::Accounts { command } => accounts(&pool, command).await,
+        Command::Tokens { command } => tokens(&pool, command).await,
+        Command::Topics { command } => topics(&pool, command).await,
+        Command::Prune { older_than_days } => prune(&pool, older_than_days).await,
+        Command::Stats => {
+            let stats = db::get_database_stats(&pool, Utc::now().naive_utc()).await?;
+            println!("{}", serde_json::to_string_pretty(&stats)?);
+            Ok(())
+        }
+        Command::Password { .. } => unreachable!(),
+    }
+}
+
+fn password(command: PasswordCommand) -> Result<()> {
+    let password = read_password()?;
+
+    match command {
+        PasswordCommand::Hash => println!("{}", auth::hash_password(&password)?),
+        PasswordCommand::Verify { hash } => {
+            if !auth::verify_password(&password, &hash) {
+                return Err(Error::InvalidCredentials);
+            }
+            println!("Password matches");
+        }
+    }
+
+    Ok(())
+}
+
+async fn accounts(pool: &SqlitePool, command: AccountsCommand) -> Result<()> {
+    match command {
+        AccountsCommand::List => {
+            for account in db::get_accounts(pool).await? {
+                println!(
+                    "{}\t{}\t{:?}\t{}",
+                    account.id,
+                    account.username,
+                    account.role,
+                    if account.enabled {
+                        "enabled"
+                    } else {
+                        "disabled"
+                    }
+                );
+            }
+        }
+        AccountsCommand::Create { username, role } => {
+            if db::account_username_exists(pool, &username).await? {
+                return Err(Error::BadRequest(format!(
+                    "Account with username {username} already exists"
+                )));
+            }
+
+            let password_hash = auth::hash_password(&read_password()?)?;
+            let account = db::create_account(pool, &username, &password_hash, role.into()).await?;
+            println!(
+                "Created account {} with id {}",
+                account.username, account.id
+            );
+        }
+        AccountsCommand::Disable { username } => {
+            let id = account_id(pool, &username).await?;
+            if db::get_other_enabled_admins_count(pool, id).await? == 0
+                && db::get_account(pool, id)
+                    .await?
+                    .is_some_and(|account| account.role == Role::Admin)
+            {
+                return Err(Error::BadRequest(
+                    "At least one enabled admin account is required".to_string(),
+                ));
+            }
+
+            db::update_account(pool, id, None, None, Some(false)).await?;
+            db::delete_account_sessions(pool, id, None).await?;
+            println!("Disabled account {username}");
+        }
+        AccountsCommand::Enable { username } => {
+            let id = account_id(pool, &username).await?;
+            db::update_account(pool, id, None, None, Some(true)).await?;
+            println!("Enabled account {username}");
+        }
+    }
+
+    Ok(())
+}
+
+async fn tokens(pool: &SqlitePool, command: TokensCommand) -> Result<()> {
+    match command {
+        TokensCommand::Issue {
+            username,
+            name,
+            scopes,
+            expires_in_days,
+        } => {
+            let id = account_id(pool, &username).await?;
+            let created = auth::create_api_token(
+                pool,
+                id,
+                CreateApiToken {
+                    name,
+                    scopes: scopes.into_iter().map(Scope::from).collect(),
+                    expires_in_days,
+                },
+            )
+            .await?;
+
+            eprintln!(
+                "Issued API token {} for {username}, it won't be shown again",
+                created.api_token.id
+            );
+            println!("{}", created.token);
+        }
+        TokensCommand::Revoke { username, id } => {
+            let account_id = account_id(pool, &username).await?;
+            if !db::revoke_api_token(pool, account_id, id).await? {
+                return Err(Error::NotFound(format!(
+                    "API token with id {id} not found for {username}"
+                )));
+            }
+            println!("Revoked API token {id}");
+        }
+    }
+
+    Ok(())
+}
+
+async fn topics(pool: &SqlitePool, command: TopicsCommand) -> Result<()> {
+    match command {
+        TopicsCommand::Export { output } => {
+            let topics = db::get_all_topics(pool)
+                .await?
+                .into_iter()
+                .map(TopicExport::from)
+                .collect::<Vec<_>>();
+            let json = serde_json::to_string_pretty(&topics)?;
+
+            match output {
+                Some(path) => fs::write(&path, json)?,
+                None => println!("{json}"),
+            }
+            eprintln!("Exported {} topics", topics.len());
+        }
+        TopicsCommand::Import { input } => {
+            let json = match input {
+                Some(path) => fs::read_to_string(&path)?,
+                None => {
+                    let mut json = String::new();
+                    io::stdin().read_to_string(&mut json)?;
+                    json
+                }
+            };
+            let topics: Vec<TopicExport> = serde_json::from_str(&json)?;
+
+            let (mut imported, mut skipped) = (0, 0);
+            for topic in topics {
+                if db::get_topic_id_by_slug(pool, &slugify(&topic.subject))
+                    .await?
+                    .is_some()
+                {
+                    eprintln!("Skipping existing topic {}", topic.subject);
+                    skipped += 1;
+                    continue;
+                }
+
+                let created = db::create_topic(
+                    pool,
+                    CreateTopic {
+                        subject: topic.subject,
+                        description: topic.description,
+                        keywords: topic.keywords,
+                        visibility: topic.visibility,
+                    },
+                    None,
+                )
+                .await?;
+
+                if !topic.enabled {
+                    db::update_topic(
+                        pool,
+                        created.id,
+                        UpdateTopic {
+                            description: None,
+                            keywords: None,
+                            enabled: Some(false),
+                            visibility: None,
+                        },
+                    )
+                    .await?;
+                }
+                imported += 1;
+            }
+            println!("Imported {imported} topics, skipped {skipped}");
+        }
+    }
+
+    Ok(())
+}
+
+async fn prune(pool: &SqlitePool, older_than_days: i64) -> Result<()> {
+    if older_than_days <= 0 {
+        return Err(Error::BadRequest(
+            "The number of days must be positive".to_string(),
+        ));
+    }
+
+    let now = Utc::now().naive_utc();
+    let cutoff = now - TimeDelta::days(older_than_days);
+
+    let mut tx = pool.begin().await?;
+    let result = PruneResult {
+        posts: db::delete_posts_created_before(&mut *tx, cutoff).await?,
+        users: db::delete_orphan_users(&mut *tx).await?,
+        alert_events: db::delete_alert_events_created_before(&mut *tx, cutoff).await?,
+        sessions: db::delete_expired_sessions(&mut *tx, now).await?,
+    };
+    tx.commit().await?;
+
+    println!("{}", serde_json::to_string_pretty(&result)?);
+
+    Ok(())
+}
+
+async fn account_id(pool: &SqlitePool, username: &str) -> Result<i64> {
+    db::get_account_credentials(pool, username)
+        .await?
+        .map(|account| account.id)
+        .ok_or(Error::NotFound(format!(
+            "Account with username {username} not found"
+        )))
+}
+
+/// First line of stdin, prompting for it when stdin is a terminal
+fn read_password() -> Result<String> {
+    let stdin = io::stdin();
+    if stdin.is_terminal() {
+        eprint!("Password: ");
+        io::stderr().flush()?;
+    }
+
+    let mut password = String::new();
+    stdin.lock().read_line(&mut password)?;
+
+    Ok(password.trim_end_matches(['\r', '\n']).to_string())
+}
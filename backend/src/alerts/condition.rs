@@ -0,0 +1,235 @@
+use chrono::{NaiveDateTime, TimeDelta};
+
+use crate::models::alert::AlertCondition;
+
+/// Longest window a condition can be evaluated over, a week
+const MAX_WINDOW_MINUTES: i64 = 7 * 24 * 60;
+/// Longest trailing baseline of a spike condition, 90 days
+const MAX_BASELINE_HOURS: i64 = 90 * 24;
+
+#[derive(Debug, PartialEq)]
+pub struct Evaluation {
+    pub firing: bool,
+    pub value: f64,
+    pub baseline: Option<f64>,
+    pub message: String,
+}
+
+impl AlertCondition {
+    pub fn window_minutes(&self) -> i64 {
+        match self {
+            Self::Threshold { window_minutes, .. } | Self::Spike { window_minutes, .. } => {
+                *window_minutes
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.window_minutes() <= 0 {
+            return Err("window_minutes must be greater than 0".to_string());
+        }
+
+        if self.window_minutes() > MAX_WINDOW_MINUTES {
+            return Err(format!(
+                "window_minutes must not be greater than {MAX_WINDOW_MINUTES}"
+            ));
+        }
+
+        match self {
+            Self::Threshold { count, .. } if *count < 0 => {
+                Err("count must not be negative".to_string())
+            }
+            Self::Spike { factor, .. } if *factor <= 0.0 => {
+                Err("factor must be greater than 0".to_string())
+            }
+            Self::Spike { baseline_hours, .. } if *baseline_hours <= 0 => {
+                Err("baseline_hours must be greater than 0".to_string())
+            }
+            Self::Spike { baseline_hours, .. } if *baseline_hours > MAX_BASELINE_HOURS => Err(
+                format!("baseline_hours must not be greater than {MAX_BASELINE_HOURS}"),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    fn window_duration(&self) -> Result<TimeDelta, String> {
+        TimeDelta::try_minutes(self.window_minutes())
+            .ok_or_else(|| "window_minutes is out of range".to_string())
+    }
+
+    fn baseline_duration(&self) -> Result<Option<TimeDelta>, String> {
+        match self {
+            Self::Threshold { .. } => Ok(None),
+            Self::Spike { baseline_hours, .. } => TimeDelta::try_hours(*baseline_hours)
+                .map(Some)
+                .ok_or_else(|| "baseline_hours is out of range".to_string()),
+        }
+    }
+
+    /// How far back the counts the condition is evaluated on go, baseline included
+    pub fn lookback(&self) -> Result<TimeDelta, String> {
+        let window = self.window_duration()?;
+
+        match self.baseline_duration()? {
+            Some(baseline) => window
+                .checked_add(&baseline)
+                .ok_or_else(|| "baseline_hours is out of range".to_string()),
+            None => Ok(window),
+        }
+    }
+
+    /// Time range `[from, to)` of the posts being evaluated
+    pub fn window(&self, now: NaiveDateTime) -> Result<(NaiveDateTime, NaiveDateTime), String> {
+        let from = now
+            .checked_sub_signed(self.window_duration()?)
+            .ok_or_else(|| "window_minutes is out of range".to_string())?;
+
+        Ok((from, now))
+    }
+
+    /// Time range `[from, to)` of the posts used as baseline, right before the evaluated window
+    pub fn baseline_window(
+        &self,
+        now: NaiveDateTime,
+    ) -> Result<Option<(NaiveDateTime, NaiveDateTime)>, String> {
+        let Some(baseline) = self.baseline_duration()? else {
+            return Ok(None);
+        };
+
+        let (to, _) = self.window(now)?;
+        let from = to
+            .checked_sub_signed(baseline)
+            .ok_or_else(|| "baseline_hours is out of range".to_string())?;
+
+        Ok(Some((from, to)))
+    }
+
+    pub fn evaluate(&self, count: i64, baseline_count: Option<i64>) -> Evaluation {
+        match self {
+            Self::Threshold {
+                count: threshold,
+                window_minutes,
+            } => {
+                let firing = count > *threshold;
+                let comparison = if firing { "above" } else { "not above" };
+
+                Evaluation {
+                    firing,
+                    value: count as f64,
+                    baseline: None,
+                    message: format!(
+                        "{count} posts in the last {window_minutes} minutes, {comparison} the threshold of {threshold}"
+                    ),
+                }
+            }
+            Self::Spike {
+                factor,
+                window_minutes,
+                baseline_hours,
+                min_count,
+            } => {
+                let baseline_minutes = baseline_hours.checked_mul(60).unwrap_or(i64::MAX);
+                let expected = baseline_count.unwrap_or_default() as f64 * *window_minutes as f64
+                    / baseline_minutes as f64;
+                let firing = count >= *min_count && count as f64 >= factor * expected;
+                let comparison = if firing { "at least" } else { "less than" };
+
+                Evaluation {
+                    firing,
+                    value: count as f64,
+                    baseline: Some(expected),
+                    message: format!(
+                        "{count} posts in the last {window_minutes} minutes, {comparison} {factor}x the {expected:.1} expected from the trailing {baseline_hours}h"
+                    ),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spike(factor: f64) -> AlertCondition {
+        AlertCondition::Spike {
+            factor,
+            window_minutes: 60,
+            baseline_hours: 24,
+            min_count: 10,
+        }
+    }
+
+    #[test]
+    fn test_given_count_above_threshold_when_evaluate_return_firing() {
+        let condition = AlertCondition::Threshold {
+            count: 100,
+            window_minutes: 10,
+        };
+
+        assert!(condition.evaluate(101, None).firing);
+        assert!(!condition.evaluate(100, None).firing);
+    }
+
+    #[test]
+    fn test_given_volume_above_factor_of_baseline_when_evaluate_return_firing() {
+        // 240 posts over 24h is an expected 10 posts per hour
+        let evaluation = spike(3.0).evaluate(30, Some(240));
+
+        assert!(evaluation.firing);
+        assert_eq!(evaluation.baseline, Some(10.0));
+        assert!(!spike(3.0).evaluate(29, Some(240)).firing);
+    }
+
+    #[test]
+    fn test_given_volume_under_min_count_when_evaluate_return_not_firing() {
+        assert!(!spike(3.0).evaluate(9, Some(0)).firing);
+        assert!(spike(3.0).evaluate(10, Some(0)).firing);
+    }
+
+    #[test]
+    fn test_given_spike_condition_when_baseline_window_return_window_before_evaluated_window() {
+        let now =
+            NaiveDateTime::parse_from_str("2025-06-02 12:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let (from, to) = spike(3.0).baseline_window(now).unwrap().unwrap();
+
+        assert_eq!(to, now - TimeDelta::hours(1));
+        assert_eq!(from, now - TimeDelta::hours(25));
+    }
+
+    #[test]
+    fn test_given_invalid_values_when_validate_return_error() {
+        assert!(spike(0.0).validate().is_err());
+        assert!(
+            AlertCondition::Threshold {
+                count: 10,
+                window_minutes: 0
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(spike(3.0).validate().is_ok());
+    }
+
+    #[test]
+    fn test_given_out_of_range_values_when_validate_return_error() {
+        let condition = AlertCondition::Spike {
+            factor: 3.0,
+            window_minutes: 60,
+            baseline_hours: i64::MAX,
+            min_count: 10,
+        };
+
+        assert!(condition.validate().is_err());
+        assert!(condition.lookback().is_err());
+        assert!(condition.baseline_window(NaiveDateTime::default()).is_err());
+        assert!(
+            AlertCondition::Threshold {
+                count: 10,
+                window_minutes: i64::MAX
+            }
+            .validate()
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,241 @@
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use sqlx::SqlitePool;
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::{error, info};
+
+use crate::{
+    Error, Result, config, db,
+    models::alert::{AlertCondition, AlertRule, AlertState, CreateAlertEvent, NotificationChannel},
+    state::AppState,
+};
+
+mod condition;
+mod notifier;
+mod webhook;
+
+use notifier::AlertNotification;
+
+#[derive(Clone)]
+pub struct AlertEngine {
+    pool: SqlitePool,
+    notifier: notifier::Notifier,
+    config: config::Alerts,
+    /// How long the per minute counts rules are evaluated on are kept, `None` when forever
+    count_retention: Option<TimeDelta>,
+    last_evaluations: Arc<Mutex<HashMap<i64, Instant>>>,
+    evaluation_lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl AlertEngine {
+    pub fn new(
+        config: &config::Alerts,
+        count_retention: Option<TimeDelta>,
+        pool: SqlitePool,
+    ) -> Result<Self> {
+        Ok(Self {
+            pool,
+            notifier: notifier::Notifier::new(config)?,
+            config: config.clone(),
+            count_retention,
+            last_evaluations: Arc::new(Mutex::new(HashMap::new())),
+            evaluation_lock: Arc::new(tokio::sync::Mutex::new(())),
+        })
+    }
+
+    pub async fn validate(
+        &self,
+        condition: Option<&AlertCondition>,
+        channels: Option<&[NotificationChannel]>,
+    ) -> Result<()> {
+        if let Some(condition) = condition {
+            condition.validate().map_err(Error::BadRequest)?;
+
+            let lookback = condition.lookback().map_err(Error::BadRequest)?;
+            match self.count_retention {
+                Some(retention) if lookback > retention => {
+                    return Err(Error::BadRequest(format!(
+                        "The window and baseline of the condition can't span more than the {} days posts are counted per minute",
+                        retention.num_days()
+                    )));
+                }
+                _ => {}
+            }
+        }
+
+        for channel in channels.unwrap_or_default() {
+            match channel {
+                NotificationChannel::Webhook { url } => {
+                    self.notifier
+                        .webhook_policy()
+                        .check(url)
+                        .await
+                        .map_err(Error::BadRequest)?;
+                }
+                NotificationChannel::Email { to } => {
+                    if !self.notifier.email_enabled() {
+                        return Err(Error::BadRequest(
+                            "Email notifications are disabled".to_string(),
+                        ));
+                    }
+
+                    for recipient in to {
+                        recipient
+                            .parse::<lettre::Address>()
+                            .map_err(|e| Error::BadRequest(format!("Invalid email: {e}")))?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Called from the ingest path, evaluates the rules of the topics which haven't been evaluated
+    /// in the last `min_evaluation_interval_seconds`
+    pub fn on_posts_ingested(&self, topic_ids: &BTreeSet<i64>) {
+        if !self.config.enabled {
+            return;
+        }
+
+        let min_interval = Duration::from_secs(self.config.min_evaluation_interval_seconds);
+        let due_topic_ids = {
+            let mut last_evaluations = self.last_evaluations.lock().unwrap();
+            topic_ids
+                .iter()
+                .filter(|topic_id| {
+                    let due = last_evaluations
+                        .get(topic_id)
+                        .is_none_or(|at| at.elapsed() >= min_interval);
+                    if due {
+                        last_evaluations.insert(**topic_id, Instant::now());
+                    }
+                    due
+                })
+                .copied()
+                .collect::<BTreeSet<i64>>()
+        };
+
+        if due_topic_ids.is_empty() {
+            return;
+        }
+
+        let engine = self.clone();
+        tokio::spawn(async move {
+            if let Err(err) = engine.evaluate(Some(&due_topic_ids)).await {
+                error!("Error evaluating alert rules for topics {due_topic_ids:?}: {err}");
+            }
+        });
+    }
+
+    pub async fn evaluate(&self, topic_ids: Option<&BTreeSet<i64>>) -> Result<()> {
+        let _guard = self.evaluation_lock.lock().await;
+
+        let rules = db::get_enabled_alert_rules(&self.pool)
+            .await?
+            .into_iter()
+            .filter(|rule| topic_ids.is_none_or(|ids| ids.contains(&rule.topic_id)));
+
+        let now = Utc::now().naive_utc();
+
+        for rule in rules {
+            let rule_id = rule.id;
+            if let Err(err) = self.evaluate_rule(rule, now).await {
+                error!("Error evaluating alert rule {rule_id}: {err}");
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn evaluate_rule(&self, rule: AlertRule, now: NaiveDateTime) -> Result<()> {
+        let (from, to) = rule.condition.window(now).map_err(Error::BadRequest)?;
+        let count = db::count_topic_posts_between(&self.pool, rule.topic_id, from, to).await?;
+
+        let baseline_count = match rule
+            .condition
+            .baseline_window(now)
+            .map_err(Error::BadRequest)?
+        {
+            Some((from, to)) => {
+                Some(db::count_topic_posts_between(&self.pool, rule.topic_id, from, to).await?)
+            }
+            None => None,
+        };
+
+        let evaluation = rule.condition.evaluate(count, baseline_count);
+        let state = if evaluation.firing {
+            AlertState::Firing
+        } else {
+            AlertState::Resolved
+        };
+
+        db::update_alert_rule_evaluation(&self.pool, rule.id, state, now).await?;
+
+        if state == rule.state {
+            return Ok(());
+        }
+
+        info!(
+            "Alert rule {} ({}) is now {state:?}: {}",
+            rule.id, rule.name, evaluation.message
+        );
+
+        let event = db::create_alert_event(
+            &self.pool,
+            CreateAlertEvent {
+                rule_id: rule.id,
+                state,
+                value: evaluation.value,
+                baseline: evaluation.baseline,
+                message: evaluation.message,
+            },
+        )
+        .await?;
+
+        let topic = db::get_topic(&self.pool, rule.topic_id).await?;
+        let notification = AlertNotification {
+            rule_id: rule.id,
+            rule_name: rule.name,
+            topic_id: topic.id,
+            topic_slug: topic.slug,
+            topic_subject: topic.subject,
+            state,
+            value: event.value,
+            baseline: event.baseline,
+            message: event.message,
+            triggered_at: event.created_at,
+        };
+
+        let notifier = self.notifier.clone();
+        tokio::spawn(async move {
+            notifier.notify(&rule.channels, &notification).await;
+        });
+
+        Ok(())
+    }
+}
+
+pub fn start_evaluator(state: AppState) {
+    if !state.config.alerts.enabled {
+        info!("Alerts are disabled, won't evaluate alert rules");
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(
+            state.config.alerts.evaluation_interval_seconds,
+        ));
+
+        loop {
+            interval.tick().await;
+
+            if let Err(err) = state.alerts.evaluate(None).await {
+                error!("Error evaluating alert rules: {err}");
+            }
+        }
+    });
+}
@@ -0,0 +1,276 @@
+use chrono::NaiveDateTime;
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor, message::Mailbox,
+    transport::smtp::authentication::Credentials,
+};
+use reqwest::{Client, redirect};
+use serde::Serialize;
+use std::{sync::Arc, time::Duration};
+use tracing::{error, info};
+
+use crate::{
+    Error, Result, config,
+    models::alert::{AlertState, NotificationChannel},
+};
+
+use super::webhook::WebhookPolicy;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AlertNotification {
+    pub rule_id: i64,
+    pub rule_name: String,
+    pub topic_id: i64,
+    pub topic_slug: String,
+    pub topic_subject: String,
+    pub state: AlertState,
+    pub value: f64,
+    pub baseline: Option<f64>,
+    pub message: String,
+    pub triggered_at: NaiveDateTime,
+}
+
+impl AlertNotification {
+    fn subject(&self) -> String {
+        let state = match self.state {
+            AlertState::Firing => "FIRING",
+            AlertState::Resolved => "RESOLVED",
+        };
+
+        format!("[{state}] {} - {}", self.topic_subject, self.rule_name)
+    }
+}
+
+#[derive(Clone)]
+struct Smtp {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+#[derive(Clone)]
+pub struct Notifier {
+    webhook_client: Client,
+    webhook_policy: WebhookPolicy,
+    smtp: Option<Smtp>,
+}
+
+impl Notifier {
+    pub fn new(config: &config::Alerts) -> Result<Self> {
+        let webhook_policy = WebhookPolicy::new(&config.webhook);
+        let webhook_client = Client::builder()
+            .user_agent(&config.webhook.user_agent)
+            .timeout(Duration::from_secs(config.webhook.timeout_seconds))
+            .redirect(redirect::Policy::none())
+            .dns_resolver(Arc::new(webhook_policy.clone()))
+            .build()?;
+
+        let smtp = if config.smtp.enabled {
+            info!("Initializing SMTP client");
+            Some(Smtp {
+                transport: smtp_transport(&config.smtp)?,
+                from: config.smtp.from.parse()?,
+            })
+        } else {
+            info!("SMTP client disabled, email notifications will not be sent");
+            None
+        };
+
+        Ok(Self {
+            webhook_client,
+            webhook_policy,
+            smtp,
+        })
+    }
+
+    pub fn email_enabled(&self) -> bool {
+        self.smtp.is_some()
+    }
+
+    pub fn webhook_policy(&self) -> &WebhookPolicy {
+        &self.webhook_policy
+    }
+
+    pub async fn notify(&self, channels: &[NotificationChannel], notification: &AlertNotification) {
+        for channel in channels {
+            let result = match channel {
+                NotificationChannel::Webhook { url } => self.send_webhook(url, notification).await,
+                NotificationChannel::Email { to } => self.send_email(to, notification).await,
+            };
+
+            if let Err(err) = result {
+                error!(
+                    "Error sending notification for alert rule {}: {err}",
+                    notification.rule_id
+                );
+            }
+        }
+    }
+
+    async fn send_webhook(&self, url: &str, notification: &AlertNotification) -> Result<()> {
+        let url = self
+            .webhook_policy
+            .check(url)
+            .await
+            .map_err(Error::BadRequest)?;
+
+        self.webhook_client
+            .post(url)
+            .json(notification)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    async fn send_email(&self, to: &[String], notification: &AlertNotification) -> Result<()> {
+        let Some(smtp) = &self.smtp else {
+            return Err(Error::SmtpDisabled);
+        };
+
+        let mut builder = Message::builder()
+            .from(smtp.from.clone())
+            .subject(notification.subject());
+
+        for recipient in to {
+            builder = builder.to(recipient.parse()?);
+        }
+
+        let email = builder.body(format!(
+            "{}\n\nTopic: {} ({})\nRule: {}\nState: {:?}\nAt: {} UTC\n",
+            notification.message,
+            notification.topic_subject,
+            notification.topic_slug,
+            notification.rule_name,
+            notification.state,
+            notification.triggered_at,
+        ))?;
+
+        smtp.transport.send(email).await?;
+
+        Ok(())
+    }
+}
+
+fn smtp_transport(config: &config::Smtp) -> Result<AsyncSmtpTransport<Tokio1Executor>> {
+    let builder = if config.tls {
+        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?
+    } else {
+        AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)
+    };
+
+    let builder = builder
+        .port(config.port)
+        .timeout(Some(Duration::from_secs(config.timeout_seconds)));
+
+    let builder = match (&config.username, &config.password) {
+        (Some(username), Some(password)) => {
+            builder.credentials(Credentials::new(username.clone(), password.clone()))
+        }
+        _ => builder,
+    };
+
+    Ok(builder.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    /// Minimal SMTP server accepting a single message and returning its DATA section
+    async fn smtp_stand_in(listener: TcpListener) -> String {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        let mut data = String::new();
+        let mut in_data = false;
+
+        writer.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+
+        while let Some(line) = lines.next_line().await.unwrap() {
+            if in_data {
+                if line == "." {
+                    in_data = false;
+                    writer.write_all(b"250 OK\r\n").await.unwrap();
+                } else {
+                    data.push_str(&line);
+                    data.push('\n');
+                }
+                continue;
+            }
+
+            let reply: &[u8] = match line.get(..4).unwrap_or_default() {
+                "EHLO" => b"250 localhost\r\n",
+                "DATA" => {
+                    in_data = true;
+                    b"354 End data with <CR><LF>.<CR><LF>\r\n"
+                }
+                "QUIT" => {
+                    writer.write_all(b"221 Bye\r\n").await.unwrap();
+                    break;
+                }
+                _ => b"250 OK\r\n",
+            };
+            writer.write_all(reply).await.unwrap();
+        }
+
+        data
+    }
+
+    fn alerts_config(port: u16) -> config::Alerts {
+        config::Alerts {
+            enabled: true,
+            evaluation_interval_seconds: 60,
+            min_evaluation_interval_seconds: 10,
+            webhook: config::Webhook {
+                timeout_seconds: 5,
+                user_agent: "test".to_string(),
+                allowed_hosts: vec![],
+            },
+            smtp: config::Smtp {
+                enabled: true,
+                host: "127.0.0.1".to_string(),
+                port,
+                tls: false,
+                from: "Bluflare <bluflare@localhost>".to_string(),
+                username: None,
+                password: None,
+                timeout_seconds: 5,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_given_email_channel_when_notify_send_email_to_smtp_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(smtp_stand_in(listener));
+
+        let notifier = Notifier::new(&alerts_config(port)).unwrap();
+        let notification = AlertNotification {
+            rule_id: 1,
+            rule_name: "Too many posts".to_string(),
+            topic_id: 1,
+            topic_slug: "ai".to_string(),
+            topic_subject: "AI".to_string(),
+            state: AlertState::Firing,
+            value: 120.0,
+            baseline: None,
+            message: "120 posts in the last 10 minutes".to_string(),
+            triggered_at: NaiveDateTime::default(),
+        };
+
+        notifier
+            .send_email(&["ops@example.com".to_string()], &notification)
+            .await
+            .unwrap();
+
+        let data = server.await.unwrap();
+        assert!(data.contains("Subject: [FIRING] AI - Too many posts"));
+        assert!(data.contains("To: ops@example.com"));
+        assert!(data.contains("120 posts in the last 10 minutes"));
+    }
+}
@@ -0,0 +1,178 @@
+use reqwest::{
+    Url,
+    dns::{Addrs, Name, Resolve, Resolving},
+};
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use crate::config;
+
+/// Restricts webhooks to public HTTP(S) endpoints, or to the hosts allowed in the config, so alert
+/// rules can't be used to reach the network of the backend
+#[derive(Clone)]
+pub struct WebhookPolicy {
+    allowed_hosts: Arc<Vec<String>>,
+}
+
+impl WebhookPolicy {
+    pub fn new(config: &config::Webhook) -> Self {
+        Self {
+            allowed_hosts: Arc::new(config.allowed_hosts.clone()),
+        }
+    }
+
+    fn is_allowed_host(&self, host: &str) -> bool {
+        self.allowed_hosts
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(host))
+    }
+
+    /// Parsed URL, an error when it isn't HTTP(S) or its host isn't allowed and doesn't only
+    /// resolve to public addresses
+    pub async fn check(&self, url: &str) -> Result<Url, String> {
+        let url = Url::parse(url).map_err(|e| format!("Invalid webhook URL: {e}"))?;
+
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("Webhook URL must use http or https".to_string());
+        }
+
+        let Some(host) = url.host_str() else {
+            return Err("Webhook URL must have a host".to_string());
+        };
+
+        if self.is_allowed_host(host) {
+            return Ok(url);
+        }
+
+        match host.trim_start_matches('[').trim_end_matches(']').parse() {
+            Ok(ip) if !is_public(ip) => {
+                return Err(format!(
+                    "Webhook URL can't target the non-public address {ip}"
+                ));
+            }
+            Ok(_) => {}
+            Err(_) => {
+                self.lookup(host).await?;
+            }
+        }
+
+        Ok(url)
+    }
+
+    /// Addresses of the host, an error when any of them isn't public unless the host is allowed
+    async fn lookup(&self, host: &str) -> Result<Vec<SocketAddr>, String> {
+        let addrs = tokio::net::lookup_host((host, 0))
+            .await
+            .map_err(|e| format!("Can't resolve webhook host {host}: {e}"))?
+            .collect::<Vec<_>>();
+
+        if self.is_allowed_host(host) {
+            return Ok(addrs);
+        }
+
+        if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+            return Err(format!(
+                "Webhook host {host} resolves to the non-public address {}",
+                addr.ip()
+            ));
+        }
+
+        Ok(addrs)
+    }
+}
+
+/// Resolves hosts again when sending, so a host can't be pointed to a private address after its
+/// rule was validated
+impl Resolve for WebhookPolicy {
+    fn resolve(&self, name: Name) -> Resolving {
+        let policy = self.clone();
+
+        Box::pin(async move {
+            let addrs = policy.lookup(name.as_str()).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || first == 0
+                // Shared address space of carrier-grade NATs, 100.64.0.0/10
+                || (first == 100 && second & 0xc0 == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+
+                !(ip.is_unspecified()
+                    || ip.is_loopback()
+                    || ip.is_multicast()
+                    // Unique local, fc00::/7
+                    || first & 0xfe00 == 0xfc00
+                    // Link-local, fe80::/10
+                    || first & 0xffc0 == 0xfe80)
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(allowed_hosts: &[&str]) -> WebhookPolicy {
+        WebhookPolicy::new(&config::Webhook {
+            timeout_seconds: 5,
+            user_agent: "test".to_string(),
+            allowed_hosts: allowed_hosts.iter().map(|host| host.to_string()).collect(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_given_non_public_or_non_http_url_when_check_return_error() {
+        let policy = policy(&[]);
+
+        for url in [
+            "file:///etc/passwd",
+            "gopher://example.com",
+            "http://127.0.0.1:3000/api",
+            "http://169.254.169.254/latest/meta-data",
+            "http://10.0.0.1/hook",
+            "http://192.168.1.1/hook",
+            "http://100.64.0.1/hook",
+            "http://[::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+            "http://[fd00::1]/hook",
+            "http://localhost:3000/hook",
+        ] {
+            assert!(policy.check(url).await.is_err(), "{url}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_given_public_or_allowed_url_when_check_return_url() {
+        let policy = policy(&["localhost", "10.0.0.1"]);
+
+        for url in [
+            "https://93.184.215.14/hook",
+            "http://[2606:4700::1111]/hook",
+            "http://localhost:3000/hook",
+            "http://10.0.0.1/hook",
+        ] {
+            assert!(policy.check(url).await.is_ok(), "{url}");
+        }
+    }
+}
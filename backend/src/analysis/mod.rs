@@ -0,0 +1,182 @@
+use chrono::NaiveDateTime;
+use std::collections::{BTreeSet, HashSet};
+use tracing::warn;
+
+use crate::{
+    Error, Result, db,
+    gemini::{ANALYSIS_PROMPT_VERSION, analysis::PostSelection},
+    models::{
+        analysis::{Analysis, AnalysisResult, CreateAnalysis},
+        topic::{Topic, UpdateTopicAnalysis},
+        usage::{LlmPurpose, UsageContext},
+        user::{User, UserAnalysis},
+    },
+    state::AppState,
+};
+
+mod scheduler;
+
+pub use scheduler::start_scheduler;
+
+/// Analyzes the posts of the topic published in `[since, until)`, storing the analysis and making
+/// it the latest of the topic
+pub async fn analyze_topic(
+    state: &AppState,
+    topic: &Topic,
+    (since, until): (NaiveDateTime, NaiveDateTime),
+    created_by: Option<i64>,
+) -> Result<Analysis> {
+    let config = state.gemini.analysis_config();
+
+    let posts = db::get_topic_analysis_posts(&state.pool, topic.id, since, until).await?;
+    if posts.is_empty() {
+        return Err(Error::BadRequest(format!(
+            "No posts were published between {since} and {until}"
+        )));
+    }
+
+    let selection = PostSelection::new(posts, config.max_posts, config.max_input_tokens);
+    let post_count = selection.posts.len();
+
+    let Some(generation) = state
+        .gemini
+        .analyze_posts(
+            UsageContext {
+                purpose: LlmPurpose::Analysis,
+                account_id: created_by,
+                topic_id: Some(topic.id),
+            },
+            &topic.subject,
+            (since, until),
+            selection.posts,
+        )
+        .await?
+    else {
+        return Err(Error::GeminiDisabled);
+    };
+
+    let post_ids = selection.post_ids.into_iter().collect::<HashSet<_>>();
+    let (result, structured_result) = match AnalysisResult::parse(&generation.text, &post_ids) {
+        Ok(structured_result) => (structured_result.summary.clone(), Some(structured_result)),
+        Err(e) => {
+            warn!(
+                "Unable to parse the analysis of topic {}, keeping its text: {e}",
+                topic.id
+            );
+            (generation.text, None)
+        }
+    };
+
+    let mut tx = state.pool.begin().await?;
+
+    let analysis = db::create_analysis(
+        &mut *tx,
+        CreateAnalysis {
+            topic_id: topic.id,
+            created_by,
+            since,
+            until,
+            model: state.gemini.model().to_string(),
+            prompt_version: ANALYSIS_PROMPT_VERSION,
+            matched_post_count: selection.matched as i64,
+            post_count: post_count as i64,
+            result,
+            structured_result,
+            usage: generation.usage,
+        },
+    )
+    .await?;
+
+    db::update_topic_analysis(
+        &mut *tx,
+        topic.id,
+        UpdateTopicAnalysis {
+            last_analysis: analysis.result.clone(),
+            last_analysis_at: analysis.created_at,
+            last_analysis_since: since,
+            last_analysis_until: until,
+            last_analysis_post_count: analysis.post_count,
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(analysis)
+}
+
+/// Describes the user from their posts in the given topics, storing it as the latest analysis of
+/// the user requested by the account, only ever returned to it
+pub async fn analyze_user(
+    state: &AppState,
+    user: &User,
+    topic_ids: &BTreeSet<i64>,
+    created_by: Option<i64>,
+) -> Result<UserAnalysis> {
+    let config = state.gemini.analysis_config();
+
+    let posts = db::get_user_analysis_posts(&state.pool, user.id, topic_ids).await?;
+    if posts.is_empty() {
+        return Err(Error::BadRequest(format!(
+            "{} has no posts to analyze",
+            user.did
+        )));
+    }
+
+    // Unlike topics, users are analyzed in a single request
+    let selection = PostSelection::new(
+        posts,
+        config.max_posts,
+        config.max_input_tokens.min(config.chunk_tokens),
+    );
+    let topics = db::get_user_topics(&state.pool, user.id, topic_ids).await?;
+    let topic_post_counts = topics
+        .iter()
+        .filter(|topic| topic.post_count > 0)
+        .map(|topic| (topic.subject.clone(), topic.post_count))
+        .collect::<Vec<_>>();
+    let handle = user
+        .aka
+        .first()
+        .map(|aka| format!("@{}", aka.trim_start_matches("at://")))
+        .unwrap_or_else(|| user.did.clone());
+
+    let Some(generation) = state
+        .gemini
+        .analyze_user(
+            UsageContext {
+                purpose: LlmPurpose::UserAnalysis,
+                account_id: created_by,
+                topic_id: None,
+            },
+            &handle,
+            &topic_post_counts,
+            selection.posts,
+        )
+        .await?
+    else {
+        return Err(Error::GeminiDisabled);
+    };
+
+    let mut tx = state.pool.begin().await?;
+    db::delete_user_analyses(&mut *tx, user.id, created_by).await?;
+    let analysis = db::create_user_analysis(
+        &mut *tx,
+        user.id,
+        created_by,
+        &generation.text,
+        selection.matched as i64,
+        selection.post_ids.len() as i64,
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(UserAnalysis {
+        did: user.did.clone(),
+        analysis: analysis.analysis,
+        analyzed_at: analysis.analyzed_at,
+        matched_post_count: analysis.matched_post_count,
+        post_count: analysis.post_count,
+        topics,
+    })
+}
@@ -0,0 +1,119 @@
+use chrono::Utc;
+use chrono_tz::Tz;
+use futures_util::{StreamExt, stream};
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::{
+    Result, db,
+    models::schedule::{AnalysisSchedule, ScheduleStatus, next_run_at},
+    state::AppState,
+};
+
+pub fn start_scheduler(state: AppState) {
+    if !state.config.schedules.enabled {
+        info!("Analysis schedules are disabled, won't run scheduled analyses");
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(
+            state.config.schedules.poll_interval_seconds,
+        ));
+
+        loop {
+            interval.tick().await;
+
+            if let Err(err) = run_due_schedules(&state).await {
+                error!("Error running scheduled analyses: {err}");
+            }
+        }
+    });
+}
+
+/// Runs the due schedules, a few at a time, the next tick waiting for all of them to finish
+async fn run_due_schedules(state: &AppState) -> Result<()> {
+    let now = Utc::now().naive_utc();
+    let schedules = db::get_due_analysis_schedules(&state.pool, now).await?;
+
+    stream::iter(schedules)
+        .for_each_concurrent(state.config.schedules.concurrency.max(1), |schedule| {
+            run_schedule(state, schedule)
+        })
+        .await;
+
+    Ok(())
+}
+
+async fn run_schedule(state: &AppState, schedule: AnalysisSchedule) {
+    let topic_id = schedule.topic_id;
+
+    if let Err(err) = try_run_schedule(state, schedule).await {
+        error!("Error running scheduled analysis of topic {topic_id}: {err}");
+
+        if let Err(err) = db::finish_analysis_schedule_run(
+            &state.pool,
+            topic_id,
+            ScheduleStatus::Failed,
+            Some(err.to_string()),
+            None,
+        )
+        .await
+        {
+            error!("Error updating analysis schedule of topic {topic_id}: {err}");
+        }
+    }
+}
+
+/// Analyzes the period which ended at the scheduled time, skipping it when no post was published
+async fn try_run_schedule(state: &AppState, schedule: AnalysisSchedule) -> Result<()> {
+    let now = Utc::now().naive_utc();
+    let timezone = schedule.timezone.parse::<Tz>().unwrap_or(Tz::UTC);
+    let next = next_run_at(schedule.frequency, schedule.time, timezone, now);
+
+    db::start_analysis_schedule_run(&state.pool, schedule.topic_id, now, next).await?;
+
+    // Runs missed for longer than a period, while the server was down, analyze the last period
+    // instead of one which already ended long ago
+    let period = schedule.frequency.period();
+    let until = if now - schedule.next_run_at >= period {
+        now
+    } else {
+        schedule.next_run_at
+    };
+    let since = until - period;
+
+    if db::count_topic_posts_published_between(&state.pool, schedule.topic_id, since, until).await?
+        == 0
+    {
+        info!(
+            "Skipping scheduled analysis of topic {}, no posts since {since}",
+            schedule.topic_id
+        );
+        return db::finish_analysis_schedule_run(
+            &state.pool,
+            schedule.topic_id,
+            ScheduleStatus::Skipped,
+            None,
+            None,
+        )
+        .await;
+    }
+
+    let topic = db::get_topic(&state.pool, schedule.topic_id).await?;
+    let analysis = super::analyze_topic(state, &topic, (since, until), schedule.created_by).await?;
+
+    info!(
+        "Ran scheduled analysis {} of topic {}",
+        analysis.id, schedule.topic_id
+    );
+
+    db::finish_analysis_schedule_run(
+        &state.pool,
+        schedule.topic_id,
+        ScheduleStatus::Succeeded,
+        None,
+        Some(analysis.id),
+    )
+    .await
+}
@@ -0,0 +1,163 @@
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header::USER_AGENT, request::Parts},
+};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::{convert::Infallible, net::SocketAddr};
+use tracing::error;
+
+use crate::{
+    Result, db,
+    models::{
+        account::Account,
+        api_token::ApiToken,
+        audit::{AuditAction, CreateAuditEntry},
+    },
+};
+
+/// Who made the request, taken from what the auth middleware attached to it and the connection
+#[derive(Debug, Clone, Default)]
+pub struct Actor {
+    pub account_id: Option<i64>,
+    pub username: Option<String>,
+    pub api_token_id: Option<i64>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl Actor {
+    /// Used when the request isn't authenticated yet, such as a login attempt
+    pub fn with_account(mut self, account_id: Option<i64>, username: &str) -> Self {
+        self.account_id = account_id;
+        self.username = Some(username.to_string());
+        self
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Actor {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        let account = parts.extensions.get::<Account>();
+
+        Ok(Self {
+            account_id: account.map(|account| account.id),
+            username: account.map(|account| account.username.clone()),
+            api_token_id: parts
+                .extensions
+                .get::<ApiToken>()
+                .map(|api_token| api_token.id),
+            ip: parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip().to_string()),
+            user_agent: parts
+                .headers
+                .get(USER_AGENT)
+                .and_then(|user_agent| user_agent.to_str().ok())
+                .map(str::to_string),
+        })
+    }
+}
+
+/// Appends an entry to the audit log, only keeping the fields that changed between `before` and
+/// `after`. Failures are logged rather than failing a change that already happened.
+pub async fn record(
+    pool: &SqlitePool,
+    actor: &Actor,
+    action: AuditAction,
+    target_id: Option<i64>,
+    before: Option<Value>,
+    after: Option<Value>,
+) {
+    if let Err(e) = try_record(pool, actor, action, target_id, before, after).await {
+        error!("Error recording {action:?} audit entry: {e:?}");
+    }
+}
+
+async fn try_record(
+    pool: &SqlitePool,
+    actor: &Actor,
+    action: AuditAction,
+    target_id: Option<i64>,
+    before: Option<Value>,
+    after: Option<Value>,
+) -> Result<()> {
+    let (before, after) = diff(before, after);
+
+    db::create_audit_entry(
+        pool,
+        CreateAuditEntry {
+            actor_account_id: actor.account_id,
+            actor_username: actor.username.clone(),
+            actor_api_token_id: actor.api_token_id,
+            action,
+            target_id,
+            before,
+            after,
+            ip: actor.ip.clone(),
+            user_agent: actor.user_agent.clone(),
+        },
+    )
+    .await
+}
+
+/// JSON snapshot of an entity to pass as the `before` or `after` of an entry
+pub fn snapshot(value: &impl Serialize) -> Option<Value> {
+    serde_json::to_value(value).ok()
+}
+
+/// Strips the fields that are equal in both objects, values that aren't both objects are kept as is
+fn diff(before: Option<Value>, after: Option<Value>) -> (Option<Value>, Option<Value>) {
+    let (Some(Value::Object(mut before)), Some(Value::Object(mut after))) =
+        (before.clone(), after.clone())
+    else {
+        return (before, after);
+    };
+
+    let unchanged = before
+        .iter()
+        .filter(|(key, value)| after.get(*key) == Some(value))
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+
+    for key in unchanged {
+        before.remove(&key);
+        after.remove(&key);
+    }
+
+    (Some(Value::Object(before)), Some(Value::Object(after)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_given_changed_fields_when_diff_return_only_changed_fields() {
+        let before = json!({ "id": 1, "subject": "Rust", "enabled": true, "keywords": ["rust"] });
+        let after =
+            json!({ "id": 1, "subject": "Rust", "enabled": false, "keywords": ["rust", "cargo"] });
+
+        assert_eq!(
+            diff(Some(before), Some(after)),
+            (
+                Some(json!({ "enabled": true, "keywords": ["rust"] })),
+                Some(json!({ "enabled": false, "keywords": ["rust", "cargo"] }))
+            )
+        );
+    }
+
+    #[test]
+    fn test_given_created_entity_when_diff_return_full_after() {
+        let after = json!({ "id": 1, "subject": "Rust" });
+
+        assert_eq!(diff(None, Some(after.clone())), (None, Some(after)));
+    }
+}
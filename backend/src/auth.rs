@@ -1,55 +1,493 @@
+use argon2::{
+    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+    password_hash::{Encoding, SaltString},
+};
 use axum::{
     extract::{ConnectInfo, Request, State},
+    http::header,
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
+};
+use axum_extra::{
+    TypedHeader,
+    extract::{
+        CookieJar,
+        cookie::{Cookie, SameSite},
+    },
+    headers::{Authorization, UserAgent, authorization::Bearer},
 };
-use axum_extra::{TypedHeader, extract::CookieJar, headers::UserAgent};
-use serde::{Deserialize, Serialize};
-use std::net::SocketAddr;
-use time::OffsetDateTime;
+use chrono::{TimeDelta, Utc};
+use rand_core::{OsRng, RngCore};
+use reqwest::Url;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use std::{net::SocketAddr, sync::LazyLock};
+use time::{Duration, OffsetDateTime};
+use tracing::{error, info, warn};
+
+use crate::{
+    Error, Result, config, db,
+    models::{
+        account::{Account, Role},
+        api_token::{ApiToken, CreateApiToken, CreatedApiToken, Scope},
+        session::{CreateSession, Session},
+    },
+    state::AppState,
+};
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+const SESSION_COOKIE: &str = "session_id";
+const CSRF_COOKIE: &str = "csrf_token";
+pub const CSRF_HEADER: &str = "x-csrf-token";
+const SESSION_REFRESH_INTERVAL_SECONDS: i64 = 60;
+const API_TOKEN_PREFIX: &str = "bf_";
+
+/// Minimum role of the account, and scope of API tokens, required by a route. Routes without a
+/// scope can't be accessed with API tokens.
+#[derive(Clone, Copy)]
+pub struct Requirement {
+    pub role: Role,
+    pub scope: Option<Scope>,
+}
+
+pub fn hash_password(password: &str) -> Result<String> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(Error::BadRequest(format!(
+            "Password must be at least {MIN_PASSWORD_LENGTH} characters long"
+        )));
+    }
+
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default().hash_password(password.as_bytes(), &salt)?;
+
+    Ok(hash.to_string())
+}
+
+/// Verified instead of the hash of an account which can't log in, so that failing takes as long
+/// whether the username exists or not
+static DUMMY_PASSWORD_HASH: LazyLock<String> = LazyLock::new(|| {
+    let salt = SaltString::generate(&mut OsRng);
+
+    Argon2::default()
+        .hash_password(b"dummy password", &salt)
+        .expect("Hashing the dummy password can't fail")
+        .to_string()
+});
+
+/// Same as [`verify_password`] for the account found by username, `None` when there is none or
+/// it's disabled
+pub fn verify_login(password: &str, password_hash: Option<&str>) -> bool {
+    match password_hash.filter(|password_hash| PasswordHash::new(password_hash).is_ok()) {
+        Some(password_hash) => verify_password(password, password_hash),
+        None => {
+            verify_password(password, &DUMMY_PASSWORD_HASH);
+            false
+        }
+    }
+}
+
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    let Ok(password_hash) = PasswordHash::parse(password_hash, Encoding::default()) else {
+        return false;
+    };
+
+    Argon2::default()
+        .verify_password(password.as_bytes(), &password_hash)
+        .is_ok()
+}
+
+/// Seeds the first admin account from the `username` and `password_hash` of the auth config when
+/// there are no accounts yet
+pub async fn bootstrap_admin(pool: &SqlitePool, config: &config::Auth) -> Result<()> {
+    if !config.enabled || db::get_accounts_count(pool).await? > 0 {
+        return Ok(());
+    }
+
+    let (Some(username), Some(password_hash)) = (&config.username, &config.password_hash) else {
+        warn!(
+            "Authentication is enabled without any account, set a username and password hash to create the first admin"
+        );
+        return Ok(());
+    };
 
-use crate::{Error, Result, state::AppState};
+    db::create_account(pool, username, password_hash, Role::Admin).await?;
+    info!("Created admin account {username} from the auth config");
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Claims {
-    sub: String,
-    exp: i64,
-    iat: i64,
+    Ok(())
 }
 
+/// Requires a valid API token in the `Authorization` header or a valid session cookie, whose
+/// account has at least the required role. The account, and the session or API token, are then
+/// added to the request extensions.
 pub async fn auth_middleware(
-    State(state): State<AppState>,
+    State((state, requirement)): State<(AppState, Requirement)>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     jar: CookieJar,
-    TypedHeader(user_agent): TypedHeader<UserAgent>,
-    request: Request,
+    user_agent: Option<TypedHeader<UserAgent>>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    mut request: Request,
     next: Next,
 ) -> Result<Response> {
-    if state.config.server.auth.enabled {
-        let Some(session_id) = jar.get("session_id") else {
-            return Err(Error::Unauthorized("No session id in cookies".to_string()));
-        };
-
-        let state_session_id = state
-            .session_id
-            .read()
-            .await
-            .clone()
-            .ok_or(Error::Unauthorized("No session id in state".to_string()))?;
+    if !state.config.server.auth.enabled {
+        return Ok(next.run(request).await);
+    }
 
-        if state_session_id.expires_at < OffsetDateTime::now_utc() {
-            return Err(Error::Unauthorized("Session expired".to_string()));
-        }
+    let user_agent = user_agent
+        .map(|TypedHeader(user_agent)| user_agent.to_string())
+        .unwrap_or_default();
+
+    if let Some(TypedHeader(Authorization(bearer))) = bearer {
+        let (api_token, account) =
+            authenticate_api_token(&state, bearer.token(), requirement).await?;
 
-        if session_id.value() != state_session_id.id {
-            return Err(Error::Unauthorized("Invalid session id".to_string()));
+        tracing::info!(
+            "Authenticated account: {} with API token {}, {}, user agent: {}",
+            account.username,
+            api_token.id,
+            addr,
+            user_agent
+        );
+
+        request.extensions_mut().insert(account);
+        request.extensions_mut().insert(api_token);
+        return Ok(next.run(request).await);
+    }
+
+    let Some(token) = jar.get(SESSION_COOKIE) else {
+        return Err(Error::Unauthorized("No session id in cookies".to_string()));
+    };
+    let token = token.value().to_string();
+
+    let session = db::get_valid_session(&state.pool, &hash_token(&token), Utc::now().naive_utc())
+        .await?
+        .ok_or(Error::Unauthorized(
+            "Invalid or expired session".to_string(),
+        ))?;
+
+    let account = get_authorized_account(&state, session.account_id, requirement.role).await?;
+    verify_csrf(&state, &request, &session)?;
+
+    tracing::info!(
+        "Authenticated account: {}, {}, user agent: {}",
+        account.username,
+        addr,
+        user_agent
+    );
+
+    let refreshed = refresh_session(&state, &session).await?;
+    let csrf_token = session.csrf_token.clone();
+
+    request.extensions_mut().insert(account);
+    request.extensions_mut().insert(session);
+    let response = next.run(request).await;
+
+    if refreshed {
+        let jar = jar
+            .add(build_session_cookie(&state.config.server.auth, token))
+            .add(build_csrf_cookie(&state.config.server.auth, csrf_token));
+        return Ok((jar, response).into_response());
+    }
+
+    Ok(response)
+}
+
+/// Adds the account to the request extensions when the request has a valid API token with the
+/// `topics:read` scope or a valid session cookie, letting anonymous requests through for routes
+/// also serving public topics
+pub async fn identify_middleware(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response> {
+    if !state.config.server.auth.enabled {
+        return Ok(next.run(request).await);
+    }
+
+    let requirement = Requirement {
+        role: Role::Viewer,
+        scope: Some(Scope::TopicsRead),
+    };
+
+    if let Some(TypedHeader(Authorization(bearer))) = bearer {
+        let (api_token, account) =
+            authenticate_api_token(&state, bearer.token(), requirement).await?;
+
+        request.extensions_mut().insert(account);
+        request.extensions_mut().insert(api_token);
+        return Ok(next.run(request).await);
+    }
+
+    // Stale cookies are ignored rather than rejected since the caller may only want public topics
+    let session = match jar.get(SESSION_COOKIE) {
+        Some(token) => {
+            let now = Utc::now().naive_utc();
+            db::get_valid_session(&state.pool, &hash_token(token.value()), now).await?
         }
+        None => None,
+    };
+    let account = match &session {
+        Some(session) => get_authorized_account(&state, session.account_id, requirement.role)
+            .await
+            .ok(),
+        None => None,
+    };
 
-        tracing::info!("Authenticated user: {}, user agent: {}", addr, user_agent);
+    if let (Some(session), Some(account)) = (session, account) {
+        request.extensions_mut().insert(account);
+        request.extensions_mut().insert(session);
     }
+
     Ok(next.run(request).await)
 }
 
+async fn authenticate_api_token(
+    state: &AppState,
+    token: &str,
+    requirement: Requirement,
+) -> Result<(ApiToken, Account)> {
+    let now = Utc::now().naive_utc();
+
+    let api_token = db::get_valid_api_token(&state.pool, &hash_token(token), now)
+        .await?
+        .ok_or(Error::Unauthorized(
+            "Invalid, expired or revoked API token".to_string(),
+        ))?;
+
+    let Some(scope) = requirement
+        .scope
+        .filter(|scope| api_token.scopes.contains(scope))
+    else {
+        return Err(Error::Forbidden(format!(
+            "API token {} is missing scope {:?}",
+            api_token.id, requirement.scope
+        )));
+    };
+
+    let account = get_authorized_account(state, api_token.account_id, requirement.role).await?;
+    db::update_api_token_last_used(&state.pool, api_token.id, now).await?;
+
+    tracing::debug!("API token {} used with scope {scope:?}", api_token.id);
+
+    Ok((api_token, account))
+}
+
+/// Enabled account with at least `role`
+async fn get_authorized_account(state: &AppState, account_id: i64, role: Role) -> Result<Account> {
+    let account = db::get_account(&state.pool, account_id)
+        .await?
+        .filter(|account| account.enabled)
+        .ok_or(Error::Unauthorized(
+            "Account disabled or deleted".to_string(),
+        ))?;
+
+    if account.role < role {
+        return Err(Error::Forbidden(format!(
+            "Account {} with role {:?} requires role {role:?}",
+            account.username, account.role
+        )));
+    }
+
+    Ok(account)
+}
+
+/// Slides the expiry of a session, at most once every [`SESSION_REFRESH_INTERVAL_SECONDS`] to avoid
+/// a write per request
+async fn refresh_session(state: &AppState, session: &Session) -> Result<bool> {
+    let now = Utc::now().naive_utc();
+    if (now - session.last_seen_at).num_seconds() < SESSION_REFRESH_INTERVAL_SECONDS {
+        return Ok(false);
+    }
+
+    let expires_at = now + session_duration(&state.config.server.auth);
+    db::touch_session(&state.pool, session.id, now, expires_at).await?;
+
+    Ok(true)
+}
+
+fn session_duration(config: &config::Auth) -> TimeDelta {
+    TimeDelta::minutes(config.cookie_expiry_minutes.unwrap_or(30) as i64)
+}
+
+pub(crate) fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+pub(crate) fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Creates a session for the account, adding the cookies holding its token and CSRF token
+pub async fn create_session(
+    state: &AppState,
+    jar: CookieJar,
+    account_id: i64,
+    user_agent: Option<String>,
+    ip: Option<String>,
+) -> Result<CookieJar> {
+    let token = random_token();
+    let csrf_token = random_token();
+
+    db::create_session(
+        &state.pool,
+        CreateSession {
+            token_hash: hash_token(&token),
+            account_id,
+            expires_at: Utc::now().naive_utc() + session_duration(&state.config.server.auth),
+            user_agent,
+            ip,
+            csrf_token: csrf_token.clone(),
+        },
+    )
+    .await?;
+
+    Ok(jar
+        .add(build_session_cookie(&state.config.server.auth, token))
+        .add(build_csrf_cookie(&state.config.server.auth, csrf_token)))
+}
+
+/// Creates an API token for the account, returning it with the only copy of the secret token
+pub async fn create_api_token(
+    pool: &SqlitePool,
+    account_id: i64,
+    api_token: CreateApiToken,
+) -> Result<CreatedApiToken> {
+    if api_token.name.trim().is_empty() {
+        return Err(Error::BadRequest(
+            "API token name can't be empty".to_string(),
+        ));
+    }
+
+    if api_token.scopes.is_empty() {
+        return Err(Error::BadRequest(
+            "API token requires at least one scope".to_string(),
+        ));
+    }
+
+    if api_token.expires_in_days.is_some_and(|days| days <= 0) {
+        return Err(Error::BadRequest(
+            "API token expiry must be a positive number of days".to_string(),
+        ));
+    }
+
+    let token = format!("{API_TOKEN_PREFIX}{}", random_token());
+
+    let expires_at = api_token
+        .expires_in_days
+        .map(|days| Utc::now().naive_utc() + TimeDelta::days(days));
+
+    let created = db::create_api_token(
+        pool,
+        account_id,
+        api_token.name.trim(),
+        &hash_token(&token),
+        &api_token.scopes,
+        expires_at,
+    )
+    .await?;
+
+    Ok(CreatedApiToken {
+        api_token: created,
+        token,
+    })
+}
+
+/// Deletes the session of the cookie, removing its cookies
+pub async fn delete_session(state: &AppState, jar: CookieJar) -> Result<CookieJar> {
+    if let Some(token) = jar.get(SESSION_COOKIE) {
+        db::delete_session_by_token_hash(&state.pool, &hash_token(token.value())).await?;
+    }
+
+    let config = &state.config.server.auth;
+
+    Ok(jar
+        .remove(build_session_cookie(config, "".to_string()))
+        .remove(build_csrf_cookie(config, "".to_string())))
+}
+
+/// Readable by the frontend so it can send it back in the [`CSRF_HEADER`] of mutating requests
+fn build_csrf_cookie<'a>(config: &config::Auth, csrf_token: String) -> Cookie<'a> {
+    let mut cookie = build_session_cookie(config, csrf_token);
+    cookie.set_name(CSRF_COOKIE);
+    cookie.set_http_only(false);
+    cookie
+}
+
+/// Cookie authenticated requests which aren't safe need the CSRF token of their session, and must
+/// come from the allowed origin when the browser tells where they come from
+fn verify_csrf(state: &AppState, request: &Request, session: &Session) -> Result<()> {
+    if request.method().is_safe() {
+        return Ok(());
+    }
+
+    let allowed_origin = state
+        .config
+        .server
+        .cors
+        .allowed_origin
+        .trim_end_matches('/');
+
+    let headers = request.headers();
+    let origin = headers
+        .get(header::ORIGIN)
+        .and_then(|origin| origin.to_str().ok())
+        .map(str::to_string)
+        .or_else(|| {
+            let referer = headers.get(header::REFERER)?.to_str().ok()?;
+            let referer = Url::parse(referer).ok()?;
+            Some(referer.origin().ascii_serialization())
+        });
+
+    if let Some(origin) = origin.filter(|origin| origin.trim_end_matches('/') != allowed_origin) {
+        return Err(Error::Forbidden(format!(
+            "Request from origin {origin} not allowed"
+        )));
+    }
+
+    let csrf_token = headers
+        .get(CSRF_HEADER)
+        .and_then(|csrf_token| csrf_token.to_str().ok())
+        .unwrap_or_default();
+
+    if session.csrf_token.is_empty() || !constant_time_eq(csrf_token, &session.csrf_token) {
+        return Err(Error::Forbidden("Invalid CSRF token".to_string()));
+    }
+
+    Ok(())
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+fn build_session_cookie<'a>(config: &config::Auth, token: String) -> Cookie<'a> {
+    let cookie_expiry_minutes = config.cookie_expiry_minutes.unwrap_or(30);
+    let cookie_domain = config
+        .cookie_domain
+        .clone()
+        .unwrap_or("localhost".to_string());
+    let cookie_secure = config.cookie_secure.unwrap_or(false);
+    let expires_at = OffsetDateTime::now_utc() + Duration::minutes(cookie_expiry_minutes as i64);
+
+    Cookie::build((SESSION_COOKIE, token))
+        .path("/")
+        .http_only(true)
+        .secure(cookie_secure)
+        .domain(cookie_domain)
+        .same_site(SameSite::Strict)
+        .max_age(Duration::minutes(cookie_expiry_minutes as i64))
+        .expires(expires_at)
+        .build()
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum AuthPermission {
@@ -58,26 +496,52 @@ pub enum AuthPermission {
     InvalidSession,
 }
 
-pub async fn auth_permission(state: &AppState, jar: &CookieJar) -> AuthPermission {
+pub async fn auth_permission(state: &AppState, jar: &CookieJar) -> Result<AuthPermission> {
     if !state.config.server.auth.enabled {
-        return AuthPermission::AuthDisabled;
+        return Ok(AuthPermission::AuthDisabled);
     }
 
-    let Some(session_id) = jar.get("session_id") else {
-        return AuthPermission::InvalidSession;
+    let Some(token) = jar.get(SESSION_COOKIE) else {
+        return Ok(AuthPermission::InvalidSession);
     };
 
-    let Some(state_session_id) = state.session_id.read().await.clone() else {
-        return AuthPermission::InvalidSession;
-    };
+    let session = db::get_valid_session(
+        &state.pool,
+        &hash_token(token.value()),
+        Utc::now().naive_utc(),
+    )
+    .await?;
 
-    if state_session_id.expires_at < OffsetDateTime::now_utc() {
-        return AuthPermission::InvalidSession;
-    }
+    Ok(match session {
+        Some(_) => AuthPermission::Authenticated,
+        None => AuthPermission::InvalidSession,
+    })
+}
 
-    if session_id.value() != state_session_id.id {
-        return AuthPermission::InvalidSession;
+pub fn start_session_cleanup(state: AppState) {
+    if !state.config.server.auth.enabled {
+        return;
     }
 
-    AuthPermission::Authenticated
+    let interval_minutes = state
+        .config
+        .server
+        .auth
+        .session_cleanup_interval_minutes
+        .unwrap_or(60);
+
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(interval_minutes * 60));
+
+        loop {
+            interval.tick().await;
+
+            match db::delete_expired_sessions(&state.pool, Utc::now().naive_utc()).await {
+                Ok(0) => {}
+                Ok(count) => info!("Deleted {count} expired sessions"),
+                Err(err) => error!("Error deleting expired sessions: {err}"),
+            }
+        }
+    });
 }
@@ -1,12 +1,16 @@
+use chrono::TimeDelta;
 use figment::{
     Figment,
     providers::{Env, Format, Toml},
 };
 use serde::Deserialize;
-use std::net::SocketAddr;
+use std::{collections::BTreeMap, net::SocketAddr};
 use tracing::info;
 
-use crate::Result;
+use crate::{
+    Result,
+    models::{account::Role, analysis::TokenUsage, timeseries::Bucket},
+};
 
 #[derive(Deserialize, Clone)]
 pub struct Database {
@@ -34,6 +38,12 @@ pub struct Server {
     pub cors: Cors,
     pub frontend: Frontend,
     pub auth: Auth,
+    pub metrics: Metrics,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Metrics {
+    pub enabled: bool,
 }
 
 #[derive(Deserialize, Clone)]
@@ -77,9 +87,146 @@ impl Jetstream {
 #[derive(Deserialize, Clone)]
 pub struct Gemini {
     pub enabled: bool,
+    pub provider: LlmProvider,
+    /// Endpoint of the provider, including the model for Gemini
     pub base_url: String,
+    /// Model sent to the provider, required by OpenAI and Ollama
+    pub model: Option<String>,
+    /// Required by Gemini, optional for the other providers
+    pub api_key: Option<String>,
     pub timeout_seconds: u64,
     pub user_agent: String,
+    /// Requests sent to Gemini at the same time, others waiting for one to finish
+    pub max_concurrent_requests: usize,
+    /// Requests sent per minute, evenly spaced, 0 for no limit
+    pub requests_per_minute: u32,
+    /// Retries of requests failing because of rate limits or the provider being unavailable
+    pub max_retries: u32,
+    /// Delay before the first retry, doubling on every retry
+    pub retry_base_delay_ms: u64,
+    /// Longest delay before a retry, requests whose `Retry-After` is longer failing right away
+    pub max_retry_delay_seconds: u64,
+    pub analysis: GeminiAnalysis,
+    pub questions: GeminiQuestions,
+    pub usage: GeminiUsage,
+}
+
+/// API used to generate keywords and analyses
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LlmProvider {
+    Gemini,
+    /// OpenAI compatible chat completions API
+    #[serde(rename = "openai")]
+    OpenAi,
+    Ollama,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct GeminiAnalysis {
+    pub default_window_hours: i64,
+    pub max_window_days: i64,
+    /// Posts kept after deduplication, evenly sampled over the window when there are more
+    pub max_posts: usize,
+    /// Estimated tokens of posts sent across all the requests of an analysis
+    pub max_input_tokens: usize,
+    /// Estimated tokens of posts sent in a single request, larger inputs being summarized in
+    /// chunks which are then combined
+    pub chunk_tokens: usize,
+    pub chunk_concurrency: usize,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct GeminiQuestions {
+    /// Most relevant posts retrieved to answer a question
+    pub max_posts: i64,
+    /// Estimated tokens of the retrieved posts sent along with the question
+    pub max_input_tokens: usize,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct GeminiUsage {
+    /// Prompt and output tokens allowed per UTC month, 0 for no limit
+    pub monthly_token_budget: i64,
+    /// Cost allowed per UTC month, 0 for no limit
+    pub monthly_cost_budget: f64,
+    /// Prices of the configured model, used to compute the cost of every call
+    pub prompt_cost_per_million_tokens: f64,
+    pub output_cost_per_million_tokens: f64,
+}
+
+impl GeminiUsage {
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        (usage.prompt_tokens as f64 * self.prompt_cost_per_million_tokens
+            + usage.output_tokens as f64 * self.output_cost_per_million_tokens)
+            / 1_000_000.0
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Schedules {
+    pub enabled: bool,
+    pub poll_interval_seconds: u64,
+    /// Scheduled analyses run at the same time
+    pub concurrency: usize,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Smtp {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+    pub from: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub timeout_seconds: u64,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Webhook {
+    pub timeout_seconds: u64,
+    pub user_agent: String,
+    /// Hosts webhooks can target even when they resolve to private or loopback addresses
+    pub allowed_hosts: Vec<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Timeseries {
+    pub cleanup_interval_minutes: u64,
+    pub retention_days: RollupRetention,
+}
+
+/// Days rollups of each bucket are kept, forever when 0
+#[derive(Deserialize, Clone)]
+pub struct RollupRetention {
+    pub minute: i64,
+    pub five_minutes: i64,
+    pub hour: i64,
+    pub day: i64,
+}
+
+impl Timeseries {
+    /// How long rollups of the bucket are kept, `None` when forever
+    pub fn retention(&self, bucket: Bucket) -> Option<TimeDelta> {
+        let days = match bucket {
+            Bucket::Minute => self.retention_days.minute,
+            Bucket::FiveMinutes => self.retention_days.five_minutes,
+            Bucket::Hour => self.retention_days.hour,
+            Bucket::Day => self.retention_days.day,
+        };
+
+        (days > 0).then(|| TimeDelta::days(days))
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Alerts {
+    pub enabled: bool,
+    pub evaluation_interval_seconds: u64,
+    pub min_evaluation_interval_seconds: u64,
+    pub webhook: Webhook,
+    pub smtp: Smtp,
 }
 
 #[derive(Deserialize, Clone, Default)]
@@ -95,6 +242,54 @@ pub struct Auth {
     pub cookie_expiry_minutes: Option<u64>,
     pub cookie_domain: Option<String>,
     pub cookie_secure: Option<bool>,
+    pub session_cleanup_interval_minutes: Option<u64>,
+    pub oidc: Option<Oidc>,
+    #[serde(default)]
+    pub login_throttle: LoginThrottle,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct LoginThrottle {
+    /// Failed attempts allowed before delays are enforced
+    pub free_attempts: u32,
+    pub base_delay_seconds: u64,
+    pub max_delay_seconds: u64,
+    /// Failed attempts after which the IP or username is locked out
+    pub lockout_threshold: u32,
+    pub lockout_minutes: u64,
+}
+
+impl Default for LoginThrottle {
+    fn default() -> Self {
+        Self {
+            free_attempts: 3,
+            base_delay_seconds: 1,
+            max_delay_seconds: 60,
+            lockout_threshold: 10,
+            lockout_minutes: 15,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Oidc {
+    pub enabled: bool,
+    pub issuer_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    /// Public URL of the `/api/v1/auth/oidc/callback` route, registered with the issuer
+    pub redirect_url: String,
+    /// Where the browser is sent once logged in
+    pub post_login_redirect_url: Option<String>,
+    #[serde(default)]
+    pub allowed_email_domains: Vec<String>,
+    pub groups_claim: Option<String>,
+    /// Role given for each group of the groups claim, the highest one is used
+    #[serde(default)]
+    pub group_roles: BTreeMap<String, Role>,
+    /// Role given when no group is mapped, logins are rejected without one
+    pub default_role: Option<Role>,
 }
 
 #[derive(Deserialize, Clone)]
@@ -103,15 +298,20 @@ pub struct Config {
     pub server: Server,
     pub jetstream: Jetstream,
     pub gemini: Gemini,
+    pub schedules: Schedules,
+    pub timeseries: Timeseries,
+    pub alerts: Alerts,
 }
 
 impl Config {
-    pub fn new() -> figment::error::Result<Self> {
+    pub fn new() -> Result<Self> {
         info!("Fetching config");
 
-        Figment::new()
+        let config = Figment::new()
             .merge(Toml::string(include_str!("../config.toml")))
             .merge(Env::prefixed("BLUFLARE__").split("__"))
-            .extract()
+            .extract()?;
+
+        Ok(config)
     }
 }
@@ -0,0 +1,222 @@
+use sqlx::SqliteExecutor;
+
+use crate::{
+    Result,
+    models::account::{Account, AccountCredentials, Role},
+};
+
+pub async fn create_account<'e>(
+    executor: impl SqliteExecutor<'e>,
+    username: &str,
+    password_hash: &str,
+    role: Role,
+) -> Result<Account> {
+    let account = sqlx::query_as!(
+        Account,
+        r#"
+            INSERT INTO accounts (username, password_hash, role)
+            VALUES (?, ?, ?)
+            RETURNING id, created_at, username, role as "role: Role", enabled, last_login_at
+            "#,
+        username,
+        password_hash,
+        role,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(account)
+}
+
+pub async fn get_accounts<'e>(executor: impl SqliteExecutor<'e>) -> Result<Vec<Account>> {
+    let accounts = sqlx::query_as!(
+        Account,
+        r#"
+            SELECT id, created_at, username, role as "role: Role", enabled, last_login_at
+            FROM accounts
+            ORDER BY username
+            "#,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(accounts)
+}
+
+pub async fn get_account<'e>(
+    executor: impl SqliteExecutor<'e>,
+    id: i64,
+) -> Result<Option<Account>> {
+    let account = sqlx::query_as!(
+        Account,
+        r#"
+            SELECT id, created_at, username, role as "role: Role", enabled, last_login_at
+            FROM accounts
+            WHERE id = ?
+            "#,
+        id,
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(account)
+}
+
+pub async fn get_account_credentials<'e>(
+    executor: impl SqliteExecutor<'e>,
+    username: &str,
+) -> Result<Option<AccountCredentials>> {
+    let credentials = sqlx::query_as!(
+        AccountCredentials,
+        r#"
+            SELECT id, password_hash, enabled
+            FROM accounts
+            WHERE username = ?
+            "#,
+        username,
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(credentials)
+}
+
+pub async fn get_account_password_hash<'e>(
+    executor: impl SqliteExecutor<'e>,
+    id: i64,
+) -> Result<Option<String>> {
+    let password_hash =
+        sqlx::query_scalar!(r#"SELECT password_hash FROM accounts WHERE id = ?"#, id)
+            .fetch_optional(executor)
+            .await?;
+
+    Ok(password_hash)
+}
+
+pub async fn account_username_exists<'e>(
+    executor: impl SqliteExecutor<'e>,
+    username: &str,
+) -> Result<bool> {
+    let exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM accounts WHERE username = ?)"#,
+        username
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(exists == 1)
+}
+
+pub async fn get_accounts_count<'e>(executor: impl SqliteExecutor<'e>) -> Result<i64> {
+    let count = sqlx::query_scalar!(r#"SELECT COUNT(*) FROM accounts"#)
+        .fetch_one(executor)
+        .await?;
+
+    Ok(count)
+}
+
+/// Enabled admins other than `excluded_id`, used to never lock everyone out of account management
+pub async fn get_other_enabled_admins_count<'e>(
+    executor: impl SqliteExecutor<'e>,
+    excluded_id: i64,
+) -> Result<i64> {
+    let count = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) FROM accounts WHERE role = 'admin' AND enabled = 1 AND id != ?"#,
+        excluded_id
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(count)
+}
+
+pub async fn update_account<'e>(
+    executor: impl SqliteExecutor<'e>,
+    id: i64,
+    password_hash: Option<String>,
+    role: Option<Role>,
+    enabled: Option<bool>,
+) -> Result<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE accounts SET
+            password_hash = COALESCE(?, password_hash),
+            role = COALESCE(?, role),
+            enabled = COALESCE(?, enabled)
+        WHERE id = ?
+        "#,
+        password_hash,
+        role,
+        enabled,
+        id,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn update_account_last_login<'e>(
+    executor: impl SqliteExecutor<'e>,
+    id: i64,
+) -> Result<()> {
+    sqlx::query!(
+        r#"UPDATE accounts SET last_login_at = CURRENT_TIMESTAMP WHERE id = ?"#,
+        id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_account<'e>(executor: impl SqliteExecutor<'e>, id: i64) -> Result<bool> {
+    let result = sqlx::query!(r#"DELETE FROM accounts WHERE id = ?"#, id)
+        .execute(executor)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_account_by_oidc_subject<'e>(
+    executor: impl SqliteExecutor<'e>,
+    oidc_subject: &str,
+) -> Result<Option<Account>> {
+    let account = sqlx::query_as!(
+        Account,
+        r#"
+            SELECT id, created_at, username, role as "role: Role", enabled, last_login_at
+            FROM accounts
+            WHERE oidc_subject = ?
+            "#,
+        oidc_subject,
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(account)
+}
+
+/// Creates an account authenticating through OIDC, it has no usable password
+pub async fn create_oidc_account<'e>(
+    executor: impl SqliteExecutor<'e>,
+    username: &str,
+    oidc_subject: &str,
+    role: Role,
+) -> Result<Account> {
+    let account = sqlx::query_as!(
+        Account,
+        r#"
+            INSERT INTO accounts (username, password_hash, role, oidc_subject)
+            VALUES (?, '', ?, ?)
+            RETURNING id, created_at, username, role as "role: Role", enabled, last_login_at
+            "#,
+        username,
+        role,
+        oidc_subject,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(account)
+}
@@ -0,0 +1,78 @@
+use chrono::NaiveDateTime;
+use sqlx::SqliteExecutor;
+
+use crate::{Result, models::admin::DatabaseStats};
+
+pub async fn get_database_stats<'e>(
+    executor: impl SqliteExecutor<'e>,
+    now: NaiveDateTime,
+) -> Result<DatabaseStats> {
+    let stats = sqlx::query_as!(
+        DatabaseStats,
+        r#"
+            SELECT
+                (SELECT COUNT(*) FROM topics) as "topics!: i64",
+                (SELECT COUNT(*) FROM posts) as "posts!: i64",
+                (SELECT COUNT(*) FROM users) as "users!: i64",
+                (SELECT COUNT(*) FROM accounts) as "accounts!: i64",
+                (SELECT COUNT(*) FROM sessions WHERE expires_at > ?1) as "active_sessions!: i64",
+                (
+                    SELECT COUNT(*) FROM api_tokens
+                    WHERE revoked_at IS NULL AND (expires_at IS NULL OR expires_at > ?1)
+                ) as "active_api_tokens!: i64",
+                (SELECT COUNT(*) FROM alert_rules) as "alert_rules!: i64",
+                (SELECT MIN(created_at) FROM posts) as "oldest_post_at: String",
+                (SELECT MAX(created_at) FROM posts) as "newest_post_at: String"
+            "#,
+        now,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(stats)
+}
+
+/// Deletes posts created before the cutoff, their topic links and mentions cascading, while the
+/// stats rollups are kept
+pub async fn delete_posts_created_before<'e>(
+    executor: impl SqliteExecutor<'e>,
+    cutoff: NaiveDateTime,
+) -> Result<u64> {
+    let result = sqlx::query!(
+        r#"
+            DELETE FROM posts
+            WHERE CAST(strftime('%s', created_at) AS INTEGER) < CAST(strftime('%s', ?) AS INTEGER)
+            "#,
+        cutoff,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Deletes users who neither authored nor were mentioned in any remaining post
+pub async fn delete_orphan_users<'e>(executor: impl SqliteExecutor<'e>) -> Result<u64> {
+    let result = sqlx::query!(
+        r#"
+            DELETE FROM users
+            WHERE NOT EXISTS (SELECT 1 FROM posts WHERE posts.author_id = users.id)
+            AND NOT EXISTS (SELECT 1 FROM post_mentions WHERE post_mentions.user_id = users.id)
+            "#,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn delete_alert_events_created_before<'e>(
+    executor: impl SqliteExecutor<'e>,
+    cutoff: NaiveDateTime,
+) -> Result<u64> {
+    let result = sqlx::query!(r#"DELETE FROM alert_events WHERE created_at < ?"#, cutoff)
+        .execute(executor)
+        .await?;
+
+    Ok(result.rows_affected())
+}
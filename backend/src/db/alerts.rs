@@ -0,0 +1,214 @@
+use chrono::NaiveDateTime;
+use sqlx::SqliteExecutor;
+
+use crate::{
+    Result,
+    models::alert::{
+        AlertEvent, AlertRule, AlertState, CreateAlertEvent, CreateAlertRule, DbAlertRule,
+        UpdateAlertRule,
+    },
+};
+
+pub async fn create_alert_rule<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+    rule: CreateAlertRule,
+) -> Result<AlertRule> {
+    let condition = serde_json::to_vec(&rule.condition)?;
+    let channels = serde_json::to_vec(&rule.channels)?;
+
+    let rule = sqlx::query_as!(
+        DbAlertRule,
+        r#"
+            INSERT INTO alert_rules (topic_id, name, condition, channels)
+            VALUES (?, ?, ?, ?)
+            RETURNING id, created_at, topic_id, name, enabled, condition, channels,
+                state as "state: AlertState", state_changed_at, last_evaluated_at
+            "#,
+        topic_id,
+        rule.name,
+        condition,
+        channels,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(rule.into())
+}
+
+pub async fn get_topic_alert_rules<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+) -> Result<Vec<AlertRule>> {
+    let rules = sqlx::query_as!(
+        DbAlertRule,
+        r#"
+            SELECT id, created_at, topic_id, name, enabled, condition, channels,
+                state as "state: AlertState", state_changed_at, last_evaluated_at
+            FROM alert_rules
+            WHERE topic_id = ?
+            ORDER BY created_at DESC
+            "#,
+        topic_id,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rules.into_iter().map(AlertRule::from).collect())
+}
+
+pub async fn get_enabled_alert_rules<'e>(
+    executor: impl SqliteExecutor<'e>,
+) -> Result<Vec<AlertRule>> {
+    let rules = sqlx::query_as!(
+        DbAlertRule,
+        r#"
+            SELECT alert_rules.id, alert_rules.created_at, topic_id, name, alert_rules.enabled,
+                condition, channels, state as "state: AlertState", state_changed_at,
+                last_evaluated_at
+            FROM alert_rules
+            JOIN topics ON alert_rules.topic_id = topics.id
+            WHERE alert_rules.enabled = 1 AND topics.enabled = 1
+            "#,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rules.into_iter().map(AlertRule::from).collect())
+}
+
+pub async fn get_alert_rule<'e>(
+    executor: impl SqliteExecutor<'e>,
+    id: i64,
+) -> Result<Option<AlertRule>> {
+    let rule = sqlx::query_as!(
+        DbAlertRule,
+        r#"
+            SELECT id, created_at, topic_id, name, enabled, condition, channels,
+                state as "state: AlertState", state_changed_at, last_evaluated_at
+            FROM alert_rules
+            WHERE id = ?
+            "#,
+        id,
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(rule.map(AlertRule::from))
+}
+
+pub async fn update_alert_rule<'e>(
+    executor: impl SqliteExecutor<'e>,
+    id: i64,
+    rule: UpdateAlertRule,
+) -> Result<bool> {
+    let condition = rule
+        .condition
+        .map(|condition| serde_json::to_vec(&condition))
+        .transpose()?;
+    let channels = rule
+        .channels
+        .map(|channels| serde_json::to_vec(&channels))
+        .transpose()?;
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE alert_rules SET
+            name = COALESCE(?, name),
+            enabled = COALESCE(?, enabled),
+            condition = COALESCE(?, condition),
+            channels = COALESCE(?, channels)
+        WHERE id = ?
+        "#,
+        rule.name,
+        rule.enabled,
+        condition,
+        channels,
+        id,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn delete_alert_rule<'e>(executor: impl SqliteExecutor<'e>, id: i64) -> Result<bool> {
+    let result = sqlx::query!(r#"DELETE FROM alert_rules WHERE id = ?"#, id)
+        .execute(executor)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn update_alert_rule_evaluation<'e>(
+    executor: impl SqliteExecutor<'e>,
+    id: i64,
+    state: AlertState,
+    evaluated_at: NaiveDateTime,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE alert_rules SET
+            state_changed_at = CASE WHEN state != ? THEN ? ELSE state_changed_at END,
+            state = ?,
+            last_evaluated_at = ?
+        WHERE id = ?
+        "#,
+        state,
+        evaluated_at,
+        state,
+        evaluated_at,
+        id,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn create_alert_event<'e>(
+    executor: impl SqliteExecutor<'e>,
+    event: CreateAlertEvent,
+) -> Result<AlertEvent> {
+    let event = sqlx::query_as!(
+        AlertEvent,
+        r#"
+            INSERT INTO alert_events (rule_id, state, value, baseline, message)
+            VALUES (?, ?, ?, ?, ?)
+            RETURNING id, created_at, rule_id, state as "state: AlertState", value, baseline,
+                message
+            "#,
+        event.rule_id,
+        event.state,
+        event.value,
+        event.baseline,
+        event.message,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(event)
+}
+
+pub async fn get_alert_events<'e>(
+    executor: impl SqliteExecutor<'e>,
+    rule_id: i64,
+    limit: i64,
+) -> Result<Vec<AlertEvent>> {
+    let events = sqlx::query_as!(
+        AlertEvent,
+        r#"
+            SELECT id, created_at, rule_id, state as "state: AlertState", value, baseline, message
+            FROM alert_events
+            WHERE rule_id = ?
+            ORDER BY id DESC
+            LIMIT ?
+            "#,
+        rule_id,
+        limit,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(events)
+}
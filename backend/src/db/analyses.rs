@@ -0,0 +1,140 @@
+use chrono::NaiveDateTime;
+use sqlx::SqliteExecutor;
+
+use crate::{
+    Result,
+    models::analysis::{Analysis, CreateAnalysis, DbAnalysis, SentimentPoint},
+};
+
+pub async fn create_analysis<'e>(
+    executor: impl SqliteExecutor<'e>,
+    analysis: CreateAnalysis,
+) -> Result<Analysis> {
+    let structured_result = analysis
+        .structured_result
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()?;
+
+    let analysis = sqlx::query_as!(
+        DbAnalysis,
+        r#"
+            INSERT INTO analyses (topic_id, created_by, since, until, model, prompt_version,
+                matched_post_count, post_count, result, prompt_tokens, output_tokens,
+                structured_result)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+        analysis.topic_id,
+        analysis.created_by,
+        analysis.since,
+        analysis.until,
+        analysis.model,
+        analysis.prompt_version,
+        analysis.matched_post_count,
+        analysis.post_count,
+        analysis.result,
+        analysis.usage.prompt_tokens,
+        analysis.usage.output_tokens,
+        structured_result,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(analysis.into())
+}
+
+pub async fn count_topic_analyses<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+) -> Result<i64> {
+    let count = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) FROM analyses WHERE topic_id = ?"#,
+        topic_id,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(count)
+}
+
+/// Analyses of a topic, latest first
+pub async fn get_topic_analyses<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+    page: i64,
+    per_page: i64,
+) -> Result<Vec<Analysis>> {
+    let offset = (page - 1) * per_page;
+
+    let analyses = sqlx::query_as!(
+        DbAnalysis,
+        r#"
+            SELECT * FROM analyses
+            WHERE topic_id = ?
+            ORDER BY created_at DESC, id DESC
+            LIMIT ? OFFSET ?
+            "#,
+        topic_id,
+        per_page,
+        offset,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(analyses.into_iter().map(Analysis::from).collect())
+}
+
+pub async fn get_topic_analysis<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+    id: i64,
+) -> Result<Option<Analysis>> {
+    let analysis = sqlx::query_as!(
+        DbAnalysis,
+        r#"SELECT * FROM analyses WHERE topic_id = ? AND id = ?"#,
+        topic_id,
+        id,
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(analysis.map(Analysis::from))
+}
+
+/// Sentiment of the analyses of a topic with a structured result whose window ends in
+/// `[since, until]`, oldest first
+pub async fn get_topic_sentiment<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+    since: Option<NaiveDateTime>,
+    until: Option<NaiveDateTime>,
+) -> Result<Vec<SentimentPoint>> {
+    let points = sqlx::query_as!(
+        SentimentPoint,
+        r#"
+            SELECT
+                id AS "analysis_id!",
+                since,
+                until,
+                json_extract(structured_result, '$.sentiment.score') AS "score!: f64",
+                json_extract(structured_result, '$.sentiment.positive') AS "positive!: f64",
+                json_extract(structured_result, '$.sentiment.neutral') AS "neutral!: f64",
+                json_extract(structured_result, '$.sentiment.negative') AS "negative!: f64"
+            FROM analyses
+            WHERE topic_id = ? AND structured_result IS NOT NULL
+                AND (? IS NULL OR until >= ?)
+                AND (? IS NULL OR until <= ?)
+            ORDER BY until ASC, id ASC
+            "#,
+        topic_id,
+        since,
+        since,
+        until,
+        until,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(points)
+}
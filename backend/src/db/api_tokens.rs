@@ -0,0 +1,114 @@
+use chrono::NaiveDateTime;
+use sqlx::SqliteExecutor;
+
+use crate::{
+    Result,
+    models::api_token::{ApiToken, DbApiToken, Scope},
+};
+
+pub async fn create_api_token<'e>(
+    executor: impl SqliteExecutor<'e>,
+    account_id: i64,
+    name: &str,
+    token_hash: &str,
+    scopes: &[Scope],
+    expires_at: Option<NaiveDateTime>,
+) -> Result<ApiToken> {
+    let scopes = serde_json::to_vec(scopes)?;
+
+    let token = sqlx::query_as!(
+        DbApiToken,
+        r#"
+            INSERT INTO api_tokens (account_id, name, token_hash, scopes, expires_at)
+            VALUES (?, ?, ?, ?, ?)
+            RETURNING id, created_at, account_id, name, scopes, expires_at, last_used_at,
+                revoked_at
+            "#,
+        account_id,
+        name,
+        token_hash,
+        scopes,
+        expires_at,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(token.into())
+}
+
+pub async fn get_account_api_tokens<'e>(
+    executor: impl SqliteExecutor<'e>,
+    account_id: i64,
+) -> Result<Vec<ApiToken>> {
+    let tokens = sqlx::query_as!(
+        DbApiToken,
+        r#"
+            SELECT id, created_at, account_id, name, scopes, expires_at, last_used_at, revoked_at
+            FROM api_tokens
+            WHERE account_id = ?
+            ORDER BY created_at DESC
+            "#,
+        account_id,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(tokens.into_iter().map(ApiToken::from).collect())
+}
+
+/// Token matching the hash which is neither revoked nor expired
+pub async fn get_valid_api_token<'e>(
+    executor: impl SqliteExecutor<'e>,
+    token_hash: &str,
+    now: NaiveDateTime,
+) -> Result<Option<ApiToken>> {
+    let token = sqlx::query_as!(
+        DbApiToken,
+        r#"
+            SELECT id, created_at, account_id, name, scopes, expires_at, last_used_at, revoked_at
+            FROM api_tokens
+            WHERE token_hash = ? AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > ?)
+            "#,
+        token_hash,
+        now,
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(token.map(ApiToken::from))
+}
+
+pub async fn update_api_token_last_used<'e>(
+    executor: impl SqliteExecutor<'e>,
+    id: i64,
+    last_used_at: NaiveDateTime,
+) -> Result<()> {
+    sqlx::query!(
+        r#"UPDATE api_tokens SET last_used_at = ? WHERE id = ?"#,
+        last_used_at,
+        id,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn revoke_api_token<'e>(
+    executor: impl SqliteExecutor<'e>,
+    account_id: i64,
+    id: i64,
+) -> Result<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE api_tokens SET revoked_at = CURRENT_TIMESTAMP
+        WHERE account_id = ? AND id = ? AND revoked_at IS NULL
+        "#,
+        account_id,
+        id,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,112 @@
+use sqlx::{QueryBuilder, Sqlite, SqliteExecutor};
+
+use crate::{
+    Result,
+    models::audit::{AuditEntry, AuditLogQuery, CreateAuditEntry, DbAuditEntry},
+};
+
+pub async fn create_audit_entry<'e>(
+    executor: impl SqliteExecutor<'e>,
+    entry: CreateAuditEntry,
+) -> Result<()> {
+    let target_type = entry.action.target_type();
+    let before = entry
+        .before
+        .map(|before| serde_json::to_vec(&before))
+        .transpose()?;
+    let after = entry
+        .after
+        .map(|after| serde_json::to_vec(&after))
+        .transpose()?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO audit_log (actor_account_id, actor_username, actor_api_token_id, action,
+            target_type, target_id, before, after, ip, user_agent)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+        entry.actor_account_id,
+        entry.actor_username,
+        entry.actor_api_token_id,
+        entry.action,
+        target_type,
+        entry.target_id,
+        before,
+        after,
+        entry.ip,
+        entry.user_agent,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+fn push_audit_filters<'a>(builder: &mut QueryBuilder<'a, Sqlite>, query: &'a AuditLogQuery) {
+    builder.push(" WHERE 1 = 1");
+
+    if let Some(actor_account_id) = query.actor_account_id {
+        builder
+            .push(" AND actor_account_id = ")
+            .push_bind(actor_account_id);
+    }
+    if let Some(action) = query.action {
+        builder.push(" AND action = ").push_bind(action);
+    }
+    if let Some(target_type) = &query.target_type {
+        builder.push(" AND target_type = ").push_bind(target_type);
+    }
+    if let Some(target_id) = query.target_id {
+        builder.push(" AND target_id = ").push_bind(target_id);
+    }
+    if let Some(since) = query.since {
+        builder
+            .push(" AND created_at >= ")
+            .push_bind(since.naive_utc());
+    }
+    if let Some(until) = query.until {
+        builder
+            .push(" AND created_at < ")
+            .push_bind(until.naive_utc());
+    }
+}
+
+pub async fn count_audit_entries<'e>(
+    executor: impl SqliteExecutor<'e>,
+    query: &AuditLogQuery,
+) -> Result<i64> {
+    let mut builder = QueryBuilder::new("SELECT COUNT(*) FROM audit_log");
+    push_audit_filters(&mut builder, query);
+
+    let count = builder
+        .build_query_scalar::<i64>()
+        .fetch_one(executor)
+        .await?;
+
+    Ok(count)
+}
+
+pub async fn get_audit_entries<'e>(
+    executor: impl SqliteExecutor<'e>,
+    query: &AuditLogQuery,
+) -> Result<Vec<AuditEntry>> {
+    let (page, per_page) = query.pagination();
+
+    let mut builder = QueryBuilder::new(
+        "SELECT id, created_at, actor_account_id, actor_username, actor_api_token_id, action, \
+            target_type, target_id, before, after, ip, user_agent FROM audit_log",
+    );
+    push_audit_filters(&mut builder, query);
+    builder
+        .push(" ORDER BY id DESC LIMIT ")
+        .push_bind(per_page)
+        .push(" OFFSET ")
+        .push_bind((page - 1) * per_page);
+
+    let entries = builder
+        .build_query_as::<DbAuditEntry>()
+        .fetch_all(executor)
+        .await?;
+
+    Ok(entries.into_iter().map(AuditEntry::from).collect())
+}
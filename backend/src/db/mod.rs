@@ -1,4 +1,4 @@
-use chrono::Utc;
+use chrono::{NaiveDateTime, Utc};
 use sqlx::{
     FromRow, QueryBuilder, SqliteExecutor,
     sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions},
@@ -9,22 +9,58 @@ use tracing::info;
 use crate::{
     Result,
     models::{
-        post::{CreatePost, DbPost, DbPostWithAuthor, Post, PostWithAuthor},
+        post::{
+            AnalysisPost, CreatePost, DbAnalysisPost, DbPost, DbPostWithAuthor, Post,
+            PostWithAuthor,
+        },
         topic::{
             CreateTopic, DbTopic, DbTopicWithPostCount, Topic, TopicWithPostCount, UpdateTopic,
-            UpdateTopicAnalysis,
+            UpdateTopicAnalysis, Visibility,
         },
         user::{CreateUser, DbUser, User},
     },
     slug::slugify,
 };
 
+mod accounts;
+mod admin;
+mod alerts;
+mod analyses;
+mod api_tokens;
+mod audit;
+mod schedules;
+mod sessions;
+mod share_links;
+mod timeseries;
+mod topic_shares;
+mod usage;
+mod users;
+
+pub use accounts::*;
+pub use admin::*;
+pub use alerts::*;
+pub use analyses::*;
+pub use api_tokens::*;
+pub use audit::*;
+pub use schedules::*;
+pub use sessions::*;
+pub use share_links::*;
+pub use timeseries::*;
+pub use topic_shares::*;
+pub use usage::*;
+pub use users::*;
+
 pub async fn new(database_url: &str) -> Result<SqlitePool> {
     let executor = connect_to_db(database_url, 75, 5).await?;
     run_migrations(&executor).await?;
     Ok(executor)
 }
 
+/// Single connection without running migrations, for one-off admin commands
+pub async fn connect(database_url: &str) -> Result<SqlitePool> {
+    connect_to_db(database_url, 1, 1).await
+}
+
 pub async fn get_latest_users<'e>(executor: impl SqliteExecutor<'e>) -> Result<Vec<User>> {
     let db_users = sqlx::query_as!(
         DbUser,
@@ -46,14 +82,24 @@ pub async fn get_users_count<'e>(executor: impl SqliteExecutor<'e>) -> Result<i6
     Ok(count)
 }
 
+/// Latest posts matching any of the given topics
 pub async fn get_latest_posts<'e>(
     executor: impl SqliteExecutor<'e>,
+    topic_ids: &BTreeSet<i64>,
 ) -> Result<Vec<PostWithAuthor>> {
+    let topic_ids = serde_json::to_string(topic_ids)?;
+
     let db_posts = sqlx::query_as!(
         DbPostWithAuthor,
         r#"SELECT posts.*, users.aka, users.did FROM posts
         JOIN users ON posts.author_id = users.id
+        WHERE EXISTS (
+            SELECT 1 FROM post_topics
+            WHERE post_topics.post_id = posts.id
+            AND post_topics.topic_id IN (SELECT value FROM json_each(?))
+        )
         ORDER BY created_at DESC LIMIT 20"#,
+        topic_ids,
     )
     .fetch_all(executor)
     .await?;
@@ -93,10 +139,16 @@ pub async fn update_topic_analysis<'e>(
 ) -> Result<()> {
     sqlx::query_scalar!(
         r#"
-        UPDATE topics SET last_analysis = ?, last_analysis_at = ? WHERE id = ?
+        UPDATE topics
+        SET last_analysis = ?, last_analysis_at = ?, last_analysis_since = ?,
+            last_analysis_until = ?, last_analysis_post_count = ?
+        WHERE id = ?
         "#,
         analysis.last_analysis,
         analysis.last_analysis_at,
+        analysis.last_analysis_since,
+        analysis.last_analysis_until,
+        analysis.last_analysis_post_count,
         id,
     )
     .execute(executor)
@@ -119,12 +171,14 @@ pub async fn update_topic<'e>(
         UPDATE topics SET 
             keywords = COALESCE(?, keywords),
             description = COALESCE(?, description),
-            enabled = COALESCE(?, enabled)
+            enabled = COALESCE(?, enabled),
+            visibility = COALESCE(?, visibility)
         WHERE id = ?
         "#,
         keywords,
         topic.description,
         topic.enabled,
+        topic.visibility,
         id,
     )
     .execute(executor)
@@ -136,6 +190,7 @@ pub async fn update_topic<'e>(
 pub async fn create_topic<'e>(
     executor: impl SqliteExecutor<'e>,
     topic: CreateTopic,
+    owner_id: Option<i64>,
 ) -> Result<Topic> {
     let keywords = serde_json::to_value(topic.keywords.clone()).unwrap();
     let slug = slugify(&topic.subject);
@@ -143,14 +198,18 @@ pub async fn create_topic<'e>(
     let result = sqlx::query_as!(
         DbTopic,
         r#"
-            INSERT INTO topics (subject, slug, description, keywords)
-            VALUES (?, ?, ?, ?)
-            RETURNING *
+            INSERT INTO topics (subject, slug, description, keywords, owner_id, visibility)
+            VALUES (?, ?, ?, ?, ?, ?)
+            RETURNING id, created_at, enabled, slug, subject, description, keywords, last_analysis,
+                last_analysis_at, last_analysis_since, last_analysis_until, last_analysis_post_count,
+                owner_id, visibility as "visibility: Visibility"
             "#,
         topic.subject,
         slug,
         topic.description,
         keywords,
+        owner_id,
+        topic.visibility,
     )
     .fetch_one(executor)
     .await?;
@@ -162,7 +221,9 @@ pub async fn get_all_topics<'e>(executor: impl SqliteExecutor<'e>) -> Result<Vec
     let db_topics = sqlx::query_as!(
         DbTopic,
         r#"
-            SELECT *
+            SELECT id, created_at, enabled, slug, subject, description, keywords, last_analysis,
+                last_analysis_at, last_analysis_since, last_analysis_until, last_analysis_post_count,
+                owner_id, visibility as "visibility: Visibility"
             FROM topics
             ORDER BY created_at DESC
             "#,
@@ -179,7 +240,9 @@ pub async fn get_all_enabled_topics<'e>(executor: impl SqliteExecutor<'e>) -> Re
     let db_topics = sqlx::query_as!(
         DbTopic,
         r#"
-            SELECT *
+            SELECT id, created_at, enabled, slug, subject, description, keywords, last_analysis,
+                last_analysis_at, last_analysis_since, last_analysis_until, last_analysis_post_count,
+                owner_id, visibility as "visibility: Visibility"
             FROM topics
             WHERE enabled = 1
             ORDER BY created_at DESC
@@ -199,7 +262,9 @@ pub async fn get_all_topics_with_post_count<'e>(
     let db_topics = sqlx::query_as!(
         DbTopicWithPostCount,
         r#"
-            SELECT topics.*, (
+            SELECT id, created_at, enabled, slug, subject, description, keywords, last_analysis,
+                last_analysis_at, last_analysis_since, last_analysis_until, last_analysis_post_count,
+                owner_id, visibility as "visibility: Visibility", (
                 SELECT COUNT(*) FROM post_topics WHERE post_topics.topic_id = topics.id
             ) as post_count
             FROM topics
@@ -221,7 +286,10 @@ pub async fn get_topic<'e>(executor: impl SqliteExecutor<'e>, id: i64) -> Result
     let topic = sqlx::query_as!(
         DbTopic,
         r#"
-            SELECT * FROM topics WHERE id = ?
+            SELECT id, created_at, enabled, slug, subject, description, keywords, last_analysis,
+                last_analysis_at, last_analysis_since, last_analysis_until, last_analysis_post_count,
+                owner_id, visibility as "visibility: Visibility"
+            FROM topics WHERE id = ?
             "#,
         id,
     )
@@ -267,6 +335,110 @@ pub async fn get_topic_posts<'e>(
     Ok(posts)
 }
 
+/// Texts of the posts of a topic published in `[since, until)`, oldest first
+pub async fn get_topic_analysis_posts<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+) -> Result<Vec<AnalysisPost>> {
+    let posts = sqlx::query_as!(
+        DbAnalysisPost,
+        r#"
+            SELECT posts.id, posts.text, users.aka, users.did FROM posts
+            JOIN post_topics ON posts.id = post_topics.post_id AND post_topics.topic_id = ?
+            JOIN users ON posts.author_id = users.id
+            WHERE CAST(strftime('%s', posts.created_at) AS INTEGER) >= CAST(strftime('%s', ?) AS INTEGER)
+                AND CAST(strftime('%s', posts.created_at) AS INTEGER) < CAST(strftime('%s', ?) AS INTEGER)
+            ORDER BY posts.created_at ASC
+            "#,
+        topic_id,
+        since,
+        until,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(posts.into_iter().map(AnalysisPost::from).collect())
+}
+
+/// Posts of a topic matching the FTS5 `query`, most relevant first
+pub async fn search_topic_posts<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+    query: &str,
+    limit: i64,
+) -> Result<Vec<PostWithAuthor>> {
+    let db_posts = sqlx::query_as!(
+        DbPostWithAuthor,
+        r#"
+            SELECT posts.*, users.aka, users.did FROM posts_fts
+            JOIN posts ON posts_fts.rowid = posts.id
+            JOIN post_topics ON posts.id = post_topics.post_id AND post_topics.topic_id = ?
+            JOIN users ON posts.author_id = users.id
+            WHERE posts_fts MATCH ?
+            ORDER BY bm25(posts_fts), posts.id DESC
+            LIMIT ?
+            "#,
+        topic_id,
+        query,
+        limit,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(db_posts.into_iter().map(PostWithAuthor::from).collect())
+}
+
+/// Same window as [`get_topic_analysis_posts`], unlike the rollups which cap creation times to now
+pub async fn count_topic_posts_published_between<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+) -> Result<i64> {
+    let count = sqlx::query_scalar!(
+        r#"
+            SELECT COUNT(*) FROM posts
+            JOIN post_topics ON posts.id = post_topics.post_id AND post_topics.topic_id = ?
+            WHERE CAST(strftime('%s', posts.created_at) AS INTEGER) >= CAST(strftime('%s', ?) AS INTEGER)
+                AND CAST(strftime('%s', posts.created_at) AS INTEGER) < CAST(strftime('%s', ?) AS INTEGER)
+            "#,
+        topic_id,
+        since,
+        until,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(count)
+}
+
+pub async fn get_latest_topic_posts<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+    limit: i64,
+) -> Result<Vec<PostWithAuthor>> {
+    let db_posts = sqlx::query_as!(
+        DbPostWithAuthor,
+        r#"
+            SELECT posts.*, users.aka, users.did FROM posts
+            JOIN users ON posts.author_id = users.id
+            JOIN post_topics ON posts.id = post_topics.post_id AND post_topics.topic_id = ?
+            ORDER BY posts.id DESC
+            LIMIT ?
+            "#,
+        topic_id,
+        limit,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    let posts = db_posts.into_iter().map(PostWithAuthor::from).collect();
+
+    Ok(posts)
+}
+
 pub async fn create_post<'e>(executor: impl SqliteExecutor<'e>, post: CreatePost) -> Result<Post> {
     let langs = serde_json::to_vec(&post.langs).unwrap();
     let urls = serde_json::to_vec(&post.urls).unwrap();
@@ -275,8 +447,8 @@ pub async fn create_post<'e>(executor: impl SqliteExecutor<'e>, post: CreatePost
     let post = sqlx::query_as!(
         DbPost,
         r#"
-            INSERT INTO posts (cid, rkey, created_at, text, langs, urls, tags, author_id)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO posts (cid, rkey, created_at, text, langs, urls, tags, author_id, sentiment)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
             RETURNING *
             "#,
         post.cid,
@@ -287,6 +459,7 @@ pub async fn create_post<'e>(executor: impl SqliteExecutor<'e>, post: CreatePost
         urls,
         tags,
         post.author_id,
+        post.sentiment,
     )
     .fetch_one(executor)
     .await?;
@@ -316,6 +489,10 @@ pub async fn link_mentions_to_post<'e>(
     post_id: i64,
     mentions: Vec<i64>,
 ) -> Result<()> {
+    if mentions.is_empty() {
+        return Ok(());
+    }
+
     let mut query_builder = QueryBuilder::new("INSERT INTO post_mentions (post_id, user_id) ");
 
     query_builder.push_values(mentions, |mut b, mention| {
@@ -379,7 +556,7 @@ async fn connect_to_db(
     Ok(pool)
 }
 
-async fn run_migrations(pool: &SqlitePool) -> Result<()> {
+pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
     info!("Running migrations");
     sqlx::migrate!("./migrations").run(pool).await?;
 
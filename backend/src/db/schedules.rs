@@ -0,0 +1,176 @@
+use chrono::{NaiveDateTime, NaiveTime};
+use chrono_tz::Tz;
+use sqlx::SqliteExecutor;
+
+use crate::{
+    Result,
+    models::schedule::{AnalysisSchedule, Frequency, ScheduleStatus, UpsertAnalysisSchedule},
+};
+
+pub async fn get_analysis_schedules<'e>(
+    executor: impl SqliteExecutor<'e>,
+) -> Result<Vec<AnalysisSchedule>> {
+    let schedules = sqlx::query_as!(
+        AnalysisSchedule,
+        r#"
+            SELECT topic_id, created_at, created_by, enabled as "enabled: bool",
+                frequency as "frequency: Frequency", time as "time: NaiveTime", timezone,
+                next_run_at, last_run_at, last_status as "last_status: ScheduleStatus",
+                last_error, last_analysis_id
+            FROM analysis_schedules
+            ORDER BY next_run_at
+            "#,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(schedules)
+}
+
+pub async fn get_analysis_schedule<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+) -> Result<Option<AnalysisSchedule>> {
+    let schedule = sqlx::query_as!(
+        AnalysisSchedule,
+        r#"
+            SELECT topic_id, created_at, created_by, enabled as "enabled: bool",
+                frequency as "frequency: Frequency", time as "time: NaiveTime", timezone,
+                next_run_at, last_run_at, last_status as "last_status: ScheduleStatus",
+                last_error, last_analysis_id
+            FROM analysis_schedules
+            WHERE topic_id = ?
+            "#,
+        topic_id,
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(schedule)
+}
+
+/// Enabled schedules of enabled topics whose next run is at or before `now`
+pub async fn get_due_analysis_schedules<'e>(
+    executor: impl SqliteExecutor<'e>,
+    now: NaiveDateTime,
+) -> Result<Vec<AnalysisSchedule>> {
+    let schedules = sqlx::query_as!(
+        AnalysisSchedule,
+        r#"
+            SELECT analysis_schedules.topic_id, analysis_schedules.created_at,
+                analysis_schedules.created_by, analysis_schedules.enabled as "enabled: bool",
+                frequency as "frequency: Frequency", time as "time: NaiveTime", timezone,
+                next_run_at, last_run_at, last_status as "last_status: ScheduleStatus",
+                last_error, last_analysis_id
+            FROM analysis_schedules
+            JOIN topics ON topics.id = analysis_schedules.topic_id
+            WHERE analysis_schedules.enabled AND topics.enabled AND next_run_at <= ?
+            ORDER BY next_run_at
+            "#,
+        now,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(schedules)
+}
+
+pub async fn upsert_analysis_schedule<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+    created_by: Option<i64>,
+    schedule: &UpsertAnalysisSchedule,
+    timezone: Tz,
+    next_run_at: NaiveDateTime,
+) -> Result<AnalysisSchedule> {
+    let timezone = timezone.name();
+
+    let schedule = sqlx::query_as!(
+        AnalysisSchedule,
+        r#"
+            INSERT INTO analysis_schedules (topic_id, created_by, enabled, frequency, time, timezone,
+                next_run_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (topic_id) DO UPDATE SET enabled = excluded.enabled,
+                frequency = excluded.frequency, time = excluded.time,
+                timezone = excluded.timezone, next_run_at = excluded.next_run_at
+            RETURNING topic_id, created_at, created_by, enabled as "enabled: bool",
+                frequency as "frequency: Frequency", time as "time: NaiveTime", timezone,
+                next_run_at, last_run_at, last_status as "last_status: ScheduleStatus",
+                last_error, last_analysis_id
+            "#,
+        topic_id,
+        created_by,
+        schedule.enabled,
+        schedule.frequency,
+        schedule.time,
+        timezone,
+        next_run_at,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(schedule)
+}
+
+pub async fn delete_analysis_schedule<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+) -> Result<bool> {
+    let result = sqlx::query!(
+        r#"DELETE FROM analysis_schedules WHERE topic_id = ?"#,
+        topic_id,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Marks the run as started, moving the schedule to its next run so that it isn't picked again
+pub async fn start_analysis_schedule_run<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+    now: NaiveDateTime,
+    next_run_at: NaiveDateTime,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+            UPDATE analysis_schedules
+            SET last_run_at = ?, last_status = 'running', last_error = NULL, next_run_at = ?
+            WHERE topic_id = ?
+            "#,
+        now,
+        next_run_at,
+        topic_id,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn finish_analysis_schedule_run<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+    status: ScheduleStatus,
+    error: Option<String>,
+    analysis_id: Option<i64>,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+            UPDATE analysis_schedules
+            SET last_status = ?, last_error = ?,
+                last_analysis_id = COALESCE(?, last_analysis_id)
+            WHERE topic_id = ?
+            "#,
+        status,
+        error,
+        analysis_id,
+        topic_id,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use chrono::NaiveDateTime;
+use sqlx::SqliteExecutor;
+
+use crate::{
+    Result,
+    models::session::{CreateSession, Session},
+};
+
+pub async fn create_session<'e>(
+    executor: impl SqliteExecutor<'e>,
+    session: CreateSession,
+) -> Result<Session> {
+    let session = sqlx::query_as!(
+        Session,
+        r#"
+            INSERT INTO sessions (token_hash, account_id, expires_at, user_agent, ip, csrf_token)
+            VALUES (?, ?, ?, ?, ?, ?)
+            RETURNING id, account_id, created_at, last_seen_at, expires_at, user_agent, ip,
+                csrf_token
+            "#,
+        session.token_hash,
+        session.account_id,
+        session.expires_at,
+        session.user_agent,
+        session.ip,
+        session.csrf_token,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(session)
+}
+
+pub async fn get_valid_session<'e>(
+    executor: impl SqliteExecutor<'e>,
+    token_hash: &str,
+    now: NaiveDateTime,
+) -> Result<Option<Session>> {
+    let session = sqlx::query_as!(
+        Session,
+        r#"
+            SELECT id, account_id, created_at, last_seen_at, expires_at, user_agent, ip,
+                csrf_token
+            FROM sessions
+            WHERE token_hash = ? AND expires_at > ?
+            "#,
+        token_hash,
+        now,
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(session)
+}
+
+pub async fn get_account_sessions<'e>(
+    executor: impl SqliteExecutor<'e>,
+    account_id: i64,
+    now: NaiveDateTime,
+) -> Result<Vec<Session>> {
+    let sessions = sqlx::query_as!(
+        Session,
+        r#"
+            SELECT id, account_id, created_at, last_seen_at, expires_at, user_agent, ip,
+                csrf_token
+            FROM sessions
+            WHERE account_id = ? AND expires_at > ?
+            ORDER BY last_seen_at DESC
+            "#,
+        account_id,
+        now,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(sessions)
+}
+
+pub async fn touch_session<'e>(
+    executor: impl SqliteExecutor<'e>,
+    id: i64,
+    last_seen_at: NaiveDateTime,
+    expires_at: NaiveDateTime,
+) -> Result<()> {
+    sqlx::query!(
+        r#"UPDATE sessions SET last_seen_at = ?, expires_at = ? WHERE id = ?"#,
+        last_seen_at,
+        expires_at,
+        id,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_account_session<'e>(
+    executor: impl SqliteExecutor<'e>,
+    account_id: i64,
+    id: i64,
+) -> Result<bool> {
+    let result = sqlx::query!(
+        r#"DELETE FROM sessions WHERE account_id = ? AND id = ?"#,
+        account_id,
+        id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn delete_session_by_token_hash<'e>(
+    executor: impl SqliteExecutor<'e>,
+    token_hash: &str,
+) -> Result<()> {
+    sqlx::query!(r#"DELETE FROM sessions WHERE token_hash = ?"#, token_hash)
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
+
+/// Deletes every session of an account except `kept_id`, if any
+pub async fn delete_account_sessions<'e>(
+    executor: impl SqliteExecutor<'e>,
+    account_id: i64,
+    kept_id: Option<i64>,
+) -> Result<u64> {
+    let result = sqlx::query!(
+        r#"DELETE FROM sessions WHERE account_id = ? AND id IS NOT ?"#,
+        account_id,
+        kept_id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn delete_expired_sessions<'e>(
+    executor: impl SqliteExecutor<'e>,
+    now: NaiveDateTime,
+) -> Result<u64> {
+    let result = sqlx::query!(r#"DELETE FROM sessions WHERE expires_at <= ?"#, now)
+        .execute(executor)
+        .await?;
+
+    Ok(result.rows_affected())
+}
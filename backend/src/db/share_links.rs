@@ -0,0 +1,113 @@
+use chrono::NaiveDateTime;
+use sqlx::SqliteExecutor;
+
+use crate::{Result, models::share_link::ShareLink};
+
+pub async fn create_share_link<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+    created_by: Option<i64>,
+    name: &str,
+    token_hash: &str,
+    expires_at: Option<NaiveDateTime>,
+) -> Result<ShareLink> {
+    let link = sqlx::query_as!(
+        ShareLink,
+        r#"
+            INSERT INTO share_links (topic_id, created_by, name, token_hash, expires_at)
+            VALUES (?, ?, ?, ?, ?)
+            RETURNING id, created_at, topic_id, created_by, name, expires_at, last_used_at,
+                revoked_at
+            "#,
+        topic_id,
+        created_by,
+        name,
+        token_hash,
+        expires_at,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(link)
+}
+
+pub async fn get_topic_share_links<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+) -> Result<Vec<ShareLink>> {
+    let links = sqlx::query_as!(
+        ShareLink,
+        r#"
+            SELECT id, created_at, topic_id, created_by, name, expires_at, last_used_at, revoked_at
+            FROM share_links
+            WHERE topic_id = ?
+            ORDER BY created_at DESC
+            "#,
+        topic_id,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(links)
+}
+
+/// Link matching the hash which is neither revoked nor expired
+pub async fn get_valid_share_link<'e>(
+    executor: impl SqliteExecutor<'e>,
+    token_hash: &str,
+    now: NaiveDateTime,
+) -> Result<Option<ShareLink>> {
+    let link = sqlx::query_as!(
+        ShareLink,
+        r#"
+            SELECT id, created_at, topic_id, created_by, name, expires_at, last_used_at, revoked_at
+            FROM share_links
+            WHERE token_hash = ? AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > ?)
+            "#,
+        token_hash,
+        now,
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(link)
+}
+
+pub async fn update_share_link_last_used<'e>(
+    executor: impl SqliteExecutor<'e>,
+    id: i64,
+    now: NaiveDateTime,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+            UPDATE share_links SET last_used_at = ? WHERE id = ?
+            "#,
+        now,
+        id,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn revoke_share_link<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+    id: i64,
+    now: NaiveDateTime,
+) -> Result<bool> {
+    let result = sqlx::query!(
+        r#"
+            UPDATE share_links SET revoked_at = ?
+            WHERE id = ? AND topic_id = ? AND revoked_at IS NULL
+            "#,
+        now,
+        id,
+        topic_id,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
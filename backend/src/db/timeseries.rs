@@ -0,0 +1,148 @@
+use chrono::NaiveDateTime;
+use sqlx::{QueryBuilder, SqliteExecutor};
+
+use crate::{
+    Result,
+    models::timeseries::{Bucket, DbRollup, DbSentimentRollup, Dimension, RollupIncrement},
+};
+
+pub async fn increment_topic_post_rollups<'e>(
+    executor: impl SqliteExecutor<'e>,
+    increments: Vec<RollupIncrement>,
+) -> Result<()> {
+    if increments.is_empty() {
+        return Ok(());
+    }
+
+    let mut query_builder = QueryBuilder::new(
+        "INSERT INTO topic_post_rollups (topic_id, bucket, bucket_start, dimension, dimension_value, count, sentiment_sum) ",
+    );
+
+    query_builder.push_values(increments, |mut b, increment| {
+        b.push_bind(increment.topic_id)
+            .push_bind(increment.bucket.as_str())
+            .push_bind(increment.bucket_start)
+            .push_bind(increment.dimension.as_str())
+            .push_bind(increment.dimension_value)
+            .push_bind(1)
+            .push_bind(increment.sentiment_sum);
+    });
+
+    let query = query_builder
+        .push(
+            " ON CONFLICT(topic_id, bucket, dimension, dimension_value, bucket_start) DO UPDATE SET count = count + 1, sentiment_sum = sentiment_sum + excluded.sentiment_sum",
+        )
+        .build();
+    query.execute(executor).await?;
+
+    Ok(())
+}
+
+pub async fn count_topic_posts_between<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<i64> {
+    let bucket = Bucket::Minute.as_str();
+    let dimension = Dimension::Total.as_str();
+
+    let count = sqlx::query_scalar!(
+        r#"
+            SELECT COALESCE(SUM(count), 0) as "count!: i64"
+            FROM topic_post_rollups
+            WHERE topic_id = ? AND bucket = ? AND dimension = ?
+                AND bucket_start >= ? AND bucket_start < ?
+            "#,
+        topic_id,
+        bucket,
+        dimension,
+        from,
+        to,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(count)
+}
+
+pub async fn get_topic_post_rollups<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+    bucket: Bucket,
+    dimension: Dimension,
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+) -> Result<Vec<DbRollup>> {
+    let bucket = bucket.as_str();
+    let dimension = dimension.as_str();
+
+    let rollups = sqlx::query_as!(
+        DbRollup,
+        r#"
+            SELECT bucket_start, dimension_value, count
+            FROM topic_post_rollups
+            WHERE topic_id = ? AND bucket = ? AND dimension = ?
+                AND bucket_start >= ? AND bucket_start < ?
+            ORDER BY bucket_start
+            "#,
+        topic_id,
+        bucket,
+        dimension,
+        since,
+        until,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rollups)
+}
+
+pub async fn get_topic_sentiment_rollups<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+    bucket: Bucket,
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+) -> Result<Vec<DbSentimentRollup>> {
+    let bucket = bucket.as_str();
+    let dimension = Dimension::Sentiment.as_str();
+
+    let rollups = sqlx::query_as!(
+        DbSentimentRollup,
+        r#"
+            SELECT bucket_start, dimension_value, count, sentiment_sum
+            FROM topic_post_rollups
+            WHERE topic_id = ? AND bucket = ? AND dimension = ?
+                AND bucket_start >= ? AND bucket_start < ?
+            ORDER BY bucket_start
+            "#,
+        topic_id,
+        bucket,
+        dimension,
+        since,
+        until,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rollups)
+}
+
+pub async fn delete_topic_post_rollups_before<'e>(
+    executor: impl SqliteExecutor<'e>,
+    bucket: Bucket,
+    before: NaiveDateTime,
+) -> Result<u64> {
+    let bucket = bucket.as_str();
+
+    let result = sqlx::query!(
+        r#"DELETE FROM topic_post_rollups WHERE bucket = ? AND bucket_start < ?"#,
+        bucket,
+        before,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
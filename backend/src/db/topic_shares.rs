@@ -0,0 +1,91 @@
+use sqlx::SqliteExecutor;
+
+use crate::{
+    Result,
+    models::topic::{ShareRole, TopicShare},
+};
+
+pub async fn get_topic_shares<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+) -> Result<Vec<TopicShare>> {
+    let shares = sqlx::query_as!(
+        TopicShare,
+        r#"
+            SELECT topic_id, account_id, accounts.username, topic_shares.created_at,
+                topic_shares.role as "role: ShareRole"
+            FROM topic_shares
+            JOIN accounts ON topic_shares.account_id = accounts.id
+            WHERE topic_id = ?
+            ORDER BY accounts.username
+            "#,
+        topic_id,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(shares)
+}
+
+/// Role of each topic shared with the account
+pub async fn get_account_topic_shares<'e>(
+    executor: impl SqliteExecutor<'e>,
+    account_id: i64,
+) -> Result<Vec<(i64, ShareRole)>> {
+    let shares = sqlx::query!(
+        r#"
+            SELECT topic_id, role as "role: ShareRole"
+            FROM topic_shares
+            WHERE account_id = ?
+            "#,
+        account_id,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(shares
+        .into_iter()
+        .map(|share| (share.topic_id, share.role))
+        .collect())
+}
+
+pub async fn upsert_topic_share<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+    account_id: i64,
+    role: ShareRole,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+            INSERT INTO topic_shares (topic_id, account_id, role)
+            VALUES (?, ?, ?)
+            ON CONFLICT (topic_id, account_id) DO UPDATE SET role = excluded.role
+            "#,
+        topic_id,
+        account_id,
+        role,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_topic_share<'e>(
+    executor: impl SqliteExecutor<'e>,
+    topic_id: i64,
+    account_id: i64,
+) -> Result<bool> {
+    let result = sqlx::query!(
+        r#"
+            DELETE FROM topic_shares
+            WHERE topic_id = ? AND account_id = ?
+            "#,
+        topic_id,
+        account_id,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
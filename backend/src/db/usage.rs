@@ -0,0 +1,90 @@
+use chrono::NaiveDateTime;
+use sqlx::SqliteExecutor;
+
+use crate::{
+    Result,
+    models::usage::{CreateLlmCall, LlmCallOutcome, LlmPurpose, UsageRow},
+};
+
+pub async fn create_llm_call<'e>(
+    executor: impl SqliteExecutor<'e>,
+    call: CreateLlmCall,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+            INSERT INTO llm_calls (purpose, model, prompt_tokens, output_tokens, cost, latency_ms,
+                outcome, error, account_id, topic_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        call.context.purpose,
+        call.model,
+        call.prompt_tokens,
+        call.output_tokens,
+        call.cost,
+        call.latency_ms,
+        call.outcome,
+        call.error,
+        call.context.account_id,
+        call.context.topic_id,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Tokens and cost of the LLM calls made since `since`
+pub async fn get_llm_usage_since<'e>(
+    executor: impl SqliteExecutor<'e>,
+    since: NaiveDateTime,
+) -> Result<(i64, f64)> {
+    let usage = sqlx::query!(
+        r#"
+            SELECT
+                COALESCE(SUM(prompt_tokens + output_tokens), 0) AS "tokens!: i64",
+                COALESCE(SUM(cost), 0.0) AS "cost!: f64"
+            FROM llm_calls
+            WHERE created_at >= ?
+            "#,
+        since,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok((usage.tokens, usage.cost))
+}
+
+/// Usage of the LLM calls made in `[since, until)`, grouped by day and by what they have in common
+pub async fn get_llm_usage<'e>(
+    executor: impl SqliteExecutor<'e>,
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+) -> Result<Vec<UsageRow>> {
+    let rows = sqlx::query_as!(
+        UsageRow,
+        r#"
+            SELECT
+                date(created_at) AS "day!: chrono::NaiveDate",
+                purpose AS "purpose: LlmPurpose",
+                model,
+                outcome AS "outcome: LlmCallOutcome",
+                account_id,
+                topic_id,
+                COUNT(*) AS "calls!: i64",
+                SUM(prompt_tokens) AS "prompt_tokens!: i64",
+                SUM(output_tokens) AS "output_tokens!: i64",
+                SUM(cost) AS "cost!: f64",
+                SUM(latency_ms) AS "latency_ms!: i64"
+            FROM llm_calls
+            WHERE created_at >= ? AND created_at < ?
+            GROUP BY date(created_at), purpose, model, outcome, account_id, topic_id
+            ORDER BY date(created_at)
+            "#,
+        since,
+        until,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows)
+}
@@ -0,0 +1,289 @@
+use sqlx::SqliteExecutor;
+use std::collections::BTreeSet;
+
+use crate::{
+    Result,
+    models::{
+        post::{AnalysisPost, DbAnalysisPost, DbPostWithAuthor, PostWithAuthor},
+        user::{DbUser, DbUserMentioner, LastUserAnalysis, User, UserMentioner, UserTopic},
+    },
+};
+
+pub async fn get_user_by_did<'e>(
+    executor: impl SqliteExecutor<'e>,
+    did: &str,
+) -> Result<Option<User>> {
+    let user = sqlx::query_as!(DbUser, r#"SELECT * FROM users WHERE did = ?"#, did)
+        .fetch_optional(executor)
+        .await?;
+
+    Ok(user.map(User::from))
+}
+
+/// Posts of the user matching any of the given topics
+pub async fn count_user_posts<'e>(
+    executor: impl SqliteExecutor<'e>,
+    user_id: i64,
+    topic_ids: &BTreeSet<i64>,
+) -> Result<i64> {
+    let topic_ids = serde_json::to_string(topic_ids)?;
+
+    let count = sqlx::query_scalar!(
+        r#"
+            SELECT COUNT(*) FROM posts
+            WHERE author_id = ? AND EXISTS (
+                SELECT 1 FROM post_topics
+                WHERE post_topics.post_id = posts.id
+                AND post_topics.topic_id IN (SELECT value FROM json_each(?))
+            )
+            "#,
+        user_id,
+        topic_ids,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(count)
+}
+
+/// Posts of other users mentioning the user and matching any of the given topics
+pub async fn count_user_mentions<'e>(
+    executor: impl SqliteExecutor<'e>,
+    user_id: i64,
+    topic_ids: &BTreeSet<i64>,
+) -> Result<i64> {
+    let topic_ids = serde_json::to_string(topic_ids)?;
+
+    let count = sqlx::query_scalar!(
+        r#"
+            SELECT COUNT(*) FROM post_mentions
+            JOIN posts ON post_mentions.post_id = posts.id
+            WHERE post_mentions.user_id = ? AND posts.author_id != post_mentions.user_id
+            AND EXISTS (
+                SELECT 1 FROM post_topics
+                WHERE post_topics.post_id = posts.id
+                AND post_topics.topic_id IN (SELECT value FROM json_each(?))
+            )
+            "#,
+        user_id,
+        topic_ids,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(count)
+}
+
+/// Posts of the user matching any of the given topics, latest first
+pub async fn get_user_posts<'e>(
+    executor: impl SqliteExecutor<'e>,
+    user_id: i64,
+    topic_ids: &BTreeSet<i64>,
+    page: i64,
+    per_page: i64,
+) -> Result<Vec<PostWithAuthor>> {
+    let topic_ids = serde_json::to_string(topic_ids)?;
+    let offset = (page - 1) * per_page;
+
+    let db_posts = sqlx::query_as!(
+        DbPostWithAuthor,
+        r#"
+            SELECT posts.*, users.aka, users.did FROM posts
+            JOIN users ON posts.author_id = users.id
+            WHERE posts.author_id = ? AND EXISTS (
+                SELECT 1 FROM post_topics
+                WHERE post_topics.post_id = posts.id
+                AND post_topics.topic_id IN (SELECT value FROM json_each(?))
+            )
+            ORDER BY posts.created_at DESC, posts.id DESC
+            LIMIT ? OFFSET ?
+            "#,
+        user_id,
+        topic_ids,
+        per_page,
+        offset,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(db_posts.into_iter().map(PostWithAuthor::from).collect())
+}
+
+/// Texts of the posts of the user matching any of the given topics, oldest first
+pub async fn get_user_analysis_posts<'e>(
+    executor: impl SqliteExecutor<'e>,
+    user_id: i64,
+    topic_ids: &BTreeSet<i64>,
+) -> Result<Vec<AnalysisPost>> {
+    let topic_ids = serde_json::to_string(topic_ids)?;
+
+    let posts = sqlx::query_as!(
+        DbAnalysisPost,
+        r#"
+            SELECT posts.id, posts.text, users.aka, users.did FROM posts
+            JOIN users ON posts.author_id = users.id
+            WHERE posts.author_id = ? AND EXISTS (
+                SELECT 1 FROM post_topics
+                WHERE post_topics.post_id = posts.id
+                AND post_topics.topic_id IN (SELECT value FROM json_each(?))
+            )
+            ORDER BY posts.created_at ASC
+            "#,
+        user_id,
+        topic_ids,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(posts.into_iter().map(AnalysisPost::from).collect())
+}
+
+/// Given topics the user posted in or was mentioned in by others, most active first
+pub async fn get_user_topics<'e>(
+    executor: impl SqliteExecutor<'e>,
+    user_id: i64,
+    topic_ids: &BTreeSet<i64>,
+) -> Result<Vec<UserTopic>> {
+    let topic_ids = serde_json::to_string(topic_ids)?;
+
+    let topics = sqlx::query_as!(
+        UserTopic,
+        r#"
+            SELECT
+                topics.id AS "id!",
+                topics.slug,
+                topics.subject,
+                COALESCE(SUM(activity.authored), 0) AS "post_count!: i64",
+                COALESCE(SUM(1 - activity.authored), 0) AS "mention_count!: i64"
+            FROM (
+                SELECT posts.id AS post_id, 1 AS authored FROM posts
+                WHERE posts.author_id = ?
+                UNION ALL
+                SELECT post_mentions.post_id, 0 FROM post_mentions
+                JOIN posts ON post_mentions.post_id = posts.id
+                WHERE post_mentions.user_id = ? AND posts.author_id != post_mentions.user_id
+            ) AS activity
+            JOIN post_topics ON activity.post_id = post_topics.post_id
+            JOIN topics ON post_topics.topic_id = topics.id
+            WHERE topics.id IN (SELECT value FROM json_each(?))
+            GROUP BY topics.id
+            ORDER BY COUNT(*) DESC, topics.id
+            "#,
+        user_id,
+        user_id,
+        topic_ids,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(topics)
+}
+
+/// Authors of the posts mentioning the user and matching any of the given topics, those
+/// mentioning it the most first
+pub async fn get_user_mentioners<'e>(
+    executor: impl SqliteExecutor<'e>,
+    user_id: i64,
+    topic_ids: &BTreeSet<i64>,
+    limit: i64,
+) -> Result<Vec<UserMentioner>> {
+    let topic_ids = serde_json::to_string(topic_ids)?;
+
+    let mentioners = sqlx::query_as!(
+        DbUserMentioner,
+        r#"
+            SELECT
+                users.did AS "did!",
+                users.aka AS "aka!",
+                COUNT(*) AS "mention_count!: i64",
+                MAX(posts.created_at) AS "last_mentioned_at!: String"
+            FROM post_mentions
+            JOIN posts ON post_mentions.post_id = posts.id
+            JOIN users ON posts.author_id = users.id
+            WHERE post_mentions.user_id = ? AND posts.author_id != post_mentions.user_id
+            AND EXISTS (
+                SELECT 1 FROM post_topics
+                WHERE post_topics.post_id = posts.id
+                AND post_topics.topic_id IN (SELECT value FROM json_each(?))
+            )
+            GROUP BY users.id
+            ORDER BY COUNT(*) DESC, MAX(posts.created_at) DESC
+            LIMIT ?
+            "#,
+        user_id,
+        topic_ids,
+        limit,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(mentioners.into_iter().map(UserMentioner::from).collect())
+}
+
+/// Latest analysis of the user requested by the account, or while authentication was disabled
+/// when `created_by` is `None`
+pub async fn get_last_user_analysis<'e>(
+    executor: impl SqliteExecutor<'e>,
+    user_id: i64,
+    created_by: Option<i64>,
+) -> Result<Option<LastUserAnalysis>> {
+    let analysis = sqlx::query_as!(
+        LastUserAnalysis,
+        r#"
+            SELECT result AS analysis, created_at AS analyzed_at, matched_post_count, post_count
+            FROM user_analyses
+            WHERE user_id = ? AND created_by IS ?
+            ORDER BY created_at DESC, id DESC
+            LIMIT 1
+            "#,
+        user_id,
+        created_by,
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(analysis)
+}
+
+pub async fn delete_user_analyses<'e>(
+    executor: impl SqliteExecutor<'e>,
+    user_id: i64,
+    created_by: Option<i64>,
+) -> Result<()> {
+    sqlx::query!(
+        r#"DELETE FROM user_analyses WHERE user_id = ? AND created_by IS ?"#,
+        user_id,
+        created_by,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn create_user_analysis<'e>(
+    executor: impl SqliteExecutor<'e>,
+    user_id: i64,
+    created_by: Option<i64>,
+    analysis: &str,
+    matched_post_count: i64,
+    post_count: i64,
+) -> Result<LastUserAnalysis> {
+    let analysis = sqlx::query_as!(
+        LastUserAnalysis,
+        r#"
+            INSERT INTO user_analyses (user_id, created_by, matched_post_count, post_count, result)
+            VALUES (?, ?, ?, ?, ?)
+            RETURNING result AS analysis, created_at AS analyzed_at, matched_post_count, post_count
+            "#,
+        user_id,
+        created_by,
+        matched_post_count,
+        post_count,
+        analysis,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(analysis)
+}
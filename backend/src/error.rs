@@ -1,5 +1,5 @@
 use axum::{
-    http::StatusCode,
+    http::{StatusCode, header},
     response::{IntoResponse, Response},
 };
 use tracing::error;
@@ -10,17 +10,37 @@ pub enum Error {
     Serde(serde_json::Error),
     Sqlx(sqlx::Error),
     SqlxMigrate(sqlx::migrate::MigrateError),
-    Config(figment::Error),
+    Config(Box<figment::Error>),
     AddrParseError(std::net::AddrParseError),
     Io(std::io::Error),
     InvalidHeaderValue(reqwest::header::InvalidHeaderValue),
-    RequestWebSocket(reqwest_websocket::Error),
+    RequestWebSocket(Box<reqwest_websocket::Error>),
     NotFound(String),
     GeminiDisabled,
+    /// Prompt or response blocked by the safety filters of the LLM provider
+    LlmBlocked(String),
+    /// Rate limit or quota of the LLM provider exceeded, with when to retry if known
+    LlmQuotaExceeded(Option<std::time::Duration>),
+    /// Monthly LLM budget exceeded, with the time left until it's reset
+    LlmBudgetExceeded(std::time::Duration),
+    /// Response of the LLM provider which couldn't be understood
+    LlmMalformedResponse(String),
+    /// LLM provider unreachable or failing
+    LlmUnavailable(String),
+    /// Request refused by the LLM provider, e.g. because of an invalid API key
+    LlmRejected(String),
     AuthDisabled,
     InvalidCredentials,
-    Jwt(jsonwebtoken::errors::Error),
     Unauthorized(String),
+    Forbidden(String),
+    Oidc(String),
+    TooManyRequests(std::time::Duration),
+    PasswordHash(argon2::password_hash::Error),
+    BadRequest(String),
+    SmtpDisabled,
+    Smtp(Box<lettre::transport::smtp::Error>),
+    Email(lettre::error::Error),
+    EmailAddress(lettre::address::AddressError),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -51,7 +71,7 @@ impl From<sqlx::Error> for Error {
 
 impl From<figment::Error> for Error {
     fn from(error: figment::Error) -> Self {
-        Self::Config(error)
+        Self::Config(Box::new(error))
     }
 }
 
@@ -81,13 +101,31 @@ impl From<reqwest::header::InvalidHeaderValue> for Error {
 
 impl From<reqwest_websocket::Error> for Error {
     fn from(error: reqwest_websocket::Error) -> Self {
-        Self::RequestWebSocket(error)
+        Self::RequestWebSocket(Box::new(error))
     }
 }
 
-impl From<jsonwebtoken::errors::Error> for Error {
-    fn from(error: jsonwebtoken::errors::Error) -> Self {
-        Self::Jwt(error)
+impl From<argon2::password_hash::Error> for Error {
+    fn from(error: argon2::password_hash::Error) -> Self {
+        Self::PasswordHash(error)
+    }
+}
+
+impl From<lettre::transport::smtp::Error> for Error {
+    fn from(error: lettre::transport::smtp::Error) -> Self {
+        Self::Smtp(Box::new(error))
+    }
+}
+
+impl From<lettre::error::Error> for Error {
+    fn from(error: lettre::error::Error) -> Self {
+        Self::Email(error)
+    }
+}
+
+impl From<lettre::address::AddressError> for Error {
+    fn from(error: lettre::address::AddressError) -> Self {
+        Self::EmailAddress(error)
     }
 }
 
@@ -100,6 +138,47 @@ impl IntoResponse for Error {
             Self::GeminiDisabled => {
                 (StatusCode::SERVICE_UNAVAILABLE, "Gemini is disabled").into_response()
             }
+            Self::LlmBlocked(reason) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Blocked by the safety filters of the model: {reason}"),
+            )
+                .into_response(),
+            Self::LlmQuotaExceeded(Some(retry_after)) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(
+                    header::RETRY_AFTER,
+                    retry_after.as_secs_f64().ceil().to_string(),
+                )],
+                "Quota of the LLM provider exceeded",
+            )
+                .into_response(),
+            Self::LlmQuotaExceeded(None) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Quota of the LLM provider exceeded",
+            )
+                .into_response(),
+            Self::LlmBudgetExceeded(retry_after) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(
+                    header::RETRY_AFTER,
+                    retry_after.as_secs_f64().ceil().to_string(),
+                )],
+                "Monthly LLM budget exceeded",
+            )
+                .into_response(),
+            Self::LlmMalformedResponse(_) => (
+                StatusCode::BAD_GATEWAY,
+                "Invalid response from the LLM provider",
+            )
+                .into_response(),
+            Self::LlmRejected(_) => (
+                StatusCode::BAD_GATEWAY,
+                "Request rejected by the LLM provider",
+            )
+                .into_response(),
+            Self::LlmUnavailable(_) => {
+                (StatusCode::SERVICE_UNAVAILABLE, "LLM provider unavailable").into_response()
+            }
             Self::AuthDisabled => (
                 StatusCode::SERVICE_UNAVAILABLE,
                 "Authentication is disabled",
@@ -109,6 +188,18 @@ impl IntoResponse for Error {
                 (StatusCode::UNAUTHORIZED, "Invalid credentials").into_response()
             }
             Self::Unauthorized(_) => (StatusCode::UNAUTHORIZED, "".to_string()).into_response(),
+            Self::TooManyRequests(retry_after) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(
+                    header::RETRY_AFTER,
+                    retry_after.as_secs_f64().ceil().to_string(),
+                )],
+                "Too many failed login attempts",
+            )
+                .into_response(),
+            Self::Oidc(message) => (StatusCode::UNAUTHORIZED, message).into_response(),
+            Self::Forbidden(_) => (StatusCode::FORBIDDEN, "".to_string()).into_response(),
+            Self::BadRequest(message) => (StatusCode::BAD_REQUEST, message).into_response(),
             _ => (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()).into_response(),
         }
     }
@@ -0,0 +1,323 @@
+use atom_syndication::{
+    ContentBuilder, EntryBuilder, FeedBuilder, FixedDateTime, LinkBuilder, PersonBuilder, Text,
+};
+use chrono::{DateTime, Utc};
+use rss::{ChannelBuilder, GuidBuilder, ItemBuilder};
+use serde::Serialize;
+
+use crate::models::{post::PostWithAuthor, topic::Topic};
+
+const TITLE_MAX_CHARS: usize = 80;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeedFormat {
+    Atom,
+    Rss,
+    Json,
+}
+
+impl FeedFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Atom => "application/atom+xml; charset=utf-8",
+            Self::Rss => "application/rss+xml; charset=utf-8",
+            Self::Json => "application/feed+json; charset=utf-8",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Atom => "atom",
+            Self::Rss => "rss",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Everything needed to render a topic's feed, regardless of the output format.
+pub struct TopicFeed<'a> {
+    pub topic: &'a Topic,
+    pub posts: &'a [PostWithAuthor],
+    /// Origin of the frontend, used to link back to the topic's page
+    pub home_origin: &'a str,
+}
+
+impl TopicFeed<'_> {
+    pub fn render(&self, format: FeedFormat) -> crate::Result<String> {
+        match format {
+            FeedFormat::Atom => Ok(self.to_atom()),
+            FeedFormat::Rss => Ok(self.to_rss()),
+            FeedFormat::Json => Ok(serde_json::to_string(&self.to_json())?),
+        }
+    }
+
+    fn home_page_url(&self) -> String {
+        format!(
+            "{}/topics/{}",
+            self.home_origin.trim_end_matches('/'),
+            self.topic.slug
+        )
+    }
+
+    fn feed_url(&self, format: FeedFormat) -> String {
+        format!(
+            "{}/api/v1/topics/{}/feed.{}",
+            self.home_origin.trim_end_matches('/'),
+            self.topic.slug,
+            format.extension()
+        )
+    }
+
+    fn title(&self) -> String {
+        format!("Bluflare - {}", self.topic.subject)
+    }
+
+    fn updated(&self) -> FixedDateTime {
+        self.posts
+            .iter()
+            .map(post_timestamp)
+            .max()
+            .unwrap_or_else(|| self.topic.created_at.and_utc().fixed_offset())
+    }
+
+    fn to_atom(&self) -> String {
+        let entries = self
+            .posts
+            .iter()
+            .map(|post| {
+                let timestamp = post_timestamp(post);
+                let handle = author_handle(post);
+
+                EntryBuilder::default()
+                    .title(Text::plain(entry_title(&post.post.text)))
+                    .id(post_uri(&post.did, &post.post.rkey))
+                    .updated(timestamp)
+                    .published(Some(timestamp))
+                    .author(
+                        PersonBuilder::default()
+                            .name(handle.clone())
+                            .uri(Some(profile_url(&post.did)))
+                            .build(),
+                    )
+                    .link(
+                        LinkBuilder::default()
+                            .href(post_url(&post.did, &post.post.rkey))
+                            .rel("alternate")
+                            .build(),
+                    )
+                    .content(Some(
+                        ContentBuilder::default()
+                            .value(Some(post.post.text.clone()))
+                            .content_type(Some("text".to_string()))
+                            .build(),
+                    ))
+                    .build()
+            })
+            .collect::<Vec<_>>();
+
+        FeedBuilder::default()
+            .title(Text::plain(self.title()))
+            .subtitle(Some(Text::plain(self.topic.description.clone())))
+            .id(self.feed_url(FeedFormat::Atom))
+            .updated(self.updated())
+            .link(
+                LinkBuilder::default()
+                    .href(self.home_page_url())
+                    .rel("alternate")
+                    .build(),
+            )
+            .link(
+                LinkBuilder::default()
+                    .href(self.feed_url(FeedFormat::Atom))
+                    .rel("self")
+                    .build(),
+            )
+            .entries(entries)
+            .build()
+            .to_string()
+    }
+
+    fn to_rss(&self) -> String {
+        let items = self
+            .posts
+            .iter()
+            .map(|post| {
+                let url = post_url(&post.did, &post.post.rkey);
+
+                ItemBuilder::default()
+                    .title(Some(entry_title(&post.post.text)))
+                    .link(Some(url.clone()))
+                    .description(Some(post.post.text.clone()))
+                    .author(Some(author_handle(post)))
+                    .guid(Some(
+                        GuidBuilder::default().value(url).permalink(true).build(),
+                    ))
+                    .pub_date(Some(post_timestamp(post).to_rfc2822()))
+                    .build()
+            })
+            .collect::<Vec<_>>();
+
+        ChannelBuilder::default()
+            .title(self.title())
+            .link(self.home_page_url())
+            .description(self.topic.description.clone())
+            .last_build_date(Some(self.updated().to_rfc2822()))
+            .items(items)
+            .build()
+            .to_string()
+    }
+
+    fn to_json(&self) -> JsonFeed {
+        JsonFeed {
+            version: "https://jsonfeed.org/version/1.1",
+            title: self.title(),
+            home_page_url: self.home_page_url(),
+            feed_url: self.feed_url(FeedFormat::Json),
+            description: self.topic.description.clone(),
+            items: self
+                .posts
+                .iter()
+                .map(|post| JsonFeedItem {
+                    id: post_uri(&post.did, &post.post.rkey),
+                    url: post_url(&post.did, &post.post.rkey),
+                    title: entry_title(&post.post.text),
+                    content_text: post.post.text.clone(),
+                    date_published: post_timestamp(post).to_rfc3339(),
+                    authors: vec![JsonFeedAuthor {
+                        name: author_handle(post),
+                        url: profile_url(&post.did),
+                    }],
+                    tags: post.post.tags.clone(),
+                    language: post.post.langs.first().cloned(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// JSON Feed 1.1, see https://www.jsonfeed.org/version/1.1/
+#[derive(Serialize)]
+struct JsonFeed {
+    version: &'static str,
+    title: String,
+    home_page_url: String,
+    feed_url: String,
+    description: String,
+    items: Vec<JsonFeedItem>,
+}
+
+#[derive(Serialize)]
+struct JsonFeedItem {
+    id: String,
+    url: String,
+    title: String,
+    content_text: String,
+    date_published: String,
+    authors: Vec<JsonFeedAuthor>,
+    tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+}
+
+#[derive(Serialize)]
+struct JsonFeedAuthor {
+    name: String,
+    url: String,
+}
+
+/// Returns the first handle of the author (e.g. `alice.bsky.social`), falling back to its DID
+pub fn author_handle(post: &PostWithAuthor) -> String {
+    post.aka
+        .iter()
+        .find_map(|aka| aka.strip_prefix("at://"))
+        .map(str::to_string)
+        .unwrap_or_else(|| post.did.clone())
+}
+
+pub fn post_url(did: &str, rkey: &str) -> String {
+    format!("https://bsky.app/profile/{did}/post/{rkey}")
+}
+
+fn profile_url(did: &str) -> String {
+    format!("https://bsky.app/profile/{did}")
+}
+
+fn post_uri(did: &str, rkey: &str) -> String {
+    format!("at://{did}/app.bsky.feed.post/{rkey}")
+}
+
+/// Posts' `created_at` come straight from the record and are client provided, so fall back to the
+/// epoch rather than failing the whole feed when one isn't a valid RFC 3339 timestamp.
+fn post_timestamp(post: &PostWithAuthor) -> FixedDateTime {
+    DateTime::parse_from_rfc3339(&post.post.created_at)
+        .unwrap_or_else(|_| DateTime::<Utc>::UNIX_EPOCH.fixed_offset())
+}
+
+fn entry_title(text: &str) -> String {
+    let first_line = text.lines().next().unwrap_or_default().trim();
+
+    if first_line.chars().count() > TITLE_MAX_CHARS {
+        let truncated = first_line.chars().take(TITLE_MAX_CHARS).collect::<String>();
+        format!("{}…", truncated.trim_end())
+    } else {
+        first_line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::post::Post;
+
+    fn post_with_author(aka: Vec<&str>, created_at: &str) -> PostWithAuthor {
+        PostWithAuthor {
+            post: Post {
+                id: 1,
+                cid: "cid".to_string(),
+                rkey: "3lqrkey".to_string(),
+                created_at: created_at.to_string(),
+                text: "Hello world".to_string(),
+                langs: vec!["en".to_string()],
+                urls: vec![],
+                tags: vec![],
+                author_id: 1,
+                sentiment: None,
+            },
+            aka: aka.into_iter().map(str::to_string).collect(),
+            did: "did:plc:abc".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_given_aka_when_author_handle_return_handle_without_scheme() {
+        let post = post_with_author(vec!["at://alice.bsky.social"], "");
+        assert_eq!(author_handle(&post), "alice.bsky.social");
+    }
+
+    #[test]
+    fn test_given_no_aka_when_author_handle_return_did() {
+        let post = post_with_author(vec![], "");
+        assert_eq!(author_handle(&post), "did:plc:abc");
+    }
+
+    #[test]
+    fn test_given_did_and_rkey_when_post_url_return_bsky_app_link() {
+        assert_eq!(
+            post_url("did:plc:abc", "3lqrkey"),
+            "https://bsky.app/profile/did:plc:abc/post/3lqrkey"
+        );
+    }
+
+    #[test]
+    fn test_given_invalid_created_at_when_post_timestamp_return_epoch() {
+        let post = post_with_author(vec![], "not a date");
+        assert_eq!(post_timestamp(&post).timestamp(), 0);
+    }
+
+    #[test]
+    fn test_given_long_text_when_entry_title_return_truncated_first_line() {
+        let text = format!("{}\nsecond line", "a".repeat(100));
+        assert_eq!(entry_title(&text), format!("{}…", "a".repeat(80)));
+        assert_eq!(entry_title("short\nsecond line"), "short");
+    }
+}
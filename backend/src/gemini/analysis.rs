@@ -0,0 +1,253 @@
+use serde_json::{Value, json};
+use std::{collections::HashMap, sync::LazyLock};
+
+use crate::models::post::AnalysisPost;
+
+/// Rough token count of a post in a prompt, Gemini averaging about 4 characters per token, plus
+/// its separator
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4) + 1
+}
+
+/// Posts of a topic reduced to what fits the token budget of an analysis
+#[derive(Debug, PartialEq)]
+pub struct PostSelection {
+    /// Posts to analyze, in their original order, as `[{id}] @{author}: {text}`, reposted texts
+    /// being prefixed by their count
+    pub posts: Vec<String>,
+    /// IDs of the posts to analyze
+    pub post_ids: Vec<i64>,
+    /// Posts matched in the window
+    pub matched: usize,
+    /// Posts left after deduplication
+    pub unique: usize,
+}
+
+impl PostSelection {
+    /// Deduplicates posts, then evenly samples them so that at most `max_posts` and `max_tokens`
+    /// remain, keeping the spread of the discussion over the window
+    pub fn new(posts: Vec<AnalysisPost>, max_posts: usize, max_tokens: usize) -> Self {
+        let matched = posts.len();
+        let unique = deduplicate(posts);
+
+        let mut count = unique.len().min(max_posts);
+        let sampled = loop {
+            let sampled = sample_evenly(&unique, count);
+            let tokens = sampled
+                .iter()
+                .map(|(_, post)| estimate_tokens(post))
+                .sum::<usize>();
+
+            if tokens <= max_tokens || count == 0 {
+                break sampled;
+            }
+
+            count = (count * max_tokens / tokens).min(count - 1);
+        };
+
+        let (post_ids, posts) = sampled.into_iter().unzip();
+
+        Self {
+            posts,
+            post_ids,
+            matched,
+            unique: unique.len(),
+        }
+    }
+}
+
+/// Ignores case, links and whitespace so that bots posting the same text with different links are
+/// counted as one
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .filter(|word| !word.starts_with("http://") && !word.starts_with("https://"))
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// First occurrence of every text, along with the ID of its post
+fn deduplicate(posts: Vec<AnalysisPost>) -> Vec<(i64, String)> {
+    let mut occurrences = HashMap::new();
+    let mut unique = Vec::new();
+
+    for post in posts {
+        let count = occurrences.entry(normalize(&post.text)).or_insert_with(|| {
+            unique.push(post);
+            (unique.len() - 1, 0)
+        });
+        count.1 += 1;
+    }
+
+    let mut counts = vec![1; unique.len()];
+    for (index, count) in occurrences.into_values() {
+        counts[index] = count;
+    }
+
+    unique
+        .into_iter()
+        .zip(counts)
+        .map(|(post, count)| {
+            let reposted = match count {
+                1 => String::new(),
+                count => format!("[posted {count} times] "),
+            };
+            (
+                post.id,
+                format!("[{}] @{}: {reposted}{}", post.id, post.author, post.text),
+            )
+        })
+        .collect()
+}
+
+fn sample_evenly<T: Clone>(posts: &[T], count: usize) -> Vec<T> {
+    if count >= posts.len() {
+        return posts.to_vec();
+    }
+
+    (0..count)
+        .map(|i| posts[i * posts.len() / count].clone())
+        .collect()
+}
+
+/// Consecutive chunks of posts of at most `max_tokens`, a larger post being alone in its chunk
+pub fn chunk(posts: Vec<String>, max_tokens: usize) -> Vec<Vec<String>> {
+    let mut chunks: Vec<Vec<String>> = Vec::new();
+    let mut tokens = 0;
+
+    for post in posts {
+        let post_tokens = estimate_tokens(&post);
+
+        match chunks.last_mut() {
+            Some(chunk) if tokens + post_tokens <= max_tokens => chunk.push(post),
+            _ => {
+                chunks.push(vec![post]);
+                tokens = 0;
+            }
+        }
+        tokens += post_tokens;
+    }
+
+    chunks
+}
+
+/// JSON schema of [`crate::models::analysis::AnalysisResult`], requested from the provider
+pub static RESULT_SCHEMA: LazyLock<Value> = LazyLock::new(|| {
+    let object = |properties: Value| {
+        let required = properties
+            .as_object()
+            .map(|properties| properties.keys().cloned().collect::<Vec<_>>());
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+        })
+    };
+    let string = json!({ "type": "string" });
+    let share = json!({ "type": "number", "minimum": 0, "maximum": 1 });
+
+    object(json!({
+        "summary": string,
+        "sentiment": object(json!({
+            "score": { "type": "number", "minimum": -1, "maximum": 1 },
+            "positive": share,
+            "neutral": share,
+            "negative": share,
+        })),
+        "themes": {
+            "type": "array",
+            "items": object(json!({
+                "name": string,
+                "description": string,
+                "post_ids": { "type": "array", "items": { "type": "integer" } },
+            })),
+        },
+        "notable_accounts": {
+            "type": "array",
+            "items": object(json!({
+                "handle": string,
+                "reason": string,
+            })),
+        },
+        "emerging_terms": { "type": "array", "items": string },
+    }))
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn posts(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(|text| text.to_string()).collect()
+    }
+
+    fn analysis_posts(texts: &[&str]) -> Vec<AnalysisPost> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(id, text)| AnalysisPost {
+                id: id as i64,
+                author: format!("user{id}.bsky.social"),
+                text: text.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_given_reposted_texts_when_select_return_first_occurrence_with_count() {
+        let selection = PostSelection::new(
+            analysis_posts(&[
+                "New model released https://a.example",
+                "Something else",
+                "new  MODEL released https://b.example",
+            ]),
+            10,
+            1000,
+        );
+
+        assert_eq!(
+            selection,
+            PostSelection {
+                posts: posts(&[
+                    "[0] @user0.bsky.social: [posted 2 times] New model released https://a.example",
+                    "[1] @user1.bsky.social: Something else",
+                ]),
+                post_ids: vec![0, 1],
+                matched: 3,
+                unique: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_given_too_many_posts_when_select_return_evenly_sampled_posts_within_budget() {
+        let texts = (0..100).map(|i| format!("post {i:03}")).collect::<Vec<_>>();
+        let texts = analysis_posts(&texts.iter().map(String::as_str).collect::<Vec<_>>());
+
+        let selection = PostSelection::new(texts.clone(), 10, 1000);
+        assert_eq!(selection.posts.len(), 10);
+        assert_eq!(selection.posts[0], "[0] @user0.bsky.social: post 000");
+        assert_eq!(selection.post_ids[9], 90);
+
+        let selection = PostSelection::new(texts, 100, 90);
+        let tokens = selection.posts.iter().map(|post| estimate_tokens(post));
+        assert_eq!(selection.posts.len(), 9);
+        assert!(tokens.sum::<usize>() <= 90);
+        assert_eq!(selection.unique, 100);
+    }
+
+    #[test]
+    fn test_given_posts_when_chunk_return_chunks_within_budget() {
+        let chunks = chunk(posts(&["aaaa", "bbbb", "cccc", &"d".repeat(40)]), 5);
+
+        assert_eq!(
+            chunks,
+            vec![
+                posts(&["aaaa", "bbbb"]),
+                posts(&["cccc"]),
+                vec!["d".repeat(40)],
+            ]
+        );
+    }
+}
@@ -1,96 +1,322 @@
-use axum::http::HeaderMap;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use std::time::Duration;
-use tracing::{info, warn};
+use futures_util::StreamExt;
+use rand_core::{OsRng, RngCore};
+use serde_json::Value;
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    sync::{Mutex, Semaphore},
+    time::Instant,
+};
+use tracing::warn;
 
-use crate::{Result, config};
+use super::providers::{self, DeltaStream, Generation, Provider};
+use crate::{
+    Error, Result, config,
+    metrics::{METRICS, Metrics},
+};
 
 #[derive(Clone)]
 pub struct GenericGeminiClient {
-    client: Client,
-    base_url: String,
+    /// `None` when disabled
+    provider: Option<Arc<dyn Provider>>,
+    model: String,
+    permits: Arc<Semaphore>,
+    rate_limiter: Arc<RateLimiter>,
+    max_retries: u32,
+    retry_base_delay: Duration,
+    max_retry_delay: Duration,
 }
 
 impl GenericGeminiClient {
     pub fn new(config: &config::Gemini) -> Result<Self> {
-        let api_key = std::env::var("BLUFLARE__GEMINI__API_KEY").unwrap_or_else(|_| {
-            warn!("BLUFLARE__GEMINI__API_KEY is not set, gemini will not be used");
-            "".to_string()
-        });
-
-        if api_key.is_empty() || !config.enabled {
-            info!("Gemini client disabled");
-            return Ok(Self {
-                client: Client::new(),
-                base_url: String::new(),
-            });
-        }
+        let provider = providers::new_provider(config)?;
 
-        info!("Initializing Gemini client");
+        Ok(Self {
+            model: provider
+                .as_ref()
+                .map(|provider| provider.model().to_string())
+                .unwrap_or_default(),
+            provider,
+            permits: Arc::new(Semaphore::new(config.max_concurrent_requests.max(1))),
+            rate_limiter: Arc::new(RateLimiter::new(config.requests_per_minute)),
+            max_retries: config.max_retries,
+            retry_base_delay: Duration::from_millis(config.retry_base_delay_ms),
+            max_retry_delay: Duration::from_secs(config.max_retry_delay_seconds),
+        })
+    }
 
-        let mut headers = HeaderMap::new();
-        headers.insert("x-goog-api-key", api_key.parse()?);
+    pub fn model(&self) -> &str {
+        &self.model
+    }
 
-        let client = Client::builder()
-            .user_agent(&config.user_agent)
-            .timeout(Duration::from_secs(config.timeout_seconds))
-            .default_headers(headers)
-            .build()?;
+    pub fn is_enabled(&self) -> bool {
+        self.provider.is_some()
+    }
 
-        Ok(Self {
-            client,
-            base_url: config.base_url.clone(),
-        })
+    /// Generates text from the prompt, a JSON document matching `schema` when given, retrying
+    /// with backoff when rate limited or when the provider is unavailable
+    pub async fn send_request(
+        &self,
+        prompt: String,
+        schema: Option<&Value>,
+    ) -> Result<Option<Generation>> {
+        let Some(provider) = &self.provider else {
+            return Ok(None);
+        };
+
+        self.with_retries(|| self.generate(provider, prompt.clone(), schema))
+            .await
+            .map(Some)
     }
 
-    pub async fn send_request(&self, prompt: String) -> Result<Option<String>> {
-        if self.base_url.is_empty() {
+    /// Generates text from the prompt as it's produced, retrying like [`Self::send_request`] until
+    /// the generation starts, one of the concurrent requests being used until the stream is dropped
+    pub async fn send_stream_request(&self, prompt: String) -> Result<Option<DeltaStream>> {
+        let Some(provider) = &self.provider else {
             return Ok(None);
+        };
+
+        self.with_retries(|| self.generate_stream(provider, prompt.clone()))
+            .await
+            .map(Some)
+    }
+
+    async fn with_retries<T, F: Future<Output = Result<T>>>(
+        &self,
+        mut request: impl FnMut() -> F,
+    ) -> Result<T> {
+        let mut attempt = 0;
+        loop {
+            let result = request().await;
+
+            let delay = match &result {
+                Err(Error::LlmQuotaExceeded(retry_after)) => {
+                    retry_after.unwrap_or_else(|| self.backoff(attempt))
+                }
+                Err(Error::LlmUnavailable(_)) => self.backoff(attempt),
+                _ => return result,
+            };
+
+            if attempt >= self.max_retries || delay > self.max_retry_delay {
+                return result;
+            }
+
+            if let Err(e) = &result {
+                warn!("Gemini request failed, retrying in {delay:?}: {e}");
+            }
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
         }
+    }
 
-        let request = GeminiRequest {
-            contents: vec![Content {
-                parts: vec![Part { text: prompt }],
-            }],
-        };
+    async fn generate(
+        &self,
+        provider: &Arc<dyn Provider>,
+        prompt: String,
+        schema: Option<&Value>,
+    ) -> Result<Generation> {
+        self.rate_limiter.acquire().await;
 
-        let response = self
-            .client
-            .post(self.base_url.clone())
-            .json(&request)
-            .send()
-            .await?
-            .json::<GeminiResponse>()
-            .await?;
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("Gemini semaphore is never closed");
 
-        let content = response.candidates[0].content.parts[0].text.clone();
+        let start = std::time::Instant::now();
+        let generation = provider.generate(prompt, schema).await;
+        Metrics::observe_request(
+            &METRICS.gemini_requests,
+            &METRICS.gemini_request_duration,
+            start,
+            generation.is_ok(),
+        );
 
-        Ok(Some(content))
+        generation
     }
-}
 
-#[derive(Serialize)]
-struct GeminiRequest {
-    contents: Vec<Content>,
-}
+    /// Starts streaming the generation, its duration only covering the time to the first response
+    async fn generate_stream(
+        &self,
+        provider: &Arc<dyn Provider>,
+        prompt: String,
+    ) -> Result<DeltaStream> {
+        self.rate_limiter.acquire().await;
+
+        let permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("Gemini semaphore is never closed");
+
+        let start = std::time::Instant::now();
+        let deltas = provider.generate_stream(prompt).await;
+        Metrics::observe_request(
+            &METRICS.gemini_requests,
+            &METRICS.gemini_request_duration,
+            start,
+            deltas.is_ok(),
+        );
+
+        deltas.map(|deltas| {
+            deltas
+                .map(move |delta| {
+                    let _permit = &permit;
+                    delta
+                })
+                .boxed()
+        })
+    }
+
+    /// Exponential delay of the retry, randomly reduced by up to half so that requests failing
+    /// together don't all retry at the same time
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .retry_base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_retry_delay);
+        let jitter = OsRng.next_u32() as f64 / u32::MAX as f64;
 
-#[derive(Serialize, Deserialize)]
-struct Content {
-    parts: Vec<Part>,
+        delay.mul_f64(1.0 - jitter / 2.0)
+    }
 }
 
-#[derive(Serialize, Deserialize)]
-struct Part {
-    text: String,
+/// Spaces requests evenly to send at most a number of them per minute
+struct RateLimiter {
+    interval: Option<Duration>,
+    next: Mutex<Instant>,
 }
 
-#[derive(Deserialize)]
-struct GeminiResponse {
-    candidates: Vec<Candidate>,
+impl RateLimiter {
+    fn new(requests_per_minute: u32) -> Self {
+        Self {
+            interval: (requests_per_minute > 0)
+                .then(|| Duration::from_secs(60) / requests_per_minute),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Waits for the next free slot
+    async fn acquire(&self) {
+        let Some(interval) = self.interval else {
+            return;
+        };
+
+        let slot = {
+            let mut next = self.next.lock().await;
+            let slot = (*next).max(Instant::now());
+            *next = slot + interval;
+            slot
+        };
+
+        tokio::time::sleep_until(slot).await;
+    }
 }
 
-#[derive(Deserialize)]
-struct Candidate {
-    content: Content,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LlmProvider;
+    use axum::{
+        Json, Router,
+        http::{HeaderValue, StatusCode, header::RETRY_AFTER},
+        response::IntoResponse,
+        routing::post,
+    };
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::net::TcpListener;
+
+    /// Stub of Gemini answering with the given responses in order, returning the number of
+    /// requests it received
+    async fn stub_server(
+        responses: Vec<(StatusCode, Option<&'static str>, Value)>,
+    ) -> (String, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let count = requests.clone();
+        let app = Router::new().route(
+            "/generate",
+            post(move || async move {
+                let (status, retry_after, body) =
+                    responses[count.fetch_add(1, Ordering::SeqCst)].clone();
+                let mut response = (status, Json(body)).into_response();
+                if let Some(retry_after) = retry_after {
+                    response
+                        .headers_mut()
+                        .insert(RETRY_AFTER, HeaderValue::from_static(retry_after));
+                }
+                response
+            }),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/generate", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (url, requests)
+    }
+
+    fn client(url: String) -> GenericGeminiClient {
+        let mut config = providers::test_config(LlmProvider::Gemini, url);
+        config.max_retries = 2;
+        config.max_retry_delay_seconds = 1;
+        GenericGeminiClient::new(&config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_given_rate_limited_then_unavailable_when_send_request_return_retried_generation()
+    {
+        let (url, requests) = stub_server(vec![
+            (StatusCode::TOO_MANY_REQUESTS, Some("0"), json!({})),
+            (StatusCode::SERVICE_UNAVAILABLE, None, json!({})),
+            (
+                StatusCode::OK,
+                None,
+                json!({ "candidates": [{ "content": { "parts": [{ "text": "Hello" }] } }] }),
+            ),
+        ])
+        .await;
+
+        let generation = client(url).send_request("Hi".to_string(), None).await;
+
+        assert_eq!(generation.unwrap().unwrap().text, "Hello");
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_given_blocked_or_long_retry_after_when_send_request_return_error_without_retry() {
+        let (url, requests) = stub_server(vec![
+            (
+                StatusCode::OK,
+                None,
+                json!({ "promptFeedback": { "blockReason": "SAFETY" } }),
+            ),
+            (StatusCode::TOO_MANY_REQUESTS, Some("120"), json!({})),
+        ])
+        .await;
+        let client = client(url);
+
+        let blocked = client.send_request("Hi".to_string(), None).await;
+        assert!(matches!(blocked, Err(Error::LlmBlocked(reason)) if reason == "SAFETY"));
+
+        let rate_limited = client.send_request("Hi".to_string(), None).await;
+        assert!(matches!(
+            rate_limited,
+            Err(Error::LlmQuotaExceeded(Some(retry_after))) if retry_after.as_secs() == 120
+        ));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_given_rate_limit_when_acquire_return_evenly_spaced_slots() {
+        let rate_limiter = RateLimiter::new(600);
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            rate_limiter.acquire().await;
+        }
+
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
 }
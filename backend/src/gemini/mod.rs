@@ -1,20 +1,80 @@
+pub mod analysis;
 mod client;
+mod providers;
 
-use crate::{Result, config};
+use chrono::{NaiveDateTime, Utc};
+use futures_util::{StreamExt, TryStreamExt, stream};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::time::Instant;
+use tracing::warn;
+
+pub use providers::{Delta, DeltaStream, Generation};
+
+use crate::{
+    Error, Result, config, db,
+    models::{
+        analysis::{Analysis, TokenUsage},
+        usage::{CreateLlmCall, LlmCallOutcome, UsageBudget, UsageContext, month_bounds},
+    },
+};
+
+/// Version of the analysis prompts, to increment whenever they change so that analyses made with
+/// different prompts can be told apart
+pub const ANALYSIS_PROMPT_VERSION: i64 = 3;
 
 #[derive(Clone)]
 pub struct GeminiClient {
     client: client::GenericGeminiClient,
+    analysis: config::GeminiAnalysis,
+    questions: config::GeminiQuestions,
+    usage: config::GeminiUsage,
+    pool: SqlitePool,
 }
 
 impl GeminiClient {
-    pub fn new(config: &config::Gemini) -> Result<Self> {
+    pub fn new(config: &config::Gemini, pool: SqlitePool) -> Result<Self> {
         let client = client::GenericGeminiClient::new(config)?;
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            analysis: config.analysis.clone(),
+            questions: config.questions.clone(),
+            usage: config.usage.clone(),
+            pool,
+        })
+    }
+
+    pub fn analysis_config(&self) -> &config::GeminiAnalysis {
+        &self.analysis
+    }
+
+    pub fn questions_config(&self) -> &config::GeminiQuestions {
+        &self.questions
+    }
+
+    pub fn model(&self) -> &str {
+        self.client.model()
+    }
+
+    /// Usage of the month of `now` compared to the budgets
+    pub async fn budget(&self, now: NaiveDateTime) -> Result<UsageBudget> {
+        let (month_start, _) = month_bounds(now);
+        let (tokens, cost) = db::get_llm_usage_since(&self.pool, month_start).await?;
+
+        Ok(UsageBudget {
+            month_start,
+            tokens,
+            cost,
+            token_budget: (self.usage.monthly_token_budget > 0)
+                .then_some(self.usage.monthly_token_budget),
+            cost_budget: (self.usage.monthly_cost_budget > 0.0)
+                .then_some(self.usage.monthly_cost_budget),
+        })
     }
 
     pub async fn generate_keywords(
         &self,
+        context: UsageContext,
         subject: &str,
         description: &str,
     ) -> Result<Option<Vec<String>>> {
@@ -22,27 +82,373 @@ impl GeminiClient {
             "Generate 10-20 relevant keywords for the following subject and description. Return only the keywords separated by commas, no other text.\n\nSubject: {subject}\nDescription: {description}",
         );
 
-        let Some(response) = self.client.send_request(prompt).await? else {
+        let Some(response) = self.send(context, prompt, None).await? else {
             return Ok(None);
         };
 
-        let keywords = response.split(',').map(|s| s.trim().to_string()).collect();
+        let keywords = response
+            .text
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .collect();
 
         Ok(Some(keywords))
     }
 
-    pub async fn analyze_posts(&self, posts: &[String]) -> Result<Option<String>> {
-        let prompt = format!(
-            "Analyze these posts and provide a summary including:\n\
-- Overall sentiment and tone\n\
-- Key topics and themes\n\
-- Major points being discussed\n\
-- Any notable patterns or trends\n\
-- Brief summary of the discussion\n\n\
-Posts to analyze:\n{}",
-            posts.join("\n\n")
+    /// Analyzes the posts of a topic published in `window` as a JSON document matching
+    /// [`analysis::RESULT_SCHEMA`], posts larger than a request being summarized in chunks whose
+    /// summaries are then combined
+    pub async fn analyze_posts(
+        &self,
+        context: UsageContext,
+        subject: &str,
+        window: (NaiveDateTime, NaiveDateTime),
+        posts: Vec<String>,
+    ) -> Result<Option<Generation>> {
+        let about = format!(
+            "The posts are about {subject} and were published between {} and {} UTC. Each post \
+starts with its ID in brackets and the handle of its author.",
+            window.0, window.1
         );
 
-        self.client.send_request(prompt).await
+        let mut chunks = analysis::chunk(posts, self.analysis.chunk_tokens);
+        if chunks.len() <= 1 {
+            let posts = chunks.pop().unwrap_or_default();
+            return self
+                .send(
+                    context,
+                    format!(
+                        "Analyze these posts and respond with a JSON object including:\n\
+{ANALYSIS_POINTS}\n\n{about}\n\nPosts to analyze:\n{}",
+                        posts.join("\n\n")
+                    ),
+                    Some(&analysis::RESULT_SCHEMA),
+                )
+                .await;
+        }
+
+        let mut usage = TokenUsage::default();
+
+        let total = chunks.len();
+        let summaries = self
+            .map_chunks(context, chunks, |index, posts| {
+                format!(
+                    "These posts are part {} of {total} of a larger discussion. Summarize them \
+concisely, keeping the post IDs and handles needed to later write:\n{ANALYSIS_POINTS}\n\n\
+{about}\n\nPosts to summarize:\n{}",
+                    index + 1,
+                    posts.join("\n\n")
+                )
+            })
+            .await?;
+        let Some(summaries) = summaries else {
+            return Ok(None);
+        };
+        let mut summaries = take_texts(summaries, &mut usage);
+
+        // Summaries too large for a single request are combined in groups until they fit, or until
+        // no two of them fit together
+        loop {
+            let count = summaries.len();
+            let chunks = analysis::chunk(summaries, self.analysis.chunk_tokens);
+            if chunks.len() <= 1 || chunks.len() == count {
+                summaries = chunks.into_iter().flatten().collect();
+                break;
+            }
+
+            let combined = self
+                .map_chunks(context, chunks, |_, summaries| {
+                    format!(
+                        "Combine these summaries of parts of a larger discussion into a single \
+concise summary, keeping the post IDs and handles needed to later write:\n{ANALYSIS_POINTS}\n\n\
+{about}\n\nSummaries to combine:\n{}",
+                        summaries.join("\n\n---\n\n")
+                    )
+                })
+                .await?;
+            let Some(combined) = combined else {
+                return Ok(None);
+            };
+            summaries = take_texts(combined, &mut usage);
+        }
+
+        let analysis = self
+            .send(
+                context,
+                format!(
+                    "These are summaries of consecutive parts of a discussion. Combine them into \
+an analysis of the whole discussion and respond with a JSON object including:\n\
+{ANALYSIS_POINTS}\n\n{about}\n\nSummaries to combine:\n{}",
+                    summaries.join("\n\n---\n\n")
+                ),
+                Some(&analysis::RESULT_SCHEMA),
+            )
+            .await?;
+
+        Ok(analysis.map(|mut analysis| {
+            analysis.usage += usage;
+            analysis
+        }))
+    }
+
+    /// Describes how the discussion of a topic evolved between two of its analyses
+    pub async fn compare_analyses(
+        &self,
+        context: UsageContext,
+        subject: &str,
+        from: &Analysis,
+        to: &Analysis,
+    ) -> Result<Option<Generation>> {
+        self.send(
+            context,
+            format!(
+                "These are two analyses of posts about {subject}, the first covering posts \
+published between {} and {} UTC ({} posts) and the second between {} and {} UTC ({} posts). \
+Describe how the discussion evolved from the first to the second: changes of sentiment and tone, \
+themes that appeared, grew, faded or disappeared, and any notable shift.\n\n\
+First analysis:\n{}\n\n---\n\nSecond analysis:\n{}",
+                from.since,
+                from.until,
+                from.matched_post_count,
+                to.since,
+                to.until,
+                to.matched_post_count,
+                analysis_text(from),
+                analysis_text(to),
+            ),
+            None,
+        )
+        .await
+    }
+
+    /// Describes an author from their posts, `topics` being the subjects of the topics they posted
+    /// in along with the number of posts
+    pub async fn analyze_user(
+        &self,
+        context: UsageContext,
+        handle: &str,
+        topics: &[(String, i64)],
+        posts: Vec<String>,
+    ) -> Result<Option<Generation>> {
+        let topics = topics
+            .iter()
+            .map(|(subject, count)| format!("- {subject}: {count} posts"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        self.send(
+            context,
+            format!(
+                "These posts were written by {handle} on Bluesky. Describe this author in a few \
+short paragraphs: the topics they post about, their tone and sentiment, their recurring themes \
+and opinions, and any notable pattern in how they post. Refer to example posts by their ID in \
+brackets.\n\nTopics their posts matched:\n{topics}\n\nPosts, oldest first:\n{}",
+                posts.join("\n\n")
+            ),
+            None,
+        )
+        .await
+    }
+
+    /// Answers a question about posts of a topic, citing the IDs of the posts it relies on, the
+    /// answer being streamed as it's generated
+    pub async fn answer_question(
+        &self,
+        context: UsageContext,
+        subject: &str,
+        question: &str,
+        posts: Vec<String>,
+    ) -> Result<Option<DeltaStream>> {
+        self.send_stream(
+            context,
+            format!(
+                "Answer the question below using only these posts about {subject}, the ones most \
+relevant to the question. Each post starts with its ID in brackets and the handle of its author. \
+Cite the posts supporting each point with their ID in brackets, e.g. [123]. When the posts \
+don't answer the question, say so. Answer in the language of the question.\n\n\
+Question: {question}\n\nPosts:\n{}",
+                posts.join("\n\n")
+            ),
+        )
+        .await
+    }
+
+    /// Sends the prompt of every chunk, a few at a time, keeping the order of the chunks
+    async fn map_chunks(
+        &self,
+        context: UsageContext,
+        chunks: Vec<Vec<String>>,
+        prompt: impl Fn(usize, Vec<String>) -> String,
+    ) -> Result<Option<Vec<Generation>>> {
+        let responses = stream::iter(chunks.into_iter().enumerate())
+            .map(|(index, chunk)| self.send(context, prompt(index, chunk), None))
+            .buffered(self.analysis.chunk_concurrency.max(1))
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        Ok(responses.into_iter().collect())
+    }
+
+    /// Sends the prompt unless the monthly budget is exceeded, recording the usage of the call
+    async fn send(
+        &self,
+        context: UsageContext,
+        prompt: String,
+        schema: Option<&Value>,
+    ) -> Result<Option<Generation>> {
+        if !self.client.is_enabled() {
+            return Ok(None);
+        }
+        self.check_budget().await?;
+
+        let start = Instant::now();
+        let generation = self.client.send_request(prompt, schema).await;
+
+        let usage = match &generation {
+            Ok(Some(generation)) => generation.usage,
+            _ => TokenUsage::default(),
+        };
+        let call = self.llm_call(context, usage, start, generation.as_ref().err());
+        record_llm_call(&self.pool, call).await;
+
+        generation
+    }
+
+    /// Streams the generation of the prompt unless the monthly budget is exceeded, the usage of the
+    /// call being recorded once the stream ends or is dropped
+    async fn send_stream(
+        &self,
+        context: UsageContext,
+        prompt: String,
+    ) -> Result<Option<DeltaStream>> {
+        if !self.client.is_enabled() {
+            return Ok(None);
+        }
+        self.check_budget().await?;
+
+        let start = Instant::now();
+        let deltas = match self.client.send_stream_request(prompt).await {
+            Ok(Some(deltas)) => deltas,
+            Ok(None) => return Ok(None),
+            Err(e) => {
+                let call = self.llm_call(context, TokenUsage::default(), start, Some(&e));
+                record_llm_call(&self.pool, call).await;
+                return Err(e);
+            }
+        };
+
+        let mut recorder = CallRecorder {
+            client: self.clone(),
+            context,
+            start,
+            usage: TokenUsage::default(),
+            error: None,
+        };
+
+        Ok(Some(
+            deltas.inspect(move |delta| recorder.observe(delta)).boxed(),
+        ))
+    }
+
+    async fn check_budget(&self) -> Result<()> {
+        let now = Utc::now().naive_utc();
+        if self.budget(now).await?.exceeded() {
+            let (_, reset_at) = month_bounds(now);
+            return Err(Error::LlmBudgetExceeded(
+                (reset_at - now).to_std().unwrap_or_default(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn llm_call(
+        &self,
+        context: UsageContext,
+        usage: TokenUsage,
+        start: Instant,
+        error: Option<&Error>,
+    ) -> CreateLlmCall {
+        CreateLlmCall {
+            context,
+            model: self.model().to_string(),
+            prompt_tokens: usage.prompt_tokens,
+            output_tokens: usage.output_tokens,
+            cost: self.usage.cost(&usage),
+            latency_ms: start.elapsed().as_millis() as i64,
+            outcome: error
+                .map(LlmCallOutcome::from)
+                .unwrap_or(LlmCallOutcome::Succeeded),
+            error: error.map(ToString::to_string),
+        }
+    }
+}
+
+/// Records the usage of a streamed call when dropped, whether the stream ended, failed or was
+/// abandoned by its consumer
+struct CallRecorder {
+    client: GeminiClient,
+    context: UsageContext,
+    start: Instant,
+    usage: TokenUsage,
+    error: Option<(LlmCallOutcome, String)>,
+}
+
+impl CallRecorder {
+    fn observe(&mut self, delta: &Result<Delta>) {
+        match delta {
+            Ok(Delta::Usage(usage)) => self.usage = *usage,
+            Ok(Delta::Text(_)) => {}
+            Err(e) => self.error = Some((LlmCallOutcome::from(e), e.to_string())),
+        }
     }
 }
+
+impl Drop for CallRecorder {
+    fn drop(&mut self) {
+        let mut call = self
+            .client
+            .llm_call(self.context, self.usage, self.start, None);
+        if let Some((outcome, error)) = self.error.take() {
+            call.outcome = outcome;
+            call.error = Some(error);
+        }
+
+        let pool = self.client.pool.clone();
+        tokio::spawn(async move { record_llm_call(&pool, call).await });
+    }
+}
+
+async fn record_llm_call(pool: &SqlitePool, call: CreateLlmCall) {
+    if let Err(e) = db::create_llm_call(pool, call).await {
+        warn!("Unable to record the usage of an LLM call: {e}");
+    }
+}
+
+/// Structured result of the analysis as JSON, or its text when it has none
+fn analysis_text(analysis: &Analysis) -> String {
+    analysis
+        .structured_result
+        .as_ref()
+        .and_then(|result| serde_json::to_string_pretty(result).ok())
+        .unwrap_or_else(|| analysis.result.clone())
+}
+
+/// Texts of the generations, adding their tokens to `usage`
+fn take_texts(generations: Vec<Generation>, usage: &mut TokenUsage) -> Vec<String> {
+    generations
+        .into_iter()
+        .map(|generation| {
+            *usage += generation.usage;
+            generation.text
+        })
+        .collect()
+}
+
+const ANALYSIS_POINTS: &str = "- summary: brief summary of the discussion, its major points and \
+any notable patterns or trends\n\
+- sentiment: overall score from -1 (negative) to 1 (positive) and shares of positive, neutral and \
+negative posts\n\
+- themes: key topics and themes, each with the IDs of a few example posts\n\
+- notable_accounts: handles of the accounts driving the discussion and why\n\
+- emerging_terms: new or quickly spreading words, names or hashtags";
@@ -0,0 +1,271 @@
+use async_stream::try_stream;
+use futures_util::{FutureExt, StreamExt, future::BoxFuture};
+use reqwest::{Client, header::HeaderMap};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use super::{
+    Delta, DeltaStream, Generation, Provider, generation_stream, http_client, parse_chunk, send,
+    send_json, sse_data,
+};
+use crate::{Error, Result, config, models::analysis::TokenUsage};
+
+/// Gemini `generateContent` API, its URL including the model
+pub struct GeminiProvider {
+    client: Client,
+    base_url: String,
+    model: String,
+}
+
+impl GeminiProvider {
+    pub fn new(config: &config::Gemini, api_key: &str) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-goog-api-key", api_key.parse()?);
+
+        Ok(Self {
+            client: http_client(config, headers)?,
+            base_url: config.base_url.clone(),
+            model: config
+                .model
+                .clone()
+                .unwrap_or_else(|| model_name(&config.base_url)),
+        })
+    }
+
+    async fn request(&self, prompt: String, schema: Option<&Value>) -> Result<Generation> {
+        let request = GeminiRequest {
+            contents: vec![Content {
+                parts: vec![Part { text: Some(prompt) }],
+            }],
+            generation_config: schema.map(|schema| {
+                json!({
+                    "responseMimeType": "application/json",
+                    "responseJsonSchema": schema,
+                })
+            }),
+        };
+
+        let response: GeminiResponse =
+            send_json(self.client.post(&self.base_url).json(&request)).await?;
+
+        if let Some(reason) = response.block_reason() {
+            return Err(Error::LlmBlocked(reason));
+        }
+
+        let usage = response.usage().unwrap_or_default();
+        let candidate = response.candidates.into_iter().next().ok_or_else(|| {
+            Error::LlmMalformedResponse("Gemini returned no candidate".to_string())
+        })?;
+
+        if candidate
+            .content
+            .parts
+            .iter()
+            .all(|part| part.text.is_none())
+        {
+            return Err(Error::LlmMalformedResponse(format!(
+                "Gemini returned no text, finish reason {:?}",
+                candidate.finish_reason
+            )));
+        }
+
+        let text = candidate
+            .content
+            .parts
+            .into_iter()
+            .filter_map(|part| part.text)
+            .collect();
+
+        Ok(Generation { text, usage })
+    }
+
+    /// Streams the generation from the `streamGenerateContent` API, the usage of the whole
+    /// generation being sent with the last chunk
+    async fn request_stream(&self, prompt: String) -> Result<DeltaStream> {
+        let Some(url) = stream_url(&self.base_url) else {
+            return self.request(prompt, None).await.map(generation_stream);
+        };
+
+        let request = GeminiRequest {
+            contents: vec![Content {
+                parts: vec![Part { text: Some(prompt) }],
+            }],
+            generation_config: None,
+        };
+        let mut chunks = sse_data(send(self.client.post(url).json(&request)).await?);
+
+        Ok(try_stream! {
+            let mut usage = None;
+
+            while let Some(chunk) = chunks.next().await {
+                let response: GeminiResponse = parse_chunk(&chunk?)?;
+                if let Some(reason) = response.block_reason() {
+                    Err(Error::LlmBlocked(reason))?;
+                }
+
+                usage = response.usage().or(usage);
+                let text = response
+                    .candidates
+                    .into_iter()
+                    .next()
+                    .map(|candidate| {
+                        candidate
+                            .content
+                            .parts
+                            .into_iter()
+                            .filter_map(|part| part.text)
+                            .collect::<String>()
+                    })
+                    .unwrap_or_default();
+
+                if !text.is_empty() {
+                    yield Delta::Text(text);
+                }
+            }
+
+            yield Delta::Usage(usage.unwrap_or_default());
+        }
+        .boxed())
+    }
+}
+
+impl Provider for GeminiProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn generate<'a>(
+        &'a self,
+        prompt: String,
+        schema: Option<&'a Value>,
+    ) -> BoxFuture<'a, Result<Generation>> {
+        self.request(prompt, schema).boxed()
+    }
+
+    fn generate_stream(&self, prompt: String) -> BoxFuture<'_, Result<DeltaStream>> {
+        self.request_stream(prompt).boxed()
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiRequest {
+    contents: Vec<Content>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    generation_config: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct Content {
+    #[serde(default)]
+    parts: Vec<Part>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Part {
+    /// Missing from parts which aren't text, e.g. function calls
+    text: Option<String>,
+}
+
+/// Finish reasons of candidates blocked by the safety filters
+const BLOCK_REASONS: [&str; 6] = [
+    "SAFETY",
+    "RECITATION",
+    "BLOCKLIST",
+    "PROHIBITED_CONTENT",
+    "SPII",
+    "IMAGE_SAFETY",
+];
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiResponse {
+    #[serde(default)]
+    candidates: Vec<Candidate>,
+    prompt_feedback: Option<PromptFeedback>,
+    usage_metadata: Option<UsageMetadata>,
+}
+
+impl GeminiResponse {
+    /// Why the prompt or the first candidate was blocked by the safety filters
+    fn block_reason(&self) -> Option<String> {
+        self.prompt_feedback
+            .as_ref()
+            .and_then(|feedback| feedback.block_reason.clone())
+            .or_else(|| {
+                self.candidates
+                    .first()
+                    .and_then(|candidate| candidate.finish_reason.clone())
+                    .filter(|reason| BLOCK_REASONS.contains(&reason.as_str()))
+            })
+    }
+
+    fn usage(&self) -> Option<TokenUsage> {
+        self.usage_metadata.as_ref().map(|usage| TokenUsage {
+            prompt_tokens: usage.prompt_token_count,
+            output_tokens: usage.candidates_token_count + usage.thoughts_token_count,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptFeedback {
+    /// Set when the prompt was blocked, in which case there's no candidate
+    block_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageMetadata {
+    #[serde(default)]
+    prompt_token_count: i64,
+    #[serde(default)]
+    candidates_token_count: i64,
+    #[serde(default)]
+    thoughts_token_count: i64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Candidate {
+    /// Missing when blocked
+    #[serde(default)]
+    content: Content,
+    finish_reason: Option<String>,
+}
+
+/// `streamGenerateContent` URL of a `generateContent` URL, sending server-sent events
+fn stream_url(base_url: &str) -> Option<String> {
+    base_url
+        .strip_suffix(":generateContent")
+        .map(|url| format!("{url}:streamGenerateContent?alt=sse"))
+}
+
+/// Model of a `.../models/{model}:generateContent` URL, or the whole URL for other endpoints
+fn model_name(base_url: &str) -> String {
+    base_url
+        .split_once("/models/")
+        .map(|(_, model)| model.split(':').next().unwrap_or(model))
+        .unwrap_or(base_url)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_given_generate_content_url_when_model_name_return_model() {
+        assert_eq!(
+            model_name(
+                "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash:generateContent"
+            ),
+            "gemini-2.5-flash"
+        );
+        assert_eq!(
+            model_name("http://localhost:8080/generate"),
+            "http://localhost:8080/generate"
+        );
+    }
+}
@@ -0,0 +1,462 @@
+use async_stream::try_stream;
+use chrono::{DateTime, Utc};
+use futures_util::{
+    FutureExt, StreamExt,
+    future::BoxFuture,
+    stream::{self, BoxStream},
+};
+use reqwest::{
+    Client, RequestBuilder, Response, StatusCode,
+    header::{HeaderMap, RETRY_AFTER},
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::{sync::Arc, time::Duration};
+use tracing::{info, warn};
+
+use crate::{
+    Error, Result,
+    config::{self, LlmProvider},
+    models::analysis::TokenUsage,
+};
+
+mod gemini;
+mod ollama;
+mod openai;
+
+/// Text generated by the model and the tokens it cost
+#[derive(Debug)]
+pub struct Generation {
+    pub text: String,
+    pub usage: TokenUsage,
+}
+
+/// Part of a generation streamed as the model produces it
+#[derive(Debug, PartialEq)]
+pub enum Delta {
+    Text(String),
+    /// Tokens of the whole generation, sent once it's complete
+    Usage(TokenUsage),
+}
+
+pub type DeltaStream = BoxStream<'static, Result<Delta>>;
+
+/// Model API used by [`super::GeminiClient`] to generate text from a prompt
+pub trait Provider: Send + Sync {
+    fn model(&self) -> &str;
+
+    /// Generates text from the prompt, a JSON document matching `schema` when given
+    fn generate<'a>(
+        &'a self,
+        prompt: String,
+        schema: Option<&'a Value>,
+    ) -> BoxFuture<'a, Result<Generation>>;
+
+    /// Generates text from the prompt as it's produced, the future failing like [`Self::generate`]
+    /// when the request is refused, and the stream when the generation fails midway. Providers
+    /// which can't stream send the whole generation at once
+    fn generate_stream(&self, prompt: String) -> BoxFuture<'_, Result<DeltaStream>> {
+        async move { self.generate(prompt, None).await.map(generation_stream) }.boxed()
+    }
+}
+
+/// Whole generation sent as a stream
+fn generation_stream(generation: Generation) -> DeltaStream {
+    stream::iter([
+        Ok(Delta::Text(generation.text)),
+        Ok(Delta::Usage(generation.usage)),
+    ])
+    .boxed()
+}
+
+/// Provider selected by the configuration, `None` when disabled or missing its API key
+pub fn new_provider(config: &config::Gemini) -> Result<Option<Arc<dyn Provider>>> {
+    if !config.enabled {
+        info!("LLM provider disabled");
+        return Ok(None);
+    }
+
+    let api_key = config.api_key.clone().filter(|api_key| !api_key.is_empty());
+
+    let provider: Arc<dyn Provider> = match (config.provider, api_key) {
+        (LlmProvider::Gemini, None) => {
+            warn!("BLUFLARE__GEMINI__API_KEY is not set, gemini will not be used");
+            return Ok(None);
+        }
+        (LlmProvider::Gemini, Some(api_key)) => {
+            Arc::new(gemini::GeminiProvider::new(config, &api_key)?)
+        }
+        (LlmProvider::OpenAi, api_key) => {
+            Arc::new(openai::OpenAiProvider::new(config, api_key.as_deref())?)
+        }
+        (LlmProvider::Ollama, _) => Arc::new(ollama::OllamaProvider::new(config)?),
+    };
+
+    info!(
+        "Initializing {:?} provider with model {}",
+        config.provider,
+        provider.model()
+    );
+
+    Ok(Some(provider))
+}
+
+fn http_client(config: &config::Gemini, headers: HeaderMap) -> Result<Client> {
+    let client = Client::builder()
+        .user_agent(&config.user_agent)
+        .timeout(Duration::from_secs(config.timeout_seconds))
+        .default_headers(headers)
+        .build()?;
+
+    Ok(client)
+}
+
+/// Sends the request and parses its JSON response, turning failures into the `Llm*` errors
+async fn send_json<T: DeserializeOwned>(request: RequestBuilder) -> Result<T> {
+    let body = send(request)
+        .await?
+        .bytes()
+        .await
+        .map_err(|e| Error::LlmUnavailable(e.to_string()))?;
+
+    serde_json::from_slice(&body).map_err(|e| Error::LlmMalformedResponse(e.to_string()))
+}
+
+/// Sends the request, turning failures into the `Llm*` errors, the body of successful responses
+/// being left to read
+async fn send(request: RequestBuilder) -> Result<Response> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| Error::LlmUnavailable(e.to_string()))?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let retry_after = retry_after(response.headers(), Utc::now());
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| Error::LlmUnavailable(e.to_string()))?;
+
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(Error::LlmQuotaExceeded(
+            retry_after.or_else(|| retry_delay(&body)),
+        ));
+    }
+
+    let message = format!("{status}: {}", String::from_utf8_lossy(&body));
+    Err(
+        match status.is_server_error() || status == StatusCode::REQUEST_TIMEOUT {
+            true => Error::LlmUnavailable(message),
+            false => Error::LlmRejected(message),
+        },
+    )
+}
+
+/// Non-empty lines of a streamed response body, e.g. newline-delimited JSON
+fn lines(mut response: Response) -> BoxStream<'static, Result<String>> {
+    try_stream! {
+        let mut buffer = Vec::new();
+
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| Error::LlmUnavailable(e.to_string()))?
+        {
+            buffer.extend_from_slice(&chunk);
+
+            while let Some(end) = buffer.iter().position(|byte| *byte == b'\n') {
+                let line = buffer.drain(..=end).collect::<Vec<_>>();
+                let line = String::from_utf8_lossy(&line).trim().to_string();
+                if !line.is_empty() {
+                    yield line;
+                }
+            }
+        }
+
+        let line = String::from_utf8_lossy(&buffer).trim().to_string();
+        if !line.is_empty() {
+            yield line;
+        }
+    }
+    .boxed()
+}
+
+/// Data of the events of a server-sent events response, ignoring comments and other fields
+fn sse_data(response: Response) -> BoxStream<'static, Result<String>> {
+    lines(response)
+        .filter_map(|line| async move {
+            match line {
+                Ok(line) => line
+                    .strip_prefix("data:")
+                    .map(|data| Ok(data.trim_start().to_string())),
+                Err(e) => Some(Err(e)),
+            }
+        })
+        .boxed()
+}
+
+/// Parses a JSON document of a streamed response
+fn parse_chunk<T: DeserializeOwned>(chunk: &str) -> Result<T> {
+    serde_json::from_str(chunk).map_err(|e| Error::LlmMalformedResponse(e.to_string()))
+}
+
+/// `Retry-After` header, either in seconds or as an HTTP date
+fn retry_after(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => DateTime::parse_from_rfc2822(value).ok().map(|date| {
+            (date.with_timezone(&Utc) - now)
+                .to_std()
+                .unwrap_or_default()
+        }),
+    }
+}
+
+/// `retryDelay` of the `RetryInfo` detail of a Gemini error, e.g. `"30s"`
+fn retry_delay(body: &[u8]) -> Option<Duration> {
+    let error: Value = serde_json::from_slice(body).ok()?;
+
+    error["error"]["details"]
+        .as_array()?
+        .iter()
+        .find_map(|detail| detail["retryDelay"].as_str())
+        .and_then(|delay| delay.strip_suffix('s'))
+        .and_then(|seconds| seconds.parse::<f64>().ok())
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+}
+
+/// Model of the configuration, required by providers whose URL doesn't include it
+fn required_model(config: &config::Gemini) -> Result<String> {
+    config.model.clone().ok_or(Error::BadRequest(format!(
+        "BLUFLARE__GEMINI__MODEL is required by the {:?} provider",
+        config.provider
+    )))
+}
+
+#[cfg(test)]
+/// Configuration of a provider sending its requests to `base_url`, without retries
+pub(super) fn test_config(provider: LlmProvider, base_url: String) -> config::Gemini {
+    config::Gemini {
+        enabled: true,
+        provider,
+        base_url,
+        model: Some("test-model".to_string()),
+        api_key: Some("secret".to_string()),
+        timeout_seconds: 5,
+        user_agent: "test".to_string(),
+        max_concurrent_requests: 1,
+        requests_per_minute: 0,
+        max_retries: 0,
+        retry_base_delay_ms: 0,
+        max_retry_delay_seconds: 0,
+        analysis: config::GeminiAnalysis {
+            default_window_hours: 24,
+            max_window_days: 31,
+            max_posts: 100,
+            max_input_tokens: 1000,
+            chunk_tokens: 100,
+            chunk_concurrency: 1,
+        },
+        questions: config::GeminiQuestions {
+            max_posts: 10,
+            max_input_tokens: 1000,
+        },
+        usage: config::GeminiUsage {
+            monthly_token_budget: 0,
+            monthly_cost_budget: 0.0,
+            prompt_cost_per_million_tokens: 0.0,
+            output_cost_per_million_tokens: 0.0,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Json, Router, routing::post};
+    use serde_json::{Value, json};
+    use tokio::net::TcpListener;
+
+    async fn stub_server(response: Value) -> (String, tokio::sync::mpsc::Receiver<Value>) {
+        let (sender, receiver) = tokio::sync::mpsc::channel(1);
+        let app = Router::new().route(
+            "/generate",
+            post(move |Json(request): Json<Value>| async move {
+                sender.send(request).await.unwrap();
+                Json(response)
+            }),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/generate", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (url, receiver)
+    }
+
+    #[tokio::test]
+    async fn test_given_gemini_provider_when_generate_return_candidate_text() {
+        let (url, mut requests) = stub_server(json!({
+            "candidates": [{ "content": { "parts": [{ "text": "Hello" }] } }],
+            "usageMetadata": { "promptTokenCount": 3, "candidatesTokenCount": 1 }
+        }))
+        .await;
+
+        let provider = new_provider(&test_config(LlmProvider::Gemini, url))
+            .unwrap()
+            .unwrap();
+        let generation = provider.generate("Hi".to_string(), None).await.unwrap();
+
+        assert_eq!(generation.text, "Hello");
+        assert_eq!(
+            generation.usage,
+            TokenUsage {
+                prompt_tokens: 3,
+                output_tokens: 1
+            }
+        );
+        assert_eq!(
+            requests.recv().await.unwrap(),
+            json!({ "contents": [{ "parts": [{ "text": "Hi" }] }] })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_given_openai_provider_when_generate_return_message_content() {
+        let (url, mut requests) = stub_server(json!({
+            "choices": [{ "message": { "role": "assistant", "content": "Hello" } }],
+            "usage": { "prompt_tokens": 3, "completion_tokens": 1 }
+        }))
+        .await;
+
+        let provider = new_provider(&test_config(LlmProvider::OpenAi, url))
+            .unwrap()
+            .unwrap();
+        let generation = provider.generate("Hi".to_string(), None).await.unwrap();
+
+        assert_eq!(generation.text, "Hello");
+        assert_eq!(generation.usage.prompt_tokens, 3);
+        assert_eq!(
+            requests.recv().await.unwrap(),
+            json!({
+                "model": "test-model",
+                "messages": [{ "role": "user", "content": "Hi" }]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_given_openai_provider_when_generate_stream_return_deltas_and_usage() {
+        let app = Router::new().route(
+            "/generate",
+            post(|| async {
+                (
+                    [("content-type", "text/event-stream")],
+                    concat!(
+                        "data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+                        "data: {\"choices\":[{\"delta\":{\"content\":\"lo\"}}]}\n\n",
+                        "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":3,\"completion_tokens\":1}}\n\n",
+                        "data: [DONE]\n\n"
+                    ),
+                )
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/generate", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let provider = new_provider(&test_config(LlmProvider::OpenAi, url))
+            .unwrap()
+            .unwrap();
+        let deltas = provider
+            .generate_stream("Hi".to_string())
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(
+            deltas,
+            vec![
+                Delta::Text("Hel".to_string()),
+                Delta::Text("lo".to_string()),
+                Delta::Usage(TokenUsage {
+                    prompt_tokens: 3,
+                    output_tokens: 1
+                }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_given_ollama_provider_when_generate_return_message_content() {
+        let (url, mut requests) = stub_server(json!({
+            "message": { "role": "assistant", "content": "Hello" },
+            "done": true,
+            "prompt_eval_count": 3,
+            "eval_count": 1
+        }))
+        .await;
+
+        let mut config = test_config(LlmProvider::Ollama, url);
+        config.api_key = None;
+        let provider = new_provider(&config).unwrap().unwrap();
+        let generation = provider.generate("Hi".to_string(), None).await.unwrap();
+
+        assert_eq!(generation.text, "Hello");
+        assert_eq!(generation.usage.output_tokens, 1);
+        assert_eq!(
+            requests.recv().await.unwrap(),
+            json!({
+                "model": "test-model",
+                "messages": [{ "role": "user", "content": "Hi" }],
+                "stream": false
+            })
+        );
+    }
+
+    #[test]
+    fn test_given_retry_after_or_retry_delay_when_parse_return_duration() {
+        let now = DateTime::parse_from_rfc3339("2025-08-31T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let headers =
+            |value: &'static str| HeaderMap::from_iter([(RETRY_AFTER, value.parse().unwrap())]);
+
+        assert_eq!(
+            retry_after(&headers("30"), now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            retry_after(&headers("Sun, 31 Aug 2025 12:01:00 GMT"), now),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(retry_after(&HeaderMap::new(), now), None);
+
+        let body = json!({
+            "error": {
+                "code": 429,
+                "details": [{ "@type": "type.googleapis.com/google.rpc.RetryInfo", "retryDelay": "1.5s" }]
+            }
+        });
+        assert_eq!(
+            retry_delay(body.to_string().as_bytes()),
+            Some(Duration::from_millis(1500))
+        );
+    }
+
+    #[test]
+    fn test_given_gemini_provider_without_api_key_when_new_provider_return_none() {
+        let mut config = test_config(LlmProvider::Gemini, String::new());
+        config.api_key = None;
+
+        assert!(new_provider(&config).unwrap().is_none());
+    }
+}
@@ -0,0 +1,133 @@
+use async_stream::try_stream;
+use futures_util::{FutureExt, StreamExt, future::BoxFuture};
+use reqwest::{Client, header::HeaderMap};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    Delta, DeltaStream, Generation, Provider, http_client, lines, parse_chunk, required_model,
+    send, send_json,
+};
+use crate::{Result, config, models::analysis::TokenUsage};
+
+/// Ollama chat API of a local model, e.g. `http://localhost:11434/api/chat`
+pub struct OllamaProvider {
+    client: Client,
+    base_url: String,
+    model: String,
+}
+
+impl OllamaProvider {
+    pub fn new(config: &config::Gemini) -> Result<Self> {
+        Ok(Self {
+            client: http_client(config, HeaderMap::new())?,
+            base_url: config.base_url.clone(),
+            model: required_model(config)?,
+        })
+    }
+
+    async fn request(&self, prompt: String, schema: Option<&Value>) -> Result<Generation> {
+        let request = ChatRequest {
+            model: &self.model,
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: prompt,
+            }],
+            stream: false,
+            format: schema,
+        };
+
+        let response: ChatResponse =
+            send_json(self.client.post(&self.base_url).json(&request)).await?;
+
+        Ok(Generation {
+            usage: response.usage(),
+            text: response.message.content,
+        })
+    }
+
+    /// Streams the generation as newline-delimited JSON, the last object including the usage
+    async fn request_stream(&self, prompt: String) -> Result<DeltaStream> {
+        let request = ChatRequest {
+            model: &self.model,
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: prompt,
+            }],
+            stream: true,
+            format: None,
+        };
+        let mut chunks = lines(send(self.client.post(&self.base_url).json(&request)).await?);
+
+        Ok(try_stream! {
+            while let Some(chunk) = chunks.next().await {
+                let chunk: ChatResponse = parse_chunk(&chunk?)?;
+                let usage = chunk.done.then(|| chunk.usage());
+
+                if !chunk.message.content.is_empty() {
+                    yield Delta::Text(chunk.message.content);
+                }
+                if let Some(usage) = usage {
+                    yield Delta::Usage(usage);
+                }
+            }
+        }
+        .boxed())
+    }
+}
+
+impl Provider for OllamaProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn generate<'a>(
+        &'a self,
+        prompt: String,
+        schema: Option<&'a Value>,
+    ) -> BoxFuture<'a, Result<Generation>> {
+        self.request(prompt, schema).boxed()
+    }
+
+    fn generate_stream(&self, prompt: String) -> BoxFuture<'_, Result<DeltaStream>> {
+        self.request_stream(prompt).boxed()
+    }
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<Message>,
+    stream: bool,
+    /// JSON schema of the response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'a Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Message {
+    role: String,
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    message: Message,
+    /// Missing when the prompt was cached
+    #[serde(default)]
+    prompt_eval_count: i64,
+    #[serde(default)]
+    eval_count: i64,
+    /// Set on the last object of a stream
+    #[serde(default)]
+    done: bool,
+}
+
+impl ChatResponse {
+    fn usage(&self) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: self.prompt_eval_count,
+            output_tokens: self.eval_count,
+        }
+    }
+}
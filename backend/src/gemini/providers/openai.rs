@@ -0,0 +1,223 @@
+use async_stream::try_stream;
+use futures_util::{FutureExt, StreamExt, future::BoxFuture};
+use reqwest::{Client, header::HeaderMap};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use super::{
+    Delta, DeltaStream, Generation, Provider, http_client, parse_chunk, required_model, send,
+    send_json, sse_data,
+};
+use crate::{Error, Result, config, models::analysis::TokenUsage};
+
+/// OpenAI compatible chat completions API, e.g. `https://api.openai.com/v1/chat/completions`
+pub struct OpenAiProvider {
+    client: Client,
+    base_url: String,
+    model: String,
+}
+
+impl OpenAiProvider {
+    pub fn new(config: &config::Gemini, api_key: Option<&str>) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        if let Some(api_key) = api_key {
+            headers.insert("authorization", format!("Bearer {api_key}").parse()?);
+        }
+
+        Ok(Self {
+            client: http_client(config, headers)?,
+            base_url: config.base_url.clone(),
+            model: required_model(config)?,
+        })
+    }
+
+    async fn request(&self, prompt: String, schema: Option<&Value>) -> Result<Generation> {
+        let request = ChatRequest {
+            model: &self.model,
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: prompt,
+            }],
+            response_format: schema.map(|schema| {
+                json!({
+                    "type": "json_schema",
+                    "json_schema": { "name": "result", "schema": schema, "strict": true },
+                })
+            }),
+            stream: false,
+            stream_options: None,
+        };
+
+        let response: ChatResponse =
+            send_json(self.client.post(&self.base_url).json(&request)).await?;
+
+        let choice =
+            response.choices.into_iter().next().ok_or_else(|| {
+                Error::LlmMalformedResponse("OpenAI returned no choice".to_string())
+            })?;
+
+        let text = match (choice.message, choice.finish_reason.as_deref()) {
+            (_, Some("content_filter")) => {
+                return Err(Error::LlmBlocked("content_filter".to_string()));
+            }
+            (
+                ResponseMessage {
+                    refusal: Some(refusal),
+                    ..
+                },
+                _,
+            ) => return Err(Error::LlmBlocked(refusal)),
+            (
+                ResponseMessage {
+                    content: Some(content),
+                    ..
+                },
+                _,
+            ) => content,
+            (_, reason) => {
+                return Err(Error::LlmMalformedResponse(format!(
+                    "OpenAI returned no content, finish reason {reason:?}"
+                )));
+            }
+        };
+        let usage = response
+            .usage
+            .map(|usage| TokenUsage {
+                prompt_tokens: usage.prompt_tokens,
+                output_tokens: usage.completion_tokens,
+            })
+            .unwrap_or_default();
+
+        Ok(Generation { text, usage })
+    }
+
+    /// Streams the generation as server-sent events, the usage being sent in a last chunk
+    /// without choices
+    async fn request_stream(&self, prompt: String) -> Result<DeltaStream> {
+        let request = ChatRequest {
+            model: &self.model,
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: prompt,
+            }],
+            response_format: None,
+            stream: true,
+            stream_options: Some(json!({ "include_usage": true })),
+        };
+        let mut chunks = sse_data(send(self.client.post(&self.base_url).json(&request)).await?);
+
+        Ok(try_stream! {
+            let mut usage = None;
+
+            while let Some(chunk) = chunks.next().await {
+                let chunk = chunk?;
+                if chunk == "[DONE]" {
+                    break;
+                }
+
+                let chunk: ChatChunk = parse_chunk(&chunk)?;
+                if let Some(chunk_usage) = chunk.usage {
+                    usage = Some(TokenUsage {
+                        prompt_tokens: chunk_usage.prompt_tokens,
+                        output_tokens: chunk_usage.completion_tokens,
+                    });
+                }
+
+                let Some(choice) = chunk.choices.into_iter().next() else {
+                    continue;
+                };
+                if choice.finish_reason.as_deref() == Some("content_filter") {
+                    Err(Error::LlmBlocked("content_filter".to_string()))?;
+                }
+                if let Some(refusal) = choice.delta.refusal {
+                    Err(Error::LlmBlocked(refusal))?;
+                }
+                if let Some(content) = choice.delta.content.filter(|content| !content.is_empty()) {
+                    yield Delta::Text(content);
+                }
+            }
+
+            yield Delta::Usage(usage.unwrap_or_default());
+        }
+        .boxed())
+    }
+}
+
+impl Provider for OpenAiProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn generate<'a>(
+        &'a self,
+        prompt: String,
+        schema: Option<&'a Value>,
+    ) -> BoxFuture<'a, Result<Generation>> {
+        self.request(prompt, schema).boxed()
+    }
+
+    fn generate_stream(&self, prompt: String) -> BoxFuture<'_, Result<DeltaStream>> {
+        self.request_stream(prompt).boxed()
+    }
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<Value>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<Value>,
+}
+
+#[derive(Serialize)]
+struct Message {
+    role: String,
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    #[serde(default)]
+    choices: Vec<Choice>,
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: ResponseMessage,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChatChunk {
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct ChunkChoice {
+    delta: ResponseMessage,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ResponseMessage {
+    /// Missing when the model refused to answer
+    content: Option<String>,
+    /// Why the model refused to answer
+    refusal: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Usage {
+    #[serde(default)]
+    prompt_tokens: i64,
+    /// Includes reasoning tokens
+    #[serde(default)]
+    completion_tokens: i64,
+}
@@ -3,11 +3,14 @@ use reqwest::Client;
 use serde::Deserialize;
 use std::{
     collections::{BTreeMap, BTreeSet},
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tracing::{error, info};
 
-use crate::{Result, config};
+use crate::{
+    Result, config,
+    metrics::{METRICS, Metrics},
+};
 
 #[derive(Clone)]
 pub struct DidClient {
@@ -59,15 +62,26 @@ impl DidClient {
             return Ok(vec![]);
         }
 
+        let start = Instant::now();
+        let result = self.request_did(&did).await;
+        Metrics::observe_request(
+            &METRICS.did_requests,
+            &METRICS.did_request_duration,
+            start,
+            result.is_ok(),
+        );
+
+        Ok(result?.also_known_as)
+    }
+
+    async fn request_did(&self, did: &str) -> Result<DidResolution> {
         let response = self
             .client
             .get(format!("{}{did}", self.base_url))
             .send()
             .await?;
 
-        let body: DidResolution = response.json().await?;
-
-        Ok(body.also_known_as)
+        Ok(response.json().await?)
     }
 }
 
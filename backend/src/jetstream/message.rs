@@ -1,5 +1,5 @@
 use serde::Deserialize;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use tracing::error;
 
 use crate::models::topic::Topic;
@@ -7,9 +7,17 @@ use crate::models::topic::Topic;
 #[derive(Deserialize)]
 #[serde(tag = "kind", rename_all = "camelCase")]
 enum Message {
-    Commit { did: String, commit: Commit },
-    Identity,
-    Account,
+    Commit {
+        did: String,
+        time_us: i64,
+        commit: Commit,
+    },
+    Identity {
+        time_us: i64,
+    },
+    Account {
+        time_us: i64,
+    },
 }
 
 #[derive(Deserialize)]
@@ -53,6 +61,84 @@ enum Feature {
     Mention { did: String },
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventKind {
+    CommitCreate,
+    CommitUpdate,
+    CommitDelete,
+    Identity,
+    Account,
+}
+
+impl EventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::CommitCreate => "commit_create",
+            Self::CommitUpdate => "commit_update",
+            Self::CommitDelete => "commit_delete",
+            Self::Identity => "identity",
+            Self::Account => "account",
+        }
+    }
+}
+
+pub struct JetstreamEvent {
+    pub kind: EventKind,
+    pub time_us: i64,
+    pub post: Option<JetstreamMessage>,
+}
+
+impl JetstreamEvent {
+    pub fn new(message: String) -> Option<Self> {
+        let Ok(message) = serde_json::from_str::<Message>(&message) else {
+            error!("Invalid Jetstream message, ignoring: {message}");
+            return None;
+        };
+
+        let event = match message {
+            Message::Identity { time_us } => Self {
+                kind: EventKind::Identity,
+                time_us,
+                post: None,
+            },
+            Message::Account { time_us } => Self {
+                kind: EventKind::Account,
+                time_us,
+                post: None,
+            },
+            Message::Commit {
+                time_us,
+                commit: Commit::Update,
+                ..
+            } => Self {
+                kind: EventKind::CommitUpdate,
+                time_us,
+                post: None,
+            },
+            Message::Commit {
+                time_us,
+                commit: Commit::Delete,
+                ..
+            } => Self {
+                kind: EventKind::CommitDelete,
+                time_us,
+                post: None,
+            },
+            Message::Commit {
+                did,
+                time_us,
+                commit: Commit::Create { record, rkey, cid },
+            } => Self {
+                kind: EventKind::CommitCreate,
+                time_us,
+                post: Some(JetstreamMessage::new(did, record, rkey, cid)),
+            },
+        };
+
+        Some(event)
+    }
+}
+
 #[derive(Clone)]
 pub struct JetstreamMessage {
     pub langs: Vec<String>,
@@ -68,20 +154,7 @@ pub struct JetstreamMessage {
 }
 
 impl JetstreamMessage {
-    pub fn new(message: String) -> Option<Self> {
-        let Ok(message) = serde_json::from_str::<Message>(&message) else {
-            error!("Invalid Jetstream message, ignoring: {message}");
-            return None;
-        };
-
-        let Message::Commit { did, commit } = message else {
-            return None;
-        };
-
-        let Commit::Create { record, rkey, cid } = commit else {
-            return None;
-        };
-
+    fn new(did: String, record: Record, rkey: String, cid: String) -> Self {
         let (mentions, urls, tags) = record
             .facets
             .iter()
@@ -99,7 +172,7 @@ impl JetstreamMessage {
                 },
             );
 
-        Some(Self {
+        Self {
             rkey,
             cid,
             did,
@@ -110,21 +183,27 @@ impl JetstreamMessage {
             langs: record.langs,
             text: record.text,
             created_at: record.created_at,
-        })
+        }
     }
 
-    pub fn matches_any_topic(&self, topics: &[Topic]) -> BTreeSet<i64> {
+    /// Returns the matched topic ids along with the keywords which matched
+    pub fn matches_any_topic(&self, topics: &[Topic]) -> BTreeMap<i64, BTreeSet<String>> {
         topics
             .iter()
-            .filter(|t| self.matches_topic(t))
-            .map(|i| i.id)
+            .map(|t| (t.id, self.matched_keywords(t)))
+            .filter(|(_, keywords)| !keywords.is_empty())
             .collect()
     }
 
-    fn matches_topic(&self, topic: &Topic) -> bool {
-        topic.keywords.iter().any(|k| {
-            let k_lower = k.to_lowercase();
-            self.text_lower.split_whitespace().any(|w| w == k_lower)
-        })
+    fn matched_keywords(&self, topic: &Topic) -> BTreeSet<String> {
+        topic
+            .keywords
+            .iter()
+            .filter(|k| {
+                let k_lower = k.to_lowercase();
+                self.text_lower.split_whitespace().any(|w| w == k_lower)
+            })
+            .cloned()
+            .collect()
     }
 }
@@ -1,10 +1,11 @@
 use futures_util::{StreamExt, pin_mut};
+use message::JetstreamEvent;
 use std::time::Duration;
 use stream::JetstreamClient;
 use tokio::time::sleep;
 use tracing::{debug, error, info, warn};
 
-use crate::{Result, db, state::AppState};
+use crate::{Result, db, metrics::METRICS, state::AppState};
 
 mod did;
 pub mod message;
@@ -28,21 +29,22 @@ async fn start_inner(state: AppState) -> Result<()> {
     let processor = processor::Processor::new(state.config.jetstream.clone(), state.pool.clone())?;
 
     loop {
-        let mut jetstream_client = match JetstreamClient::new(state.config.jetstream.clone()).await
-        {
-            Ok(client) => client,
-            Err(error) => {
-                error!(
-                    "Error creating jetstream processor, will retry in {}s: {error}",
-                    state.config.jetstream.reconnect_interval
-                );
-                sleep(Duration::from_secs(
-                    state.config.jetstream.reconnect_interval,
-                ))
-                .await;
-                continue;
-            }
-        };
+        let mut jetstream_client =
+            match JetstreamClient::new(state.config.jetstream.clone(), state.stats.clone()).await {
+                Ok(client) => client,
+                Err(error) => {
+                    METRICS.jetstream_reconnects.inc();
+                    error!(
+                        "Error creating jetstream processor, will retry in {}s: {error}",
+                        state.config.jetstream.reconnect_interval
+                    );
+                    sleep(Duration::from_secs(
+                        state.config.jetstream.reconnect_interval,
+                    ))
+                    .await;
+                    continue;
+                }
+            };
 
         let stream = jetstream_client.read_message();
         pin_mut!(stream);
@@ -59,17 +61,19 @@ async fn start_inner(state: AppState) -> Result<()> {
             tokio::select! {
                 message = stream.next() => {
                     match message {
-                        Some(Ok(message)) => {
+                        Some(Ok(JetstreamEvent { post: Some(message), .. })) => {
                             if let Err(err) = processor.process_message(message, topics.clone(), state.clone()) {
                                 error!("Error processing message: {err}");
                             }
                         }
+                        Some(Ok(_)) => {}
                         Some(Err(err)) => {
                             error!("Error reading message: {err}");
                             continue;
                         }
                         None => {
                             warn!("Stream closed, reconnecting...");
+                            METRICS.jetstream_reconnects.inc();
                             break;
                         }
                     }
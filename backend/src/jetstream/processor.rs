@@ -1,11 +1,14 @@
+use chrono::Utc;
 use sqlx::SqlitePool;
-use std::collections::BTreeSet;
+use std::{collections::BTreeSet, time::Instant};
 use tracing::{error, info};
 
 use crate::{
     Result, config, db,
     jetstream::{did::DidClient, message::JetstreamMessage},
-    models::{post::CreatePost, topic::Topic, user::CreateUser},
+    metrics::{GaugeGuard, METRICS},
+    models::{post::CreatePost, timeseries::RollupIncrement, topic::Topic, user::CreateUser},
+    sentiment,
     state::AppState,
 };
 
@@ -29,9 +32,12 @@ impl Processor {
     ) -> Result<()> {
         let pool = self.pool.clone();
         let did_client = self.did_client.clone();
+        let in_flight = GaugeGuard::new(&METRICS.processor_tasks_in_flight);
 
         tokio::spawn(async move {
-            let topic_ids = message.matches_any_topic(&topics);
+            let _in_flight = in_flight;
+            let matched_topics = message.matches_any_topic(&topics);
+            let topic_ids = matched_topics.keys().copied().collect::<BTreeSet<i64>>();
 
             if !topic_ids.is_empty() {
                 info!(
@@ -48,8 +54,15 @@ impl Processor {
                         .chain(vec![message.did.clone()]),
                 );
 
+                let did_resolution_start = Instant::now();
                 let akas = did_client.resolve_dids(dids).await;
+                state
+                    .stats
+                    .record_did_resolution(did_resolution_start.elapsed());
+                let counted_at =
+                    RollupIncrement::counted_at(&message.created_at, Utc::now().naive_utc());
 
+                let database_write_start = Instant::now();
                 let mut tx = match pool.begin().await {
                     Ok(tx) => tx,
                     Err(e) => {
@@ -73,6 +86,13 @@ impl Processor {
                         e
                     })?;
 
+                let Some(author) = users.iter().find(|user| user.did == message.did) else {
+                    error!("Error finding author {} among created users", message.did);
+                    return Err(crate::Error::NotFound(format!("User {}", message.did)));
+                };
+
+                let sentiment = sentiment::score(&message.text, &message.langs);
+
                 let post = db::create_post(
                     &mut *tx,
                     CreatePost {
@@ -83,7 +103,8 @@ impl Processor {
                         langs: message.langs.clone(),
                         urls: message.urls.clone(),
                         tags: message.tags.clone(),
-                        author_id: users[0].id, // TODO: this is not the right author id, but good enough for testing
+                        author_id: author.id,
+                        sentiment,
                     },
                 )
                 .await
@@ -99,10 +120,29 @@ impl Processor {
                         e
                     })?;
 
+                db::increment_topic_post_rollups(
+                    &mut *tx,
+                    RollupIncrement::for_post(
+                        &matched_topics,
+                        &message.langs,
+                        sentiment,
+                        counted_at,
+                    ),
+                )
+                .await
+                .map_err(|e| {
+                    error!("Error incrementing topic post rollups: {:?}", e);
+                    e
+                })?;
+
                 db::link_mentions_to_post(
                     &mut *tx,
                     post.id,
-                    users.iter().map(|user| user.id).collect(),
+                    users
+                        .iter()
+                        .filter(|user| message.mentions.contains(&user.did))
+                        .map(|user| user.id)
+                        .collect(),
                 )
                 .await
                 .map_err(|e| {
@@ -115,6 +155,18 @@ impl Processor {
                     e
                 })?;
 
+                state
+                    .stats
+                    .record_database_write(database_write_start.elapsed());
+                state.stats.record_matched_post();
+                for topic in topics.iter().filter(|t| topic_ids.contains(&t.id)) {
+                    METRICS
+                        .processor_matches
+                        .with_label_values(&[&topic.slug])
+                        .inc();
+                }
+
+                state.alerts.on_posts_ingested(&topic_ids);
                 state.send_message(post, topic_ids, akas, message.did).await;
             }
 
@@ -5,37 +5,63 @@ use reqwest_websocket::{Bytes, Message, RequestBuilderExt, WebSocket};
 use tracing::{debug, info, warn};
 use zstd::dict::DecoderDictionary;
 
-use crate::{Result, config::Jetstream, jetstream::message::JetstreamMessage};
+use crate::{
+    Result, config::Jetstream, jetstream::message::JetstreamEvent, metrics::METRICS,
+    stats::IngestStats,
+};
 
 const DICTIONARY: &[u8] = include_bytes!("../zstd_dictionary");
 
 pub struct JetstreamClient {
     websocket: WebSocket,
+    stats: IngestStats,
 }
 
 impl JetstreamClient {
-    pub async fn new(config: Jetstream) -> Result<Self> {
+    pub async fn new(config: Jetstream, stats: IngestStats) -> Result<Self> {
         let client = Client::new().get(config.url()).upgrade().send().await?;
 
         let websocket = client.into_websocket().await?;
         info!("Connected to Jetstream at {}", config.url());
 
-        Ok(Self { websocket })
+        Ok(Self { websocket, stats })
     }
 
-    pub fn read_message(&mut self) -> impl Stream<Item = Result<JetstreamMessage>> {
+    pub fn read_message(&mut self) -> impl Stream<Item = Result<JetstreamEvent>> {
         try_stream! {
             while let Some(message) = self.websocket.try_next().await? {
-                if let Some(jetstream_message) = self.handle_message(message).await {
-                    yield jetstream_message;
+                if let Some(jetstream_event) = self.handle_message(message).await {
+                    yield jetstream_event;
                 }
             }
         }
     }
 
-    async fn handle_message(&mut self, message: Message) -> Option<JetstreamMessage> {
+    fn parse_event(&self, text: String) -> Option<JetstreamEvent> {
+        let event = JetstreamEvent::new(text);
+
+        match &event {
+            Some(event) => {
+                self.stats.record_event(event.kind, event.time_us);
+                METRICS
+                    .jetstream_messages
+                    .with_label_values(&[event.kind.as_str()])
+                    .inc();
+            }
+            None => self.record_decode_error(),
+        }
+
+        event
+    }
+
+    fn record_decode_error(&self) {
+        self.stats.record_parse_failure();
+        METRICS.jetstream_decode_errors.inc();
+    }
+
+    async fn handle_message(&mut self, message: Message) -> Option<JetstreamEvent> {
         match message {
-            Message::Text(text) => JetstreamMessage::new(text),
+            Message::Text(text) => self.parse_event(text),
             Message::Binary(items) => {
                 let reader = match zstd::Decoder::with_prepared_dictionary(
                     &*items,
@@ -44,14 +70,16 @@ impl JetstreamClient {
                     Ok(d) => d,
                     Err(e) => {
                         warn!("Failed to create decompressor with dictionary: {e}");
+                        self.record_decode_error();
                         return None;
                     }
                 };
 
                 match std::io::read_to_string(reader) {
-                    Ok(text) => JetstreamMessage::new(text),
+                    Ok(text) => self.parse_event(text),
                     Err(e) => {
                         warn!("Failed to decompress binary message: {e}");
+                        self.record_decode_error();
                         None
                     }
                 }
@@ -3,7 +3,7 @@ use axum::{
     body::Body,
     extract::{DefaultBodyLimit, Request},
 };
-use http::{HeaderName, HeaderValue, Method};
+use http::{HeaderName, HeaderValue, Method, Uri, header};
 use sentry::Hub;
 use sentry_tower::{NewFromTopProvider, SentryHttpLayer, SentryLayer};
 use std::{sync::Arc, time::Duration};
@@ -24,6 +24,8 @@ use tower_http::{
 };
 use tracing::Span;
 
+use crate::{auth, metrics::HttpMetricsLayer};
+
 pub struct CommonTowerLayerBuilder {
     allowed_methods: Vec<Method>,
     allowed_origins: AllowOrigin,
@@ -36,14 +38,25 @@ pub struct CommonTowerLayerBuilder {
     tracing: bool,
     enable_sentry_layer: bool,
     normalize_path: bool,
+    metrics: bool,
 }
 
 impl CommonTowerLayerBuilder {
     pub fn new(allowed_origin: String) -> Self {
         Self {
-            allowed_methods: vec![Method::GET, Method::POST, Method::PATCH, Method::DELETE],
+            allowed_methods: vec![
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::PATCH,
+                Method::DELETE,
+            ],
             allowed_origins: AllowOrigin::exact(HeaderValue::from_str(&allowed_origin).unwrap()),
-            allowed_headers: vec![HeaderName::from_static("content-type")],
+            allowed_headers: vec![
+                header::CONTENT_TYPE,
+                header::AUTHORIZATION,
+                HeaderName::from_static(auth::CSRF_HEADER),
+            ],
             exposed_headers: vec![],
             compression_level: CompressionLevel::Fastest,
             compression_size: 1024,
@@ -52,9 +65,15 @@ impl CommonTowerLayerBuilder {
             tracing: true,
             enable_sentry_layer: true,
             normalize_path: true,
+            metrics: true,
         }
     }
 
+    pub fn metrics(mut self, metrics: bool) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
     pub fn build(
         self,
     ) -> CommonTowerLayer<impl Fn(&Request<Body>) -> Span + Send + Clone + 'static + Sync> {
@@ -83,7 +102,7 @@ impl CommonTowerLayerBuilder {
                         "request",
                         user_id = tracing::field::Empty,
                         method = %request.method(),
-                        uri = %request.uri(),
+                        uri = %redact_share_token(request.uri()),
                         version = ?request.version(),
                     )
                 }),
@@ -111,6 +130,12 @@ impl CommonTowerLayerBuilder {
             None
         };
 
+        let metrics_layer = if self.metrics {
+            Some(HttpMetricsLayer)
+        } else {
+            None
+        };
+
         CommonTowerLayer {
             cors_layer,
             compression_layer,
@@ -119,6 +144,7 @@ impl CommonTowerLayerBuilder {
             tracing_layer,
             sentry_layer,
             normalize_path_layer,
+            metrics_layer,
         }
     }
 }
@@ -140,6 +166,7 @@ pub struct CommonTowerLayer<F: Fn(&Request<Body>) -> Span + Send + Clone + 'stat
     tracing_layer: Option<CTLTracingLayer<F>>,
     sentry_layer: Option<CTLSentryLayer>,
     normalize_path_layer: Option<NormalizePathLayer>,
+    metrics_layer: Option<HttpMetricsLayer>,
 }
 
 impl<F> CommonTowerLayer<F>
@@ -156,6 +183,10 @@ where
             .layer(self.compression_layer)
             .layer(self.cors_layer);
 
+        if let Some(layer) = self.metrics_layer {
+            router = router.layer(layer);
+        }
+
         if let Some(layer) = self.normalize_path_layer {
             router = router.layer(layer);
         }
@@ -171,3 +202,45 @@ where
         router
     }
 }
+
+/// URI of the request with the token of share links replaced, so they don't end up in the logs
+fn redact_share_token(uri: &Uri) -> String {
+    let segments = uri.path().split('/').collect::<Vec<_>>();
+    let path = segments
+        .iter()
+        .enumerate()
+        .map(
+            |(index, segment)| match index.checked_sub(1).map(|index| segments[index]) {
+                Some("shared") => "{token}",
+                _ => segment,
+            },
+        )
+        .collect::<Vec<_>>()
+        .join("/");
+
+    match uri.query() {
+        Some(query) => format!("{path}?{query}"),
+        None => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_given_share_link_uri_when_redact_share_token_return_uri_without_token() {
+        let uri = "/api/v1/shared/bfs_0123abcd/timeseries?bucket=1h"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            redact_share_token(&uri),
+            "/api/v1/shared/{token}/timeseries?bucket=1h"
+        );
+        assert_eq!(
+            redact_share_token(&"/api/v1/topics/1".parse().unwrap()),
+            "/api/v1/topics/1"
+        );
+    }
+}
@@ -0,0 +1,26 @@
+pub mod access;
+pub mod alerts;
+pub mod analysis;
+pub mod audit;
+pub mod auth;
+pub mod config;
+pub mod db;
+pub mod error;
+pub mod feed;
+pub mod gemini;
+pub mod jetstream;
+pub mod layers;
+pub mod metrics;
+pub mod models;
+pub mod oidc;
+pub mod routes;
+pub mod sentiment;
+pub mod server;
+pub mod share_links;
+pub mod slug;
+pub mod state;
+pub mod stats;
+pub mod throttle;
+pub mod timeseries;
+
+pub use error::{Error, Result};
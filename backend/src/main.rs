@@ -1,20 +1,8 @@
-mod auth;
-mod config;
-mod db;
-mod error;
-mod gemini;
-mod jetstream;
-mod layers;
-mod models;
-mod routes;
-mod server;
-mod slug;
-mod state;
-
+use backend::{
+    Result, alerts, analysis, auth, config, jetstream, server, state, stats, timeseries,
+};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-pub use error::{Error, Result};
-
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::registry()
@@ -27,6 +15,11 @@ async fn main() -> Result<()> {
     let config = config::Config::new()?;
     let state = state::AppState::new(config.clone()).await?;
 
+    stats::start_sampler(state.stats.clone());
     jetstream::start_processor(state.clone());
+    alerts::start_evaluator(state.clone());
+    timeseries::start_rollup_cleanup(state.clone());
+    analysis::start_scheduler(state.clone());
+    auth::start_session_cleanup(state.clone());
     server::start_server(state).await
 }
@@ -0,0 +1,250 @@
+use axum::{
+    body::Body,
+    extract::{MatchedPath, Request},
+    http::Response,
+};
+use futures_util::future::BoxFuture;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry,
+    TextEncoder,
+};
+use sqlx::SqlitePool;
+use std::{
+    sync::LazyLock,
+    task::{Context, Poll},
+    time::Instant,
+};
+use tower::{Layer, Service};
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub struct Metrics {
+    registry: Registry,
+    pub jetstream_messages: IntCounterVec,
+    pub jetstream_reconnects: IntCounter,
+    pub jetstream_decode_errors: IntCounter,
+    pub processor_matches: IntCounterVec,
+    pub processor_tasks_in_flight: IntGauge,
+    pub did_requests: IntCounterVec,
+    pub did_request_duration: HistogramVec,
+    pub gemini_requests: IntCounterVec,
+    pub gemini_request_duration: HistogramVec,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pub sqlite_pool_connections: IntGauge,
+    pub sqlite_pool_idle_connections: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("bluflare".to_string()), None).unwrap();
+
+        let metrics = Self {
+            jetstream_messages: IntCounterVec::new(
+                Opts::new(
+                    "jetstream_messages_total",
+                    "Messages received from Jetstream, by event kind",
+                ),
+                &["kind"],
+            )
+            .unwrap(),
+            jetstream_reconnects: IntCounter::new(
+                "jetstream_reconnects_total",
+                "Reconnections to Jetstream after a failure or a closed stream",
+            )
+            .unwrap(),
+            jetstream_decode_errors: IntCounter::new(
+                "jetstream_decode_errors_total",
+                "Jetstream messages which could not be decompressed or parsed",
+            )
+            .unwrap(),
+            processor_matches: IntCounterVec::new(
+                Opts::new("processor_matches_total", "Posts matched, by topic"),
+                &["topic"],
+            )
+            .unwrap(),
+            processor_tasks_in_flight: IntGauge::new(
+                "processor_tasks_in_flight",
+                "Spawned message processing tasks which have not completed yet",
+            )
+            .unwrap(),
+            did_requests: IntCounterVec::new(
+                Opts::new("did_requests_total", "Requests to the DID resolver"),
+                &["outcome"],
+            )
+            .unwrap(),
+            did_request_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "did_request_duration_seconds",
+                    "Latency of requests to the DID resolver",
+                ),
+                &["outcome"],
+            )
+            .unwrap(),
+            gemini_requests: IntCounterVec::new(
+                Opts::new("gemini_requests_total", "Requests to Gemini"),
+                &["outcome"],
+            )
+            .unwrap(),
+            gemini_request_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "gemini_request_duration_seconds",
+                    "Latency of requests to Gemini",
+                )
+                .buckets(vec![0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0]),
+                &["outcome"],
+            )
+            .unwrap(),
+            http_requests: IntCounterVec::new(
+                Opts::new("http_requests_total", "HTTP requests handled"),
+                &["method", "route", "status"],
+            )
+            .unwrap(),
+            http_request_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "http_request_duration_seconds",
+                    "Latency of HTTP requests until the response headers are sent",
+                ),
+                &["method", "route"],
+            )
+            .unwrap(),
+            sqlite_pool_connections: IntGauge::new(
+                "sqlite_pool_connections",
+                "Connections currently opened by the SQLite pool",
+            )
+            .unwrap(),
+            sqlite_pool_idle_connections: IntGauge::new(
+                "sqlite_pool_idle_connections",
+                "Idle connections of the SQLite pool",
+            )
+            .unwrap(),
+            registry,
+        };
+
+        metrics.register();
+        metrics
+    }
+
+    fn register(&self) {
+        let collectors: [Box<dyn prometheus::core::Collector>; 13] = [
+            Box::new(self.jetstream_messages.clone()),
+            Box::new(self.jetstream_reconnects.clone()),
+            Box::new(self.jetstream_decode_errors.clone()),
+            Box::new(self.processor_matches.clone()),
+            Box::new(self.processor_tasks_in_flight.clone()),
+            Box::new(self.did_requests.clone()),
+            Box::new(self.did_request_duration.clone()),
+            Box::new(self.gemini_requests.clone()),
+            Box::new(self.gemini_request_duration.clone()),
+            Box::new(self.http_requests.clone()),
+            Box::new(self.http_request_duration.clone()),
+            Box::new(self.sqlite_pool_connections.clone()),
+            Box::new(self.sqlite_pool_idle_connections.clone()),
+        ];
+
+        for collector in collectors {
+            self.registry.register(collector).unwrap();
+        }
+    }
+
+    /// Records a request to an external service, labelled as a `success` or an `error`
+    pub fn observe_request(
+        counter: &IntCounterVec,
+        histogram: &HistogramVec,
+        start: Instant,
+        success: bool,
+    ) {
+        let outcome = if success { "success" } else { "error" };
+
+        counter.with_label_values(&[outcome]).inc();
+        histogram
+            .with_label_values(&[outcome])
+            .observe(start.elapsed().as_secs_f64());
+    }
+
+    pub fn render(&self, pool: &SqlitePool) -> String {
+        self.sqlite_pool_connections.set(pool.size() as i64);
+        self.sqlite_pool_idle_connections
+            .set(pool.num_idle() as i64);
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .unwrap();
+
+        String::from_utf8(buffer).unwrap()
+    }
+}
+
+/// Increments a gauge until dropped
+pub struct GaugeGuard(IntGauge);
+
+impl GaugeGuard {
+    pub fn new(gauge: &IntGauge) -> Self {
+        gauge.inc();
+        Self(gauge.clone())
+    }
+}
+
+impl Drop for GaugeGuard {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+#[derive(Clone)]
+pub struct HttpMetricsLayer;
+
+impl<S> Layer<S> for HttpMetricsLayer {
+    type Service = HttpMetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        HttpMetricsService { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct HttpMetricsService<S> {
+    inner: S,
+}
+
+impl<S, B> Service<Request> for HttpMetricsService<S>
+where
+    S: Service<Request, Response = Response<B>> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let start = Instant::now();
+        let method = request.method().to_string();
+        let route = request
+            .extensions()
+            .get::<MatchedPath>()
+            .map(|path| path.as_str().to_string())
+            .unwrap_or_else(|| "unmatched".to_string());
+
+        let future = self.inner.call(request);
+
+        Box::pin(async move {
+            let response = future.await?;
+
+            METRICS
+                .http_requests
+                .with_label_values(&[&method, &route, response.status().as_str()])
+                .inc();
+            METRICS
+                .http_request_duration
+                .with_label_values(&[&method, &route])
+                .observe(start.elapsed().as_secs_f64());
+
+            Ok(response)
+        })
+    }
+}
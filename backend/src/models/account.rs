@@ -0,0 +1,52 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Roles are ordered, an account is allowed on every route requiring its role or a lower one
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum Role {
+    Viewer,
+    Editor,
+    Admin,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct Account {
+    pub id: i64,
+    pub created_at: NaiveDateTime,
+    pub username: String,
+    pub role: Role,
+    pub enabled: bool,
+    pub last_login_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AccountCredentials {
+    pub id: i64,
+    pub password_hash: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateAccount {
+    pub username: String,
+    pub password: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateAccount {
+    pub password: Option<String>,
+    pub role: Option<Role>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdatePassword {
+    pub current_password: String,
+    pub new_password: String,
+}
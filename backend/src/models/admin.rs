@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::topic::{Topic, Visibility};
+
+/// Portable definition of a topic, without its posts, analysis or ownership
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TopicExport {
+    pub subject: String,
+    pub description: String,
+    pub keywords: Vec<String>,
+    pub enabled: bool,
+    #[serde(default)]
+    pub visibility: Visibility,
+}
+
+impl From<Topic> for TopicExport {
+    fn from(topic: Topic) -> Self {
+        Self {
+            subject: topic.subject,
+            description: topic.description,
+            keywords: topic.keywords,
+            enabled: topic.enabled,
+            visibility: topic.visibility,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DatabaseStats {
+    pub topics: i64,
+    pub posts: i64,
+    pub users: i64,
+    pub accounts: i64,
+    pub active_sessions: i64,
+    pub active_api_tokens: i64,
+    pub alert_rules: i64,
+    pub oldest_post_at: Option<String>,
+    pub newest_post_at: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PruneResult {
+    pub posts: u64,
+    pub users: u64,
+    pub alert_events: u64,
+    pub sessions: u64,
+}
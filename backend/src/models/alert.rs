@@ -0,0 +1,124 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// More than `count` posts within the last `window_minutes`
+    Threshold { count: i64, window_minutes: i64 },
+    /// Volume within the last `window_minutes` is at least `factor` times the volume expected from
+    /// the trailing `baseline_hours`
+    Spike {
+        factor: f64,
+        window_minutes: i64,
+        #[serde(default = "default_baseline_hours")]
+        baseline_hours: i64,
+        #[serde(default = "default_min_count")]
+        min_count: i64,
+    },
+}
+
+fn default_baseline_hours() -> i64 {
+    24
+}
+
+fn default_min_count() -> i64 {
+    10
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationChannel {
+    Webhook { url: String },
+    Email { to: Vec<String> },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum AlertState {
+    Firing,
+    Resolved,
+}
+
+#[derive(Debug, FromRow)]
+pub struct DbAlertRule {
+    pub id: i64,
+    pub created_at: NaiveDateTime,
+    pub topic_id: i64,
+    pub name: String,
+    pub enabled: bool,
+    pub condition: Vec<u8>,
+    pub channels: Vec<u8>,
+    pub state: AlertState,
+    pub state_changed_at: Option<NaiveDateTime>,
+    pub last_evaluated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AlertRule {
+    pub id: i64,
+    pub created_at: NaiveDateTime,
+    pub topic_id: i64,
+    pub name: String,
+    pub enabled: bool,
+    pub condition: AlertCondition,
+    pub channels: Vec<NotificationChannel>,
+    pub state: AlertState,
+    pub state_changed_at: Option<NaiveDateTime>,
+    pub last_evaluated_at: Option<NaiveDateTime>,
+}
+
+impl From<DbAlertRule> for AlertRule {
+    fn from(db_rule: DbAlertRule) -> Self {
+        Self {
+            id: db_rule.id,
+            created_at: db_rule.created_at,
+            topic_id: db_rule.topic_id,
+            name: db_rule.name,
+            enabled: db_rule.enabled,
+            condition: serde_json::from_slice(&db_rule.condition).unwrap(),
+            channels: serde_json::from_slice(&db_rule.channels).unwrap(),
+            state: db_rule.state,
+            state_changed_at: db_rule.state_changed_at,
+            last_evaluated_at: db_rule.last_evaluated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateAlertRule {
+    pub name: String,
+    pub condition: AlertCondition,
+    #[serde(default)]
+    pub channels: Vec<NotificationChannel>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateAlertRule {
+    pub name: Option<String>,
+    pub enabled: Option<bool>,
+    pub condition: Option<AlertCondition>,
+    pub channels: Option<Vec<NotificationChannel>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct AlertEvent {
+    pub id: i64,
+    pub created_at: NaiveDateTime,
+    pub rule_id: i64,
+    pub state: AlertState,
+    pub value: f64,
+    pub baseline: Option<f64>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateAlertEvent {
+    pub rule_id: i64,
+    pub state: AlertState,
+    pub value: f64,
+    pub baseline: Option<f64>,
+    pub message: String,
+}
@@ -0,0 +1,294 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::{collections::HashSet, ops::AddAssign};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct TokenUsage {
+    pub prompt_tokens: i64,
+    /// Generated tokens, including the thinking ones
+    pub output_tokens: i64,
+}
+
+impl AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.output_tokens += other.output_tokens;
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct DbAnalysis {
+    pub id: i64,
+    pub created_at: NaiveDateTime,
+    pub topic_id: i64,
+    pub created_by: Option<i64>,
+    pub since: NaiveDateTime,
+    pub until: NaiveDateTime,
+    pub model: String,
+    pub prompt_version: i64,
+    pub matched_post_count: i64,
+    pub post_count: i64,
+    pub result: String,
+    pub prompt_tokens: i64,
+    pub output_tokens: i64,
+    pub structured_result: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Analysis {
+    pub id: i64,
+    pub created_at: NaiveDateTime,
+    pub topic_id: i64,
+    pub created_by: Option<i64>,
+    pub since: NaiveDateTime,
+    pub until: NaiveDateTime,
+    pub model: String,
+    pub prompt_version: i64,
+    /// Posts published in the window
+    pub matched_post_count: i64,
+    /// Posts sent to Gemini, after deduplication and sampling
+    pub post_count: i64,
+    /// Summary of the analysis, or the whole response when it couldn't be parsed
+    pub result: String,
+    /// `None` for analyses made before structured results or whose response couldn't be parsed
+    pub structured_result: Option<AnalysisResult>,
+    pub usage: TokenUsage,
+}
+
+impl From<DbAnalysis> for Analysis {
+    fn from(db_analysis: DbAnalysis) -> Self {
+        Self {
+            id: db_analysis.id,
+            created_at: db_analysis.created_at,
+            topic_id: db_analysis.topic_id,
+            created_by: db_analysis.created_by,
+            since: db_analysis.since,
+            until: db_analysis.until,
+            model: db_analysis.model,
+            prompt_version: db_analysis.prompt_version,
+            matched_post_count: db_analysis.matched_post_count,
+            post_count: db_analysis.post_count,
+            result: db_analysis.result,
+            structured_result: db_analysis
+                .structured_result
+                .and_then(|result| serde_json::from_str(&result).ok()),
+            usage: TokenUsage {
+                prompt_tokens: db_analysis.prompt_tokens,
+                output_tokens: db_analysis.output_tokens,
+            },
+        }
+    }
+}
+
+pub struct CreateAnalysis {
+    pub topic_id: i64,
+    pub created_by: Option<i64>,
+    pub since: NaiveDateTime,
+    pub until: NaiveDateTime,
+    pub model: String,
+    pub prompt_version: i64,
+    pub matched_post_count: i64,
+    pub post_count: i64,
+    pub result: String,
+    pub structured_result: Option<AnalysisResult>,
+    pub usage: TokenUsage,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AnalysisResult {
+    pub summary: String,
+    pub sentiment: Sentiment,
+    pub themes: Vec<Theme>,
+    pub notable_accounts: Vec<NotableAccount>,
+    /// Terms which appear to be new or quickly gaining use in the discussion
+    pub emerging_terms: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Sentiment {
+    /// From -1 (negative) to 1 (positive)
+    pub score: f64,
+    /// Shares of positive, neutral and negative posts, adding up to 1
+    pub positive: f64,
+    pub neutral: f64,
+    pub negative: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub description: String,
+    /// Example posts of the theme
+    pub post_ids: Vec<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NotableAccount {
+    pub handle: String,
+    pub reason: String,
+}
+
+impl AnalysisResult {
+    /// Parses and validates a response, ignoring a Markdown code block around it, and dropping
+    /// the example posts which weren't part of the analyzed `post_ids`
+    pub fn parse(response: &str, post_ids: &HashSet<i64>) -> Result<Self, String> {
+        let response = response.trim();
+        let response = response
+            .strip_prefix("```json")
+            .or_else(|| response.strip_prefix("```"))
+            .and_then(|response| response.strip_suffix("```"))
+            .unwrap_or(response);
+
+        let mut result: Self = serde_json::from_str(response).map_err(|e| e.to_string())?;
+
+        if result.summary.trim().is_empty() {
+            return Err("The summary is empty".to_string());
+        }
+
+        let sentiment = &mut result.sentiment;
+        if !(-1.0..=1.0).contains(&sentiment.score) {
+            return Err(format!("Invalid sentiment score {}", sentiment.score));
+        }
+
+        let shares = [sentiment.positive, sentiment.neutral, sentiment.negative];
+        let total = shares.iter().sum::<f64>();
+        if shares.iter().any(|share| !(0.0..=1.0).contains(share)) || total <= 0.0 {
+            return Err(format!("Invalid sentiment breakdown {shares:?}"));
+        }
+
+        // Models rarely round their shares to add up to exactly 1
+        sentiment.positive /= total;
+        sentiment.neutral /= total;
+        sentiment.negative /= total;
+
+        for theme in &mut result.themes {
+            theme.post_ids.retain(|id| post_ids.contains(id));
+        }
+
+        Ok(result)
+    }
+}
+
+/// Sentiment of an analysis, to chart how it evolves over time
+#[derive(Debug, Serialize)]
+pub struct SentimentPoint {
+    pub analysis_id: i64,
+    pub since: NaiveDateTime,
+    pub until: NaiveDateTime,
+    pub score: f64,
+    pub positive: f64,
+    pub neutral: f64,
+    pub negative: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SentimentQuery {
+    /// Only analyses whose window ends at or after
+    pub since: Option<DateTime<Utc>>,
+    /// Only analyses whose window ends at or before
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnalysesQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+impl AnalysesQuery {
+    pub const MAX_PER_PAGE: i64 = 100;
+
+    /// 1-based page and page size, clamped to valid values whose offset can't overflow
+    pub fn pagination(&self) -> (i64, i64) {
+        (
+            self.page
+                .unwrap_or(1)
+                .clamp(1, i64::MAX / Self::MAX_PER_PAGE),
+            self.per_page.unwrap_or(20).clamp(1, Self::MAX_PER_PAGE),
+        )
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnalysisPage {
+    pub analyses: Vec<Analysis>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompareAnalysesQuery {
+    pub from: i64,
+    pub to: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnalysisComparison {
+    pub from: Analysis,
+    pub to: Analysis,
+    pub matched_post_count_change: i64,
+    /// Relative change of the number of posts published per hour of the windows, `None` when
+    /// there were none in the `from` window
+    pub post_rate_change: Option<f64>,
+    /// How the discussion evolved between both analyses, described by Gemini when asked
+    pub evolution: Option<String>,
+}
+
+impl AnalysisComparison {
+    pub fn new(from: Analysis, to: Analysis, evolution: Option<String>) -> Self {
+        let rate = |analysis: &Analysis| {
+            let hours = (analysis.until - analysis.since).num_seconds() as f64 / 3600.0;
+            analysis.matched_post_count as f64 / hours
+        };
+
+        let (from_rate, to_rate) = (rate(&from), rate(&to));
+
+        Self {
+            matched_post_count_change: to.matched_post_count - from.matched_post_count,
+            post_rate_change: (from_rate > 0.0).then(|| (to_rate - from_rate) / from_rate),
+            from,
+            to,
+            evolution,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_given_json_response_when_parse_return_validated_result() {
+        let response = r#"```json
+{
+    "summary": "People discuss a release",
+    "sentiment": { "score": 0.4, "positive": 0.5, "neutral": 0.25, "negative": 0.5 },
+    "themes": [{ "name": "Release", "description": "The new model", "post_ids": [1, 42] }],
+    "notable_accounts": [{ "handle": "dev.bsky.social", "reason": "Announced it" }],
+    "emerging_terms": ["agents"]
+}
+```"#;
+
+        let result = AnalysisResult::parse(response, &HashSet::from([1, 2])).unwrap();
+
+        assert_eq!(result.themes[0].post_ids, vec![1]);
+        assert_eq!(result.sentiment.positive, 0.4);
+        assert_eq!(result.notable_accounts[0].handle, "dev.bsky.social");
+    }
+
+    #[test]
+    fn test_given_invalid_response_when_parse_return_error() {
+        let parse = |response| AnalysisResult::parse(response, &HashSet::new());
+
+        assert!(parse("## Summary\nPeople discuss a release").is_err());
+        assert!(
+            parse(
+                r#"{"summary": "s", "sentiment": {"score": 3, "positive": 1, "neutral": 0,
+                "negative": 0}, "themes": [], "notable_accounts": [], "emerging_terms": []}"#
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    #[serde(rename = "topics:read")]
+    TopicsRead,
+    #[serde(rename = "topics:write")]
+    TopicsWrite,
+    #[serde(rename = "analysis:run")]
+    AnalysisRun,
+}
+
+#[derive(Debug, FromRow)]
+pub struct DbApiToken {
+    pub id: i64,
+    pub created_at: NaiveDateTime,
+    pub account_id: i64,
+    pub name: String,
+    pub scopes: Vec<u8>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApiToken {
+    pub id: i64,
+    pub created_at: NaiveDateTime,
+    pub account_id: i64,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+impl From<DbApiToken> for ApiToken {
+    fn from(db_token: DbApiToken) -> Self {
+        Self {
+            id: db_token.id,
+            created_at: db_token.created_at,
+            account_id: db_token.account_id,
+            name: db_token.name,
+            scopes: serde_json::from_slice(&db_token.scopes).unwrap(),
+            expires_at: db_token.expires_at,
+            last_used_at: db_token.last_used_at,
+            revoked_at: db_token.revoked_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiToken {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub expires_in_days: Option<i64>,
+}
+
+/// Returned once on creation, only the hash of the token is stored
+#[derive(Debug, Serialize)]
+pub struct CreatedApiToken {
+    #[serde(flatten)]
+    pub api_token: ApiToken,
+    pub token: String,
+}
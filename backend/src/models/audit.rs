@@ -0,0 +1,161 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum AuditAction {
+    Login,
+    LoginFailed,
+    Logout,
+    PasswordUpdate,
+    SessionRevoke,
+    ApiTokenCreate,
+    ApiTokenRevoke,
+    AccountCreate,
+    AccountUpdate,
+    AccountDelete,
+    TopicCreate,
+    TopicUpdate,
+    TopicDelete,
+    TopicAnalyze,
+    TopicAsk,
+    TopicShare,
+    TopicUnshare,
+    KeywordsSuggest,
+    AlertRuleCreate,
+    AlertRuleUpdate,
+    AlertRuleDelete,
+    ShareLinkCreate,
+    ShareLinkRevoke,
+    AnalysisScheduleUpdate,
+    AnalysisScheduleDelete,
+    UserAnalyze,
+}
+
+impl AuditAction {
+    pub fn target_type(&self) -> &'static str {
+        match self {
+            Self::Login | Self::LoginFailed | Self::Logout | Self::SessionRevoke => "session",
+            Self::PasswordUpdate
+            | Self::AccountCreate
+            | Self::AccountUpdate
+            | Self::AccountDelete => "account",
+            Self::ApiTokenCreate | Self::ApiTokenRevoke => "api_token",
+            Self::TopicCreate
+            | Self::TopicUpdate
+            | Self::TopicDelete
+            | Self::TopicAnalyze
+            | Self::TopicAsk
+            | Self::TopicShare
+            | Self::TopicUnshare => "topic",
+            Self::KeywordsSuggest => "keywords",
+            Self::AlertRuleCreate | Self::AlertRuleUpdate | Self::AlertRuleDelete => "alert_rule",
+            Self::ShareLinkCreate | Self::ShareLinkRevoke => "share_link",
+            Self::AnalysisScheduleUpdate | Self::AnalysisScheduleDelete => "analysis_schedule",
+            Self::UserAnalyze => "user",
+        }
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct DbAuditEntry {
+    pub id: i64,
+    pub created_at: NaiveDateTime,
+    pub actor_account_id: Option<i64>,
+    pub actor_username: Option<String>,
+    pub actor_api_token_id: Option<i64>,
+    pub action: AuditAction,
+    pub target_type: String,
+    pub target_id: Option<i64>,
+    pub before: Option<Vec<u8>>,
+    pub after: Option<Vec<u8>>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub created_at: NaiveDateTime,
+    pub actor_account_id: Option<i64>,
+    pub actor_username: Option<String>,
+    pub actor_api_token_id: Option<i64>,
+    pub action: AuditAction,
+    pub target_type: String,
+    pub target_id: Option<i64>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl From<DbAuditEntry> for AuditEntry {
+    fn from(db_entry: DbAuditEntry) -> Self {
+        Self {
+            id: db_entry.id,
+            created_at: db_entry.created_at,
+            actor_account_id: db_entry.actor_account_id,
+            actor_username: db_entry.actor_username,
+            actor_api_token_id: db_entry.actor_api_token_id,
+            action: db_entry.action,
+            target_type: db_entry.target_type,
+            target_id: db_entry.target_id,
+            before: db_entry
+                .before
+                .map(|before| serde_json::from_slice(&before).unwrap()),
+            after: db_entry
+                .after
+                .map(|after| serde_json::from_slice(&after).unwrap()),
+            ip: db_entry.ip,
+            user_agent: db_entry.user_agent,
+        }
+    }
+}
+
+pub struct CreateAuditEntry {
+    pub actor_account_id: Option<i64>,
+    pub actor_username: Option<String>,
+    pub actor_api_token_id: Option<i64>,
+    pub action: AuditAction,
+    pub target_id: Option<i64>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditLogQuery {
+    pub actor_account_id: Option<i64>,
+    pub action: Option<AuditAction>,
+    pub target_type: Option<String>,
+    pub target_id: Option<i64>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+impl AuditLogQuery {
+    pub const MAX_PER_PAGE: i64 = 200;
+
+    /// 1-based page and page size, clamped to valid values whose offset can't overflow
+    pub fn pagination(&self) -> (i64, i64) {
+        (
+            self.page
+                .unwrap_or(1)
+                .clamp(1, i64::MAX / Self::MAX_PER_PAGE),
+            self.per_page.unwrap_or(50).clamp(1, Self::MAX_PER_PAGE),
+        )
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditLogPage {
+    pub entries: Vec<AuditEntry>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
@@ -1,3 +1,15 @@
+pub mod account;
+pub mod admin;
+pub mod alert;
+pub mod analysis;
+pub mod api_token;
+pub mod audit;
 pub mod post;
+pub mod question;
+pub mod schedule;
+pub mod session;
+pub mod share_link;
+pub mod timeseries;
 pub mod topic;
+pub mod usage;
 pub mod user;
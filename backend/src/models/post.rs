@@ -12,6 +12,7 @@ pub struct DbPost {
     pub urls: Vec<u8>,
     pub tags: Vec<u8>,
     pub author_id: i64,
+    pub sentiment: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -25,6 +26,9 @@ pub struct Post {
     pub urls: Vec<String>,
     pub tags: Vec<String>,
     pub author_id: i64,
+    /// Local sentiment score from -1 (negative) to 1 (positive), `None` when the post's languages
+    /// aren't supported or it was ingested before posts were scored
+    pub sentiment: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,6 +41,7 @@ pub struct CreatePost {
     pub urls: Vec<String>,
     pub tags: Vec<String>,
     pub author_id: i64,
+    pub sentiment: Option<f64>,
 }
 
 impl From<DbPost> for Post {
@@ -51,6 +56,7 @@ impl From<DbPost> for Post {
             urls: serde_json::from_slice(&db_post.urls).unwrap(),
             tags: serde_json::from_slice(&db_post.tags).unwrap(),
             author_id: db_post.author_id,
+            sentiment: db_post.sentiment,
         }
     }
 }
@@ -66,6 +72,7 @@ pub struct DbPostWithAuthor {
     pub urls: Vec<u8>,
     pub tags: Vec<u8>,
     pub author_id: i64,
+    pub sentiment: Option<f64>,
     pub aka: Vec<u8>,
     pub did: String,
 }
@@ -78,6 +85,16 @@ pub struct PostWithAuthor {
     pub did: String,
 }
 
+impl PostWithAuthor {
+    /// Handle of the author, or its DID when it has none
+    pub fn author(&self) -> &str {
+        self.aka
+            .first()
+            .map(|aka| aka.trim_start_matches("at://"))
+            .unwrap_or(&self.did)
+    }
+}
+
 impl From<DbPostWithAuthor> for PostWithAuthor {
     fn from(db_post: DbPostWithAuthor) -> Self {
         PostWithAuthor {
@@ -91,9 +108,43 @@ impl From<DbPostWithAuthor> for PostWithAuthor {
                 urls: serde_json::from_slice(&db_post.urls).unwrap(),
                 tags: serde_json::from_slice(&db_post.tags).unwrap(),
                 author_id: db_post.author_id,
+                sentiment: db_post.sentiment,
             },
             aka: serde_json::from_slice(&db_post.aka).unwrap(),
             did: db_post.did,
         }
     }
 }
+
+#[derive(Debug, FromRow)]
+pub struct DbAnalysisPost {
+    pub id: i64,
+    pub text: String,
+    pub aka: Vec<u8>,
+    pub did: String,
+}
+
+/// Post sent to Gemini when analyzing a topic
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalysisPost {
+    pub id: i64,
+    /// Handle of the author, or its DID when it has none
+    pub author: String,
+    pub text: String,
+}
+
+impl From<DbAnalysisPost> for AnalysisPost {
+    fn from(db_post: DbAnalysisPost) -> Self {
+        let aka: Vec<String> = serde_json::from_slice(&db_post.aka).unwrap();
+
+        AnalysisPost {
+            id: db_post.id,
+            author: aka
+                .into_iter()
+                .next()
+                .map(|aka| aka.trim_start_matches("at://").to_string())
+                .unwrap_or(db_post.did),
+            text: db_post.text,
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use serde::Deserialize;
+use std::collections::BTreeSet;
+
+use crate::{gemini::analysis::estimate_tokens, models::post::PostWithAuthor};
+
+/// Words too common to help find the posts relevant to a question
+const STOP_WORDS: &[&str] = &[
+    "a", "about", "an", "and", "any", "are", "as", "at", "be", "been", "but", "by", "can", "did",
+    "do", "does", "for", "from", "had", "has", "have", "how", "i", "if", "in", "is", "it", "its",
+    "me", "my", "new", "of", "on", "or", "our", "people", "say", "saying", "said", "so", "some",
+    "than", "that", "the", "their", "them", "there", "these", "they", "this", "to", "up", "us",
+    "was", "we", "were", "what", "when", "where", "which", "who", "why", "will", "with", "would",
+    "you", "au", "aux", "avec", "ce", "ces", "comment", "dans", "de", "des", "du", "elle", "en",
+    "est", "et", "il", "ils", "la", "le", "les", "leur", "mais", "ou", "par", "pour", "quel",
+    "que", "qui", "quoi", "sur", "un", "une",
+];
+
+/// Words shorter than this only match whole words, longer ones also matching words they prefix
+const MIN_PREFIX_LENGTH: usize = 4;
+
+#[derive(Debug, Deserialize)]
+pub struct AskQuestion {
+    pub question: String,
+}
+
+impl AskQuestion {
+    pub const MAX_LENGTH: usize = 500;
+
+    /// Trimmed question, an error when it's empty or too long
+    pub fn validate(&self) -> Result<&str, String> {
+        let question = self.question.trim();
+
+        if question.is_empty() {
+            return Err("question can't be empty".to_string());
+        }
+
+        if question.chars().count() > Self::MAX_LENGTH {
+            return Err(format!(
+                "question can't be longer than {} characters",
+                Self::MAX_LENGTH
+            ));
+        }
+
+        Ok(question)
+    }
+}
+
+/// FTS5 query matching posts with any of the significant words of the question, `None` when it
+/// has none
+pub fn search_query(question: &str) -> Option<String> {
+    let words = question
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1 && !STOP_WORDS.contains(word))
+        .map(str::to_string)
+        .collect::<BTreeSet<_>>();
+
+    if words.is_empty() {
+        return None;
+    }
+
+    let terms = words
+        .into_iter()
+        .map(|word| match word.chars().count() >= MIN_PREFIX_LENGTH {
+            true => format!("\"{word}\"*"),
+            false => format!("\"{word}\""),
+        })
+        .collect::<Vec<_>>();
+
+    Some(terms.join(" OR "))
+}
+
+/// Retrieved posts sent along with a question, most relevant first
+#[derive(Debug, PartialEq)]
+pub struct QuestionContext {
+    /// Posts as `[{id}] @{author}: {text}`
+    pub posts: Vec<String>,
+    pub post_ids: Vec<i64>,
+}
+
+impl QuestionContext {
+    /// Most relevant posts fitting in `max_tokens`
+    pub fn new(posts: &[PostWithAuthor], max_tokens: usize) -> Self {
+        let mut tokens = 0;
+        let (post_ids, posts) = posts
+            .iter()
+            .map(|post| {
+                (
+                    post.post.id,
+                    format!("[{}] @{}: {}", post.post.id, post.author(), post.post.text),
+                )
+            })
+            .take_while(|(_, post)| {
+                tokens += estimate_tokens(post);
+                tokens <= max_tokens
+            })
+            .unzip();
+
+        Self { posts, post_ids }
+    }
+}
+
+/// IDs of the retrieved posts cited in brackets by the answer, e.g. `[123]` or `[123, 456]`, in
+/// order of first citation
+pub fn cited_post_ids(answer: &str, post_ids: &[i64]) -> Vec<i64> {
+    let mut cited = Vec::new();
+
+    for citation in answer.split('[').skip(1) {
+        let Some((ids, _)) = citation.split_once(']') else {
+            continue;
+        };
+
+        for id in ids.split([',', ';']) {
+            let Ok(id) = id.trim().parse::<i64>() else {
+                continue;
+            };
+
+            if post_ids.contains(&id) && !cited.contains(&id) {
+                cited.push(id);
+            }
+        }
+    }
+
+    cited
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::post::Post;
+
+    fn post(id: i64, text: &str) -> PostWithAuthor {
+        PostWithAuthor {
+            post: Post {
+                id,
+                cid: "cid".to_string(),
+                rkey: "rkey".to_string(),
+                created_at: "2025-09-21T12:00:00Z".to_string(),
+                text: text.to_string(),
+                langs: vec![],
+                urls: vec![],
+                tags: vec![],
+                author_id: 1,
+                sentiment: None,
+            },
+            aka: vec!["at://alice.bsky.social".to_string()],
+            did: "did:plc:abc".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_given_question_when_search_query_return_significant_words() {
+        assert_eq!(
+            search_query("What are people saying about the new CVE in OpenSSL?"),
+            Some("\"cve\" OR \"openssl\"*".to_string())
+        );
+        assert_eq!(
+            search_query("Que disent les gens de la faille d'OpenSSL ?"),
+            Some("\"disent\"* OR \"faille\"* OR \"gens\"* OR \"openssl\"*".to_string())
+        );
+        assert_eq!(search_query("what is it?"), None);
+    }
+
+    #[test]
+    fn test_given_posts_when_new_context_return_most_relevant_posts_within_tokens() {
+        let posts = vec![
+            post(3, &"a".repeat(40)),
+            post(1, &"b".repeat(40)),
+            post(2, "c"),
+        ];
+
+        let context = QuestionContext::new(&posts, 30);
+
+        assert_eq!(context.post_ids, vec![3]);
+        assert_eq!(
+            context.posts[0],
+            format!("[3] @alice.bsky.social: {}", "a".repeat(40))
+        );
+    }
+
+    #[test]
+    fn test_given_answer_when_cited_post_ids_return_known_ids_in_order() {
+        let answer = "Patches are out [12, 7]. Some doubt it [99] [7][link](x) [12;3]";
+
+        assert_eq!(cited_post_ids(answer, &[3, 7, 12]), vec![12, 7, 3]);
+    }
+}
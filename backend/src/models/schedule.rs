@@ -0,0 +1,172 @@
+use chrono::{DateTime, Days, DurationRound, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum Frequency {
+    /// At the start of every hour, analyzing the previous hour
+    Hourly,
+    /// Every day at a time of a time zone, analyzing the previous 24 hours
+    Daily,
+}
+
+impl Frequency {
+    pub fn period(&self) -> TimeDelta {
+        match self {
+            Self::Hourly => TimeDelta::hours(1),
+            Self::Daily => TimeDelta::days(1),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum ScheduleStatus {
+    Running,
+    Succeeded,
+    /// No post was published since the previous run
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Clone, FromRow)]
+pub struct AnalysisSchedule {
+    pub topic_id: i64,
+    pub created_at: NaiveDateTime,
+    pub created_by: Option<i64>,
+    pub enabled: bool,
+    pub frequency: Frequency,
+    pub time: Option<NaiveTime>,
+    pub timezone: String,
+    /// UTC
+    pub next_run_at: NaiveDateTime,
+    pub last_run_at: Option<NaiveDateTime>,
+    pub last_status: Option<ScheduleStatus>,
+    pub last_error: Option<String>,
+    pub last_analysis_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpsertAnalysisSchedule {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub frequency: Frequency,
+    /// Time of the day of daily analyses, e.g. `08:00`
+    pub time: Option<NaiveTime>,
+    /// IANA time zone of `time`, e.g. `America/Toronto`, UTC by default
+    pub timezone: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl UpsertAnalysisSchedule {
+    /// Time zone of the schedule, checking that only daily schedules have a time
+    pub fn validate(&self) -> Result<Tz, String> {
+        let timezone = match &self.timezone {
+            Some(timezone) => timezone
+                .parse::<Tz>()
+                .map_err(|_| format!("Unknown time zone {timezone}"))?,
+            None => Tz::UTC,
+        };
+
+        match (self.frequency, self.time) {
+            (Frequency::Daily, None) => Err("Daily schedules require a time".to_string()),
+            (Frequency::Hourly, Some(_)) => Err("Hourly schedules can't have a time".to_string()),
+            _ => Ok(timezone),
+        }
+    }
+}
+
+/// First run strictly after `after`, in UTC, skipping times which don't exist on the day of a DST
+/// change to the following hour
+pub fn next_run_at(
+    frequency: Frequency,
+    time: Option<NaiveTime>,
+    timezone: Tz,
+    after: NaiveDateTime,
+) -> NaiveDateTime {
+    let time = match (frequency, time) {
+        (Frequency::Daily, Some(time)) => time,
+        _ => {
+            return after.duration_trunc(TimeDelta::hours(1)).unwrap_or(after)
+                + TimeDelta::hours(1);
+        }
+    };
+
+    let after_utc = Utc.from_utc_datetime(&after);
+    let mut date = after_utc.with_timezone(&timezone).date_naive();
+
+    loop {
+        let local = date.and_time(time);
+        let run = timezone
+            .from_local_datetime(&local)
+            .earliest()
+            .or_else(|| {
+                timezone
+                    .from_local_datetime(&(local + TimeDelta::hours(1)))
+                    .earliest()
+            })
+            .map(|run| run.with_timezone(&Utc));
+
+        if let Some(run) = run.filter(|run: &DateTime<Utc>| *run > after_utc) {
+            return run.naive_utc();
+        }
+
+        date = date + Days::new(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datetime(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_given_hourly_schedule_when_next_run_at_return_start_of_next_hour() {
+        let next = |after| next_run_at(Frequency::Hourly, None, Tz::UTC, datetime(after));
+
+        assert_eq!(next("2025-08-24 10:15:00"), datetime("2025-08-24 11:00:00"));
+        assert_eq!(next("2025-08-24 11:00:00"), datetime("2025-08-24 12:00:00"));
+    }
+
+    #[test]
+    fn test_given_daily_schedule_when_next_run_at_return_time_in_timezone() {
+        let time = NaiveTime::from_hms_opt(8, 0, 0);
+        let next = |after| {
+            next_run_at(
+                Frequency::Daily,
+                time,
+                Tz::America__Toronto,
+                datetime(after),
+            )
+        };
+
+        // 08:00 in Toronto is 12:00 UTC in summer
+        assert_eq!(next("2025-08-24 11:00:00"), datetime("2025-08-24 12:00:00"));
+        assert_eq!(next("2025-08-24 12:00:00"), datetime("2025-08-25 12:00:00"));
+        // and 13:00 UTC once back to standard time
+        assert_eq!(next("2025-11-02 03:00:00"), datetime("2025-11-02 13:00:00"));
+    }
+
+    #[test]
+    fn test_given_daily_time_skipped_by_dst_when_next_run_at_return_following_hour() {
+        // 02:30 doesn't exist in Toronto on 2025-03-09, clocks going from 02:00 to 03:00
+        let run = next_run_at(
+            Frequency::Daily,
+            NaiveTime::from_hms_opt(2, 30, 0),
+            Tz::America__Toronto,
+            datetime("2025-03-09 05:00:00"),
+        );
+
+        assert_eq!(run, datetime("2025-03-09 07:30:00"));
+    }
+}
@@ -0,0 +1,32 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::FromRow;
+
+#[derive(Debug, Serialize, Clone, FromRow)]
+pub struct Session {
+    pub id: i64,
+    pub account_id: i64,
+    pub created_at: NaiveDateTime,
+    pub last_seen_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    #[serde(skip)]
+    pub csrf_token: String,
+}
+
+pub struct CreateSession {
+    pub token_hash: String,
+    pub account_id: i64,
+    pub expires_at: NaiveDateTime,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub csrf_token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionWithCurrent {
+    #[serde(flatten)]
+    pub session: Session,
+    pub current: bool,
+}
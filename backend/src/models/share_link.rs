@@ -0,0 +1,40 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct ShareLink {
+    pub id: i64,
+    pub created_at: NaiveDateTime,
+    pub topic_id: i64,
+    pub created_by: Option<i64>,
+    pub name: String,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateShareLink {
+    pub name: String,
+    pub expires_in_days: Option<i64>,
+}
+
+/// Returned once on creation, only the hash of the token is stored
+#[derive(Debug, Serialize)]
+pub struct CreatedShareLink {
+    #[serde(flatten)]
+    pub share_link: ShareLink,
+    pub token: String,
+    /// Path of the read-only API of the topic for this link
+    pub path: String,
+}
+
+/// What a share link exposes of a topic, leaving out its keywords, owner and settings
+#[derive(Debug, Serialize)]
+pub struct SharedTopic {
+    pub subject: String,
+    pub description: String,
+    pub last_analysis: Option<String>,
+    pub last_analysis_at: Option<NaiveDateTime>,
+}
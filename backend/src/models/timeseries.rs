@@ -0,0 +1,500 @@
+use chrono::{DateTime, DurationRound, NaiveDateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::sentiment::SentimentLabel;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Bucket {
+    #[serde(rename = "1m")]
+    Minute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "1h")]
+    Hour,
+    #[serde(rename = "1d")]
+    Day,
+}
+
+impl Bucket {
+    /// Buckets maintained on insert with every dimension, `1m` only keeps the total for alerts
+    pub const ROLLUPS: [Bucket; 3] = [Bucket::FiveMinutes, Bucket::Hour, Bucket::Day];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Minute => "1m",
+            Self::FiveMinutes => "5m",
+            Self::Hour => "1h",
+            Self::Day => "1d",
+        }
+    }
+
+    pub fn duration(&self) -> TimeDelta {
+        match self {
+            Self::Minute => TimeDelta::minutes(1),
+            Self::FiveMinutes => TimeDelta::minutes(5),
+            Self::Hour => TimeDelta::hours(1),
+            Self::Day => TimeDelta::days(1),
+        }
+    }
+
+    pub fn truncate(&self, datetime: NaiveDateTime) -> NaiveDateTime {
+        datetime.duration_trunc(self.duration()).unwrap_or(datetime)
+    }
+
+    fn default_range(&self) -> TimeDelta {
+        match self {
+            Self::Minute | Self::FiveMinutes => TimeDelta::hours(6),
+            Self::Hour => TimeDelta::days(7),
+            Self::Day => TimeDelta::days(90),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Dimension {
+    Total,
+    Language,
+    Keyword,
+    /// Local sentiment label of the posts, see [`crate::sentiment`]
+    Sentiment,
+}
+
+impl Dimension {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Total => "total",
+            Self::Language => "language",
+            Self::Keyword => "keyword",
+            Self::Sentiment => "sentiment",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RollupIncrement {
+    pub topic_id: i64,
+    pub bucket: Bucket,
+    pub bucket_start: NaiveDateTime,
+    pub dimension: Dimension,
+    pub dimension_value: String,
+    /// Sentiment score added to the row, only set on `sentiment` rows
+    pub sentiment_sum: f64,
+}
+
+impl RollupIncrement {
+    /// Time a post is counted at, its creation time capped to `now` like the rollups backfilled
+    /// from stored posts, or `now` when it can't be parsed
+    pub fn counted_at(created_at: &str, now: NaiveDateTime) -> NaiveDateTime {
+        DateTime::parse_from_rfc3339(created_at)
+            .map(|created_at| created_at.naive_utc().min(now))
+            .unwrap_or(now)
+    }
+
+    /// Every rollup row to increment for a post counted at `counted_at`, `topics` being the
+    /// matched topic ids with the keywords which matched
+    pub fn for_post(
+        topics: &BTreeMap<i64, BTreeSet<String>>,
+        langs: &[String],
+        sentiment: Option<f64>,
+        counted_at: NaiveDateTime,
+    ) -> Vec<Self> {
+        let langs = langs.iter().collect::<BTreeSet<_>>();
+
+        topics
+            .iter()
+            .flat_map(|(topic_id, keywords)| {
+                let minute = Self {
+                    topic_id: *topic_id,
+                    bucket: Bucket::Minute,
+                    bucket_start: Bucket::Minute.truncate(counted_at),
+                    dimension: Dimension::Total,
+                    dimension_value: String::new(),
+                    sentiment_sum: 0.0,
+                };
+
+                let rollups = Bucket::ROLLUPS.into_iter().flat_map(|bucket| {
+                    let bucket_start = bucket.truncate(counted_at);
+                    let increment = |dimension, dimension_value: &str| Self {
+                        topic_id: *topic_id,
+                        bucket,
+                        bucket_start,
+                        dimension,
+                        dimension_value: dimension_value.to_string(),
+                        sentiment_sum: 0.0,
+                    };
+
+                    std::iter::once(increment(Dimension::Total, ""))
+                        .chain(
+                            langs
+                                .iter()
+                                .map(move |lang| increment(Dimension::Language, lang)),
+                        )
+                        .chain(
+                            keywords
+                                .iter()
+                                .map(move |keyword| increment(Dimension::Keyword, keyword)),
+                        )
+                        .chain(sentiment.map(|sentiment| RollupIncrement {
+                            sentiment_sum: sentiment,
+                            ..increment(
+                                Dimension::Sentiment,
+                                SentimentLabel::from_score(sentiment).as_str(),
+                            )
+                        }))
+                        .collect::<Vec<_>>()
+                });
+
+                std::iter::once(minute).chain(rollups)
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimeseriesQuery {
+    pub bucket: Bucket,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub split_by: Option<Dimension>,
+}
+
+impl TimeseriesQuery {
+    pub const MAX_POINTS: i64 = 2016;
+
+    /// Returns the `[since, until)` range aligned on bucket boundaries
+    pub fn range(&self, now: NaiveDateTime) -> Result<(NaiveDateTime, NaiveDateTime), String> {
+        let until = self.until.map(|until| until.naive_utc()).unwrap_or(now);
+        let since = self
+            .since
+            .map(|since| since.naive_utc())
+            .unwrap_or(until - self.bucket.default_range());
+
+        let since = self.bucket.truncate(since);
+        let until = self.bucket.truncate(until) + self.bucket.duration();
+
+        if self.bucket == Bucket::Minute
+            && self
+                .split_by
+                .is_some_and(|split_by| split_by != Dimension::Total)
+        {
+            return Err("1m buckets can't be split by language, keyword or sentiment".to_string());
+        }
+
+        if since >= until {
+            return Err("since must be before until".to_string());
+        }
+
+        let points = (until - since).num_seconds() / self.bucket.duration().num_seconds();
+        if points > Self::MAX_POINTS {
+            return Err(format!(
+                "Requested {points} {} buckets, at most {} are allowed",
+                self.bucket.as_str(),
+                Self::MAX_POINTS
+            ));
+        }
+
+        Ok((since, until))
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct DbRollup {
+    pub bucket_start: NaiveDateTime,
+    pub dimension_value: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct TimeseriesPoint {
+    pub bucket_start: NaiveDateTime,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct TimeseriesSeries {
+    pub key: String,
+    pub total: i64,
+    pub points: Vec<TimeseriesPoint>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Timeseries {
+    pub topic_id: i64,
+    pub bucket: Bucket,
+    pub since: NaiveDateTime,
+    pub until: NaiveDateTime,
+    pub split_by: Dimension,
+    pub series: Vec<TimeseriesSeries>,
+}
+
+impl Timeseries {
+    /// Groups rollup rows per dimension value, filling empty buckets with zeros
+    pub fn from_rollups(
+        topic_id: i64,
+        bucket: Bucket,
+        (since, until): (NaiveDateTime, NaiveDateTime),
+        split_by: Dimension,
+        rollups: Vec<DbRollup>,
+    ) -> Self {
+        let mut counts: BTreeMap<String, BTreeMap<NaiveDateTime, i64>> = BTreeMap::new();
+        for rollup in rollups {
+            *counts
+                .entry(rollup.dimension_value)
+                .or_default()
+                .entry(rollup.bucket_start)
+                .or_default() += rollup.count;
+        }
+
+        if split_by == Dimension::Total && counts.is_empty() {
+            counts.insert(String::new(), BTreeMap::new());
+        }
+
+        let mut series = counts
+            .into_iter()
+            .map(|(key, counts)| {
+                let points = std::iter::successors(Some(since), |start| {
+                    Some(*start + bucket.duration()).filter(|start| *start < until)
+                })
+                .map(|bucket_start| TimeseriesPoint {
+                    bucket_start,
+                    count: counts.get(&bucket_start).copied().unwrap_or_default(),
+                })
+                .collect();
+
+                TimeseriesSeries {
+                    key: if key.is_empty() {
+                        split_by.as_str().to_string()
+                    } else {
+                        key
+                    },
+                    total: counts.values().sum(),
+                    points,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        series.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.key.cmp(&b.key)));
+
+        Self {
+            topic_id,
+            bucket,
+            since,
+            until,
+            split_by,
+            series,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SentimentTimeseriesQuery {
+    pub bucket: Bucket,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl SentimentTimeseriesQuery {
+    pub fn range(&self, now: NaiveDateTime) -> Result<(NaiveDateTime, NaiveDateTime), String> {
+        TimeseriesQuery {
+            bucket: self.bucket,
+            since: self.since,
+            until: self.until,
+            split_by: Some(Dimension::Sentiment),
+        }
+        .range(now)
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct DbSentimentRollup {
+    pub bucket_start: NaiveDateTime,
+    pub dimension_value: String,
+    pub count: i64,
+    pub sentiment_sum: f64,
+}
+
+#[derive(Debug, Serialize, Default, PartialEq)]
+pub struct SentimentTimeseriesPoint {
+    pub bucket_start: NaiveDateTime,
+    pub positive: i64,
+    pub neutral: i64,
+    pub negative: i64,
+    /// Average score of the scored posts of the bucket, `None` when there are none
+    pub average: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SentimentTimeseries {
+    pub topic_id: i64,
+    pub bucket: Bucket,
+    pub since: NaiveDateTime,
+    pub until: NaiveDateTime,
+    pub points: Vec<SentimentTimeseriesPoint>,
+}
+
+impl SentimentTimeseries {
+    /// Counts posts per sentiment label and averages their scores, filling empty buckets
+    pub fn from_rollups(
+        topic_id: i64,
+        bucket: Bucket,
+        (since, until): (NaiveDateTime, NaiveDateTime),
+        rollups: Vec<DbSentimentRollup>,
+    ) -> Self {
+        let mut buckets: BTreeMap<NaiveDateTime, (SentimentTimeseriesPoint, f64)> = BTreeMap::new();
+        for rollup in rollups {
+            let (point, sum) = buckets.entry(rollup.bucket_start).or_default();
+            match rollup.dimension_value.as_str() {
+                "positive" => point.positive += rollup.count,
+                "negative" => point.negative += rollup.count,
+                _ => point.neutral += rollup.count,
+            }
+            *sum += rollup.sentiment_sum;
+        }
+
+        let points = std::iter::successors(Some(since), |start| {
+            Some(*start + bucket.duration()).filter(|start| *start < until)
+        })
+        .map(|bucket_start| {
+            let (point, sum) = buckets.remove(&bucket_start).unwrap_or_default();
+            let count = point.positive + point.neutral + point.negative;
+
+            SentimentTimeseriesPoint {
+                bucket_start,
+                average: (count > 0).then(|| sum / count as f64),
+                ..point
+            }
+        })
+        .collect();
+
+        Self {
+            topic_id,
+            bucket,
+            since,
+            until,
+            points,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datetime(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_given_datetime_when_truncate_return_bucket_start() {
+        let at = datetime("2025-06-08 13:47:12");
+
+        assert_eq!(Bucket::Minute.truncate(at), datetime("2025-06-08 13:47:00"));
+        assert_eq!(
+            Bucket::FiveMinutes.truncate(at),
+            datetime("2025-06-08 13:45:00")
+        );
+        assert_eq!(Bucket::Hour.truncate(at), datetime("2025-06-08 13:00:00"));
+        assert_eq!(Bucket::Day.truncate(at), datetime("2025-06-08 00:00:00"));
+    }
+
+    #[test]
+    fn test_given_created_at_when_counted_at_return_creation_time_capped_to_now() {
+        let now = datetime("2025-06-08 13:47:12");
+
+        assert_eq!(
+            RollupIncrement::counted_at("2025-06-08T13:40:05.123+02:00", now),
+            datetime("2025-06-08 11:40:05") + TimeDelta::milliseconds(123)
+        );
+        assert_eq!(
+            RollupIncrement::counted_at("2025-06-09T00:00:00Z", now),
+            now
+        );
+        assert_eq!(RollupIncrement::counted_at("yesterday", now), now);
+    }
+
+    #[test]
+    fn test_given_post_when_for_post_return_increment_per_bucket_and_dimension() {
+        let topics = BTreeMap::from([(1, BTreeSet::from(["ai".to_string()]))]);
+        let langs = vec!["en".to_string(), "en".to_string()];
+        let increments =
+            RollupIncrement::for_post(&topics, &langs, Some(-0.6), datetime("2025-06-08 13:47:12"));
+
+        // 1m total, then total, language, keyword and sentiment for each of the 3 rollup buckets
+        assert_eq!(increments.len(), 1 + 3 * 4);
+        assert_eq!(increments[0].bucket, Bucket::Minute);
+        assert!(increments.iter().any(|i| i.bucket == Bucket::Hour
+            && i.dimension == Dimension::Keyword
+            && i.dimension_value == "ai"
+            && i.bucket_start == datetime("2025-06-08 13:00:00")));
+        assert!(increments.iter().any(|i| i.bucket == Bucket::Day
+            && i.dimension == Dimension::Sentiment
+            && i.dimension_value == "negative"
+            && i.sentiment_sum == -0.6));
+    }
+
+    #[test]
+    fn test_given_too_many_buckets_when_range_return_error() {
+        let query = TimeseriesQuery {
+            bucket: Bucket::FiveMinutes,
+            since: Some(DateTime::from_timestamp(0, 0).unwrap()),
+            until: None,
+            split_by: None,
+        };
+
+        assert!(query.range(datetime("2025-06-08 13:47:12")).is_err());
+    }
+
+    #[test]
+    fn test_given_sparse_rollups_when_from_rollups_return_zero_filled_points() {
+        let range = (
+            datetime("2025-06-08 10:00:00"),
+            datetime("2025-06-08 13:00:00"),
+        );
+        let rollups = vec![DbRollup {
+            bucket_start: datetime("2025-06-08 11:00:00"),
+            dimension_value: String::new(),
+            count: 4,
+        }];
+
+        let timeseries =
+            Timeseries::from_rollups(1, Bucket::Hour, range, Dimension::Total, rollups);
+
+        assert_eq!(timeseries.series.len(), 1);
+        assert_eq!(timeseries.series[0].key, "total");
+        assert_eq!(timeseries.series[0].total, 4);
+        assert_eq!(
+            timeseries.series[0]
+                .points
+                .iter()
+                .map(|p| p.count)
+                .collect::<Vec<_>>(),
+            vec![0, 4, 0]
+        );
+    }
+
+    #[test]
+    fn test_given_sentiment_rollups_when_from_rollups_return_counts_and_average_per_bucket() {
+        let range = (
+            datetime("2025-06-08 10:00:00"),
+            datetime("2025-06-08 12:00:00"),
+        );
+        let rollup = |label: &str, count, sentiment_sum| DbSentimentRollup {
+            bucket_start: datetime("2025-06-08 11:00:00"),
+            dimension_value: label.to_string(),
+            count,
+            sentiment_sum,
+        };
+        let rollups = vec![rollup("positive", 3, 1.5), rollup("negative", 1, -0.7)];
+
+        let timeseries = SentimentTimeseries::from_rollups(1, Bucket::Hour, range, rollups);
+
+        assert_eq!(timeseries.points.len(), 2);
+        assert_eq!(timeseries.points[0].average, None);
+        assert_eq!(timeseries.points[1].positive, 3);
+        assert_eq!(timeseries.points[1].negative, 1);
+        assert!((timeseries.points[1].average.unwrap() - 0.2).abs() < 1e-9);
+    }
+}
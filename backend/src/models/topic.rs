@@ -1,7 +1,32 @@
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+/// Who can see a topic besides its owner, the accounts it's shared with and admins
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum Visibility {
+    /// Only shared accounts
+    Private,
+    /// Every account
+    Team,
+    /// Everyone, including unauthenticated visitors
+    #[default]
+    Public,
+}
+
+/// Ordered, an editor can also do what a viewer can
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum ShareRole {
+    Viewer,
+    Editor,
+}
+
 #[derive(Debug, FromRow)]
 pub struct DbTopic {
     pub id: i64,
@@ -13,6 +38,11 @@ pub struct DbTopic {
     pub keywords: Vec<u8>,
     pub last_analysis: Option<String>,
     pub last_analysis_at: Option<NaiveDateTime>,
+    pub last_analysis_since: Option<NaiveDateTime>,
+    pub last_analysis_until: Option<NaiveDateTime>,
+    pub last_analysis_post_count: Option<i64>,
+    pub owner_id: Option<i64>,
+    pub visibility: Visibility,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -26,6 +56,11 @@ pub struct Topic {
     pub keywords: Vec<String>,
     pub last_analysis: Option<String>,
     pub last_analysis_at: Option<NaiveDateTime>,
+    pub last_analysis_since: Option<NaiveDateTime>,
+    pub last_analysis_until: Option<NaiveDateTime>,
+    pub last_analysis_post_count: Option<i64>,
+    pub owner_id: Option<i64>,
+    pub visibility: Visibility,
 }
 
 impl From<DbTopic> for Topic {
@@ -40,6 +75,11 @@ impl From<DbTopic> for Topic {
             keywords: serde_json::from_slice(&db_topic.keywords).unwrap(),
             last_analysis: db_topic.last_analysis,
             last_analysis_at: db_topic.last_analysis_at,
+            last_analysis_since: db_topic.last_analysis_since,
+            last_analysis_until: db_topic.last_analysis_until,
+            last_analysis_post_count: db_topic.last_analysis_post_count,
+            owner_id: db_topic.owner_id,
+            visibility: db_topic.visibility,
         }
     }
 }
@@ -55,6 +95,11 @@ pub struct DbTopicWithPostCount {
     pub keywords: Vec<u8>,
     pub last_analysis: Option<String>,
     pub last_analysis_at: Option<NaiveDateTime>,
+    pub last_analysis_since: Option<NaiveDateTime>,
+    pub last_analysis_until: Option<NaiveDateTime>,
+    pub last_analysis_post_count: Option<i64>,
+    pub owner_id: Option<i64>,
+    pub visibility: Visibility,
     pub post_count: i64,
 }
 
@@ -69,6 +114,11 @@ pub struct TopicWithPostCount {
     pub keywords: Vec<String>,
     pub last_analysis: Option<String>,
     pub last_analysis_at: Option<NaiveDateTime>,
+    pub last_analysis_since: Option<NaiveDateTime>,
+    pub last_analysis_until: Option<NaiveDateTime>,
+    pub last_analysis_post_count: Option<i64>,
+    pub owner_id: Option<i64>,
+    pub visibility: Visibility,
     pub post_count: i64,
 }
 
@@ -84,6 +134,11 @@ impl From<DbTopicWithPostCount> for TopicWithPostCount {
             keywords: serde_json::from_slice(&db_topic.keywords).unwrap(),
             last_analysis: db_topic.last_analysis,
             last_analysis_at: db_topic.last_analysis_at,
+            last_analysis_since: db_topic.last_analysis_since,
+            last_analysis_until: db_topic.last_analysis_until,
+            last_analysis_post_count: db_topic.last_analysis_post_count,
+            owner_id: db_topic.owner_id,
+            visibility: db_topic.visibility,
             post_count: db_topic.post_count,
         }
     }
@@ -94,6 +149,8 @@ pub struct CreateTopic {
     pub subject: String,
     pub description: String,
     pub keywords: Vec<String>,
+    #[serde(default)]
+    pub visibility: Visibility,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -101,10 +158,63 @@ pub struct UpdateTopic {
     pub description: Option<String>,
     pub keywords: Option<Vec<String>>,
     pub enabled: Option<bool>,
+    pub visibility: Option<Visibility>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateTopicAnalysis {
     pub last_analysis: String,
     pub last_analysis_at: NaiveDateTime,
+    pub last_analysis_since: NaiveDateTime,
+    pub last_analysis_until: NaiveDateTime,
+    pub last_analysis_post_count: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnalyzeTopicQuery {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl AnalyzeTopicQuery {
+    /// Returns the `[since, until)` window of posts to analyze, the last `default_window` by default
+    pub fn window(
+        &self,
+        now: NaiveDateTime,
+        default_window: TimeDelta,
+        max_window: TimeDelta,
+    ) -> Result<(NaiveDateTime, NaiveDateTime), String> {
+        let until = self.until.map(|until| until.naive_utc()).unwrap_or(now);
+        let since = self
+            .since
+            .map(|since| since.naive_utc())
+            .unwrap_or(until - default_window);
+
+        if since >= until {
+            return Err("since must be before until".to_string());
+        }
+
+        if until - since > max_window {
+            return Err(format!(
+                "The window can't be longer than {} days",
+                max_window.num_days()
+            ));
+        }
+
+        Ok((since, until))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct TopicShare {
+    pub topic_id: i64,
+    pub account_id: i64,
+    pub username: String,
+    pub created_at: NaiveDateTime,
+    pub role: ShareRole,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShareTopic {
+    pub role: ShareRole,
 }
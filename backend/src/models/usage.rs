@@ -0,0 +1,268 @@
+use chrono::{DateTime, Datelike, Months, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::collections::BTreeMap;
+
+use crate::Error;
+
+/// Feature which called the LLM
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum LlmPurpose {
+    Keywords,
+    Analysis,
+    AnalysisComparison,
+    UserAnalysis,
+    Question,
+}
+
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum LlmCallOutcome {
+    Succeeded,
+    Blocked,
+    QuotaExceeded,
+    MalformedResponse,
+    Unavailable,
+    Rejected,
+    Failed,
+}
+
+impl From<&Error> for LlmCallOutcome {
+    fn from(error: &Error) -> Self {
+        match error {
+            Error::LlmBlocked(_) => Self::Blocked,
+            Error::LlmQuotaExceeded(_) => Self::QuotaExceeded,
+            Error::LlmMalformedResponse(_) => Self::MalformedResponse,
+            Error::LlmUnavailable(_) => Self::Unavailable,
+            Error::LlmRejected(_) => Self::Rejected,
+            _ => Self::Failed,
+        }
+    }
+}
+
+/// What triggered an LLM call, recorded along with its usage
+#[derive(Debug, Clone, Copy)]
+pub struct UsageContext {
+    pub purpose: LlmPurpose,
+    pub account_id: Option<i64>,
+    pub topic_id: Option<i64>,
+}
+
+pub struct CreateLlmCall {
+    pub context: UsageContext,
+    pub model: String,
+    pub prompt_tokens: i64,
+    pub output_tokens: i64,
+    pub cost: f64,
+    pub latency_ms: i64,
+    pub outcome: LlmCallOutcome,
+    pub error: Option<String>,
+}
+
+/// Usage of the LLM calls of a day sharing the same purpose, model, outcome, account and topic
+#[derive(Debug, FromRow)]
+pub struct UsageRow {
+    pub day: NaiveDate,
+    pub purpose: LlmPurpose,
+    pub model: String,
+    pub outcome: LlmCallOutcome,
+    pub account_id: Option<i64>,
+    pub topic_id: Option<i64>,
+    pub calls: i64,
+    pub prompt_tokens: i64,
+    pub output_tokens: i64,
+    pub cost: f64,
+    pub latency_ms: i64,
+}
+
+#[derive(Debug, Serialize, Default, Clone, PartialEq)]
+pub struct UsageTotals {
+    pub calls: i64,
+    /// Calls whose outcome isn't `succeeded`
+    pub failed_calls: i64,
+    pub prompt_tokens: i64,
+    pub output_tokens: i64,
+    /// In the currency of the configured prices
+    pub cost: f64,
+    /// Sum of the latencies of the calls, including their retries
+    pub latency_ms: i64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, row: &UsageRow) {
+        self.calls += row.calls;
+        if row.outcome != LlmCallOutcome::Succeeded {
+            self.failed_calls += row.calls;
+        }
+        self.prompt_tokens += row.prompt_tokens;
+        self.output_tokens += row.output_tokens;
+        self.cost += row.cost;
+        self.latency_ms += row.latency_ms;
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct UsageGroup<K> {
+    pub key: K,
+    #[serde(flatten)]
+    pub usage: UsageTotals,
+}
+
+/// Usage of the current month compared to the configured budgets
+#[derive(Debug, Serialize)]
+pub struct UsageBudget {
+    pub month_start: NaiveDateTime,
+    pub tokens: i64,
+    pub cost: f64,
+    /// `None` when unlimited
+    pub token_budget: Option<i64>,
+    pub cost_budget: Option<f64>,
+}
+
+impl UsageBudget {
+    pub fn exceeded(&self) -> bool {
+        self.token_budget
+            .is_some_and(|budget| self.tokens >= budget)
+            || self.cost_budget.is_some_and(|budget| self.cost >= budget)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageReport {
+    pub since: NaiveDateTime,
+    pub until: NaiveDateTime,
+    pub total: UsageTotals,
+    pub budget: UsageBudget,
+    pub by_day: Vec<UsageGroup<NaiveDate>>,
+    pub by_purpose: Vec<UsageGroup<LlmPurpose>>,
+    pub by_model: Vec<UsageGroup<String>>,
+    pub by_outcome: Vec<UsageGroup<LlmCallOutcome>>,
+    /// Calls without an account were triggered by schedules or deleted accounts
+    pub by_account: Vec<UsageGroup<Option<i64>>>,
+    pub by_topic: Vec<UsageGroup<Option<i64>>>,
+}
+
+impl UsageReport {
+    pub fn new(
+        (since, until): (NaiveDateTime, NaiveDateTime),
+        rows: &[UsageRow],
+        budget: UsageBudget,
+    ) -> Self {
+        let mut total = UsageTotals::default();
+        for row in rows {
+            total.add(row);
+        }
+
+        Self {
+            since,
+            until,
+            total,
+            budget,
+            by_day: group(rows, |row| row.day),
+            by_purpose: group(rows, |row| row.purpose),
+            by_model: group(rows, |row| row.model.clone()),
+            by_outcome: group(rows, |row| row.outcome),
+            by_account: group(rows, |row| row.account_id),
+            by_topic: group(rows, |row| row.topic_id),
+        }
+    }
+}
+
+fn group<K: Ord>(rows: &[UsageRow], key: impl Fn(&UsageRow) -> K) -> Vec<UsageGroup<K>> {
+    let mut groups = BTreeMap::<K, UsageTotals>::new();
+    for row in rows {
+        groups.entry(key(row)).or_default().add(row);
+    }
+
+    groups
+        .into_iter()
+        .map(|(key, usage)| UsageGroup { key, usage })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UsageQuery {
+    /// Start of the current month by default
+    pub since: Option<DateTime<Utc>>,
+    /// Now by default
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// Start of the UTC month of `now` and of the following one, when budgets are reset
+pub fn month_bounds(now: NaiveDateTime) -> (NaiveDateTime, NaiveDateTime) {
+    let start = NaiveDate::from_ymd_opt(now.year(), now.month(), 1)
+        .expect("The first day of a month is valid")
+        .and_hms_opt(0, 0, 0)
+        .expect("Midnight is valid");
+
+    (start, start + Months::new(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(day: u32, purpose: LlmPurpose, outcome: LlmCallOutcome, calls: i64) -> UsageRow {
+        UsageRow {
+            day: NaiveDate::from_ymd_opt(2025, 8, day).unwrap(),
+            purpose,
+            model: "gemini-2.5-flash".to_string(),
+            outcome,
+            account_id: Some(1),
+            topic_id: None,
+            calls,
+            prompt_tokens: calls * 100,
+            output_tokens: calls * 10,
+            cost: calls as f64,
+            latency_ms: calls * 1000,
+        }
+    }
+
+    #[test]
+    fn test_given_rows_when_report_return_totals_grouped_by_key() {
+        let (since, until) = month_bounds(
+            NaiveDate::from_ymd_opt(2025, 8, 31)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap(),
+        );
+        let rows = [
+            row(1, LlmPurpose::Keywords, LlmCallOutcome::Succeeded, 2),
+            row(1, LlmPurpose::Analysis, LlmCallOutcome::Unavailable, 1),
+            row(2, LlmPurpose::Analysis, LlmCallOutcome::Succeeded, 3),
+        ];
+        let budget = UsageBudget {
+            month_start: since,
+            tokens: 660,
+            cost: 6.0,
+            token_budget: None,
+            cost_budget: Some(5.0),
+        };
+
+        let report = UsageReport::new((since, until), &rows, budget);
+
+        assert_eq!(until.to_string(), "2025-09-01 00:00:00");
+        assert_eq!(report.total.calls, 6);
+        assert_eq!(report.total.failed_calls, 1);
+        assert_eq!(report.total.prompt_tokens, 600);
+        assert_eq!(report.by_day.len(), 2);
+        assert_eq!(report.by_day[0].usage.calls, 3);
+        assert_eq!(
+            report
+                .by_purpose
+                .iter()
+                .map(|group| (group.key, group.usage.calls))
+                .collect::<Vec<_>>(),
+            vec![(LlmPurpose::Keywords, 2), (LlmPurpose::Analysis, 4)]
+        );
+        assert_eq!(report.by_account.len(), 1);
+        assert!(report.budget.exceeded());
+    }
+}
@@ -2,6 +2,8 @@ use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::models::post::PostWithAuthor;
+
 #[derive(Debug, FromRow)]
 pub struct DbUser {
     pub id: i64,
@@ -9,8 +11,6 @@ pub struct DbUser {
     pub did: String,
     pub aka: Vec<u8>,
     pub aka_retrieved_at: NaiveDateTime,
-    pub last_analysis: Option<String>,
-    pub last_analysis_at: Option<NaiveDateTime>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -20,8 +20,6 @@ pub struct User {
     pub did: String,
     pub aka: Vec<String>,
     pub aka_retrieved_at: NaiveDateTime,
-    pub last_analysis: Option<String>,
-    pub last_analysis_at: Option<NaiveDateTime>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -38,8 +36,104 @@ impl From<DbUser> for User {
             did: db_user.did,
             aka: serde_json::from_slice(&db_user.aka).unwrap(),
             aka_retrieved_at: db_user.aka_retrieved_at,
-            last_analysis: db_user.last_analysis,
-            last_analysis_at: db_user.last_analysis_at,
         }
     }
 }
+
+/// User along with its activity in the topics visible to the caller
+#[derive(Debug, Serialize)]
+pub struct UserProfile {
+    #[serde(flatten)]
+    pub user: User,
+    pub post_count: i64,
+    pub mention_count: i64,
+    /// Latest analysis of the user requested by the caller
+    pub last_analysis: Option<LastUserAnalysis>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserPostsQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+impl UserPostsQuery {
+    pub const MAX_PER_PAGE: i64 = 100;
+
+    /// 1-based page and page size, clamped to valid values whose offset can't overflow
+    pub fn pagination(&self) -> (i64, i64) {
+        (
+            self.page
+                .unwrap_or(1)
+                .clamp(1, i64::MAX / Self::MAX_PER_PAGE),
+            self.per_page.unwrap_or(20).clamp(1, Self::MAX_PER_PAGE),
+        )
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserPostPage {
+    pub posts: Vec<PostWithAuthor>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+/// Topic a user posted in or was mentioned in
+#[derive(Debug, Serialize, FromRow)]
+pub struct UserTopic {
+    pub id: i64,
+    pub slug: String,
+    pub subject: String,
+    pub post_count: i64,
+    pub mention_count: i64,
+}
+
+#[derive(Debug, FromRow)]
+pub struct DbUserMentioner {
+    pub did: String,
+    pub aka: Vec<u8>,
+    pub mention_count: i64,
+    pub last_mentioned_at: String,
+}
+
+/// Author of posts mentioning a user
+#[derive(Debug, Serialize)]
+pub struct UserMentioner {
+    pub did: String,
+    pub aka: Vec<String>,
+    pub mention_count: i64,
+    pub last_mentioned_at: String,
+}
+
+impl From<DbUserMentioner> for UserMentioner {
+    fn from(db_mentioner: DbUserMentioner) -> Self {
+        UserMentioner {
+            did: db_mentioner.did,
+            aka: serde_json::from_slice(&db_mentioner.aka).unwrap(),
+            mention_count: db_mentioner.mention_count,
+            last_mentioned_at: db_mentioner.last_mentioned_at,
+        }
+    }
+}
+
+/// Analysis of a user stored for the account which requested it
+#[derive(Debug, Serialize, FromRow)]
+pub struct LastUserAnalysis {
+    pub analysis: String,
+    pub analyzed_at: NaiveDateTime,
+    pub matched_post_count: i64,
+    pub post_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserAnalysis {
+    pub did: String,
+    pub analysis: String,
+    pub analyzed_at: NaiveDateTime,
+    /// Posts of the user in the topics visible to the caller
+    pub matched_post_count: i64,
+    /// Posts sent to the LLM
+    pub post_count: i64,
+    pub topics: Vec<UserTopic>,
+}
@@ -0,0 +1,555 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::Utc;
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::OnceCell;
+use tracing::info;
+
+use crate::{Error, Result, auth::random_token, config, models::account::Role};
+
+/// Time a user has to complete the login at the issuer
+const FLOW_TTL: Duration = Duration::from_secs(10 * 60);
+/// Logins started but not completed that are kept, the oldest ones being dropped past it
+const MAX_PENDING_FLOWS: usize = 10_000;
+
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+}
+
+struct PendingFlow {
+    nonce: String,
+    code_verifier: String,
+    created_at: Instant,
+}
+
+struct Inner {
+    client: Client,
+    config: config::Oidc,
+    metadata: OnceCell<ProviderMetadata>,
+    flows: Mutex<PendingFlows>,
+}
+
+/// Logins started at the issuer by their `state`, along with the order they were started in
+#[derive(Default)]
+struct PendingFlows {
+    by_state: HashMap<String, PendingFlow>,
+    order: VecDeque<(Instant, String)>,
+}
+
+impl PendingFlows {
+    /// Adds a flow after dropping the expired ones, and the oldest ones past `MAX_PENDING_FLOWS`
+    fn insert(&mut self, state: String, flow: PendingFlow) {
+        while let Some((created_at, _)) = self.order.front() {
+            if created_at.elapsed() < FLOW_TTL && self.order.len() < MAX_PENDING_FLOWS {
+                break;
+            }
+
+            if let Some((_, state)) = self.order.pop_front() {
+                self.by_state.remove(&state);
+            }
+        }
+
+        self.order.push_back((flow.created_at, state.clone()));
+        self.by_state.insert(state, flow);
+    }
+
+    fn remove(&mut self, state: &str) -> Option<PendingFlow> {
+        self.by_state
+            .remove(state)
+            .filter(|flow| flow.created_at.elapsed() < FLOW_TTL)
+    }
+}
+
+/// OpenID Connect relying party using the authorization code flow with PKCE
+#[derive(Clone)]
+pub struct OidcClient {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct OidcIdentity {
+    pub subject: String,
+    pub username: String,
+    pub email: Option<String>,
+    pub groups: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Audience {
+    fn contains(&self, client_id: &str) -> bool {
+        match self {
+            Self::One(audience) => audience == client_id,
+            Self::Many(audiences) => audiences.iter().any(|audience| audience == client_id),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct IdTokenClaims {
+    iss: String,
+    sub: String,
+    aud: Audience,
+    exp: i64,
+    nonce: Option<String>,
+    email: Option<String>,
+    email_verified: Option<bool>,
+    preferred_username: Option<String>,
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
+}
+
+impl OidcClient {
+    pub fn new(config: &config::Auth) -> Result<Option<Self>> {
+        let Some(oidc) = config
+            .oidc
+            .clone()
+            .filter(|oidc| config.enabled && oidc.enabled)
+        else {
+            return Ok(None);
+        };
+
+        info!("Initializing OIDC client for issuer {}", oidc.issuer_url);
+
+        let client = Client::builder().timeout(Duration::from_secs(10)).build()?;
+
+        Ok(Some(Self {
+            inner: Arc::new(Inner {
+                client,
+                config: oidc,
+                metadata: OnceCell::new(),
+                flows: Mutex::default(),
+            }),
+        }))
+    }
+
+    pub fn post_login_redirect_url(&self) -> &str {
+        self.inner
+            .config
+            .post_login_redirect_url
+            .as_deref()
+            .unwrap_or("/")
+    }
+
+    /// Discovery document of the issuer, fetched once on first use
+    async fn metadata(&self) -> Result<&ProviderMetadata> {
+        self.inner
+            .metadata
+            .get_or_try_init(|| async {
+                let url = format!(
+                    "{}/.well-known/openid-configuration",
+                    self.inner.config.issuer_url.trim_end_matches('/')
+                );
+
+                let metadata = self
+                    .inner
+                    .client
+                    .get(url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<ProviderMetadata>()
+                    .await?;
+
+                Ok(metadata)
+            })
+            .await
+    }
+
+    /// Starts a login, returning the URL of the issuer to redirect to and the `state` binding the
+    /// callback to this flow
+    pub async fn authorization_url(&self) -> Result<(String, String)> {
+        let metadata = self.metadata().await?;
+
+        let state = random_token();
+        let nonce = random_token();
+        let code_verifier = random_token();
+
+        let url = Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            [
+                ("response_type", "code"),
+                ("client_id", &self.inner.config.client_id),
+                ("redirect_uri", &self.inner.config.redirect_url),
+                ("scope", "openid email profile"),
+                ("state", &state),
+                ("nonce", &nonce),
+                ("code_challenge", &code_challenge(&code_verifier)),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|err| Error::Oidc(format!("Invalid authorization endpoint: {err}")))?;
+
+        self.inner.flows.lock().unwrap().insert(
+            state.clone(),
+            PendingFlow {
+                nonce,
+                code_verifier,
+                created_at: Instant::now(),
+            },
+        );
+
+        Ok((url.to_string(), state))
+    }
+
+    /// Exchanges the authorization code of the callback for the identity of the user
+    pub async fn exchange(&self, code: &str, state: &str) -> Result<OidcIdentity> {
+        let flow = self
+            .inner
+            .flows
+            .lock()
+            .unwrap()
+            .remove(state)
+            .ok_or(Error::Oidc("Unknown or expired login state".to_string()))?;
+
+        let metadata = self.metadata().await?;
+        let config = &self.inner.config;
+
+        let mut request = self.inner.client.post(&metadata.token_endpoint).form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &config.redirect_url),
+            ("client_id", &config.client_id),
+            ("code_verifier", &flow.code_verifier),
+        ]);
+        if let Some(client_secret) = &config.client_secret {
+            request = request.basic_auth(&config.client_id, Some(client_secret));
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(Error::Oidc(format!(
+                "Token endpoint returned {}: {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            )));
+        }
+
+        let tokens = response.json::<TokenResponse>().await?;
+        let claims = decode_id_token_claims(&tokens.id_token)?;
+
+        self.validate_claims(&claims, metadata, &flow.nonce)?;
+
+        Ok(self.identity(claims))
+    }
+
+    /// The ID token comes straight from the token endpoint over TLS, which per OpenID Connect Core
+    /// 3.1.3.7 can replace the validation of its signature, the claims still have to be checked
+    fn validate_claims(
+        &self,
+        claims: &IdTokenClaims,
+        metadata: &ProviderMetadata,
+        nonce: &str,
+    ) -> Result<()> {
+        if claims.iss.trim_end_matches('/') != metadata.issuer.trim_end_matches('/') {
+            return Err(Error::Oidc(format!("Unexpected issuer {}", claims.iss)));
+        }
+
+        if !claims.aud.contains(&self.inner.config.client_id) {
+            return Err(Error::Oidc(
+                "ID token wasn't issued for this client".to_string(),
+            ));
+        }
+
+        if claims.exp <= Utc::now().timestamp() {
+            return Err(Error::Oidc("ID token expired".to_string()));
+        }
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(Error::Oidc("ID token nonce mismatch".to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn identity(&self, claims: IdTokenClaims) -> OidcIdentity {
+        let groups_claim = self
+            .inner
+            .config
+            .groups_claim
+            .as_deref()
+            .unwrap_or("groups");
+
+        let groups = match claims.other.get(groups_claim) {
+            Some(serde_json::Value::Array(groups)) => groups
+                .iter()
+                .filter_map(|group| group.as_str().map(str::to_string))
+                .collect(),
+            Some(serde_json::Value::String(group)) => vec![group.clone()],
+            _ => vec![],
+        };
+
+        // An email the issuer didn't verify can't be trusted to grant access based on its domain
+        let email = claims.email.filter(|_| claims.email_verified == Some(true));
+
+        OidcIdentity {
+            username: email
+                .clone()
+                .or(claims.preferred_username)
+                .unwrap_or_else(|| claims.sub.clone()),
+            subject: claims.sub,
+            email,
+            groups,
+        }
+    }
+
+    pub fn role(&self, identity: &OidcIdentity) -> Result<Role> {
+        resolve_role(&self.inner.config, identity)
+    }
+}
+
+/// Role of the identity, from its groups or the default role, only once its email domain is allowed
+fn resolve_role(config: &config::Oidc, identity: &OidcIdentity) -> Result<Role> {
+    if !config.allowed_email_domains.is_empty() {
+        let domain = identity
+            .email
+            .as_deref()
+            .and_then(|email| email.rsplit_once('@'))
+            .map(|(_, domain)| domain.to_lowercase());
+
+        let allowed = domain.is_some_and(|domain| {
+            config
+                .allowed_email_domains
+                .iter()
+                .any(|allowed| allowed.to_lowercase() == domain)
+        });
+
+        if !allowed {
+            return Err(Error::Forbidden(format!(
+                "Email domain of {} is not allowed",
+                identity.username
+            )));
+        }
+    }
+
+    identity
+        .groups
+        .iter()
+        .filter_map(|group| config.group_roles.get(group).copied())
+        .max()
+        .or(config.default_role)
+        .ok_or(Error::Forbidden(format!(
+            "No role mapped for {}",
+            identity.username
+        )))
+}
+
+fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+fn decode_id_token_claims(id_token: &str) -> Result<IdTokenClaims> {
+    let payload = id_token
+        .split('.')
+        .nth(1)
+        .ok_or(Error::Oidc("Malformed ID token".to_string()))?;
+
+    let payload = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .map_err(|err| Error::Oidc(format!("Malformed ID token payload: {err}")))?;
+
+    Ok(serde_json::from_slice(&payload)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Form, Json, Router, extract::State, routing::get, routing::post};
+    use std::collections::BTreeMap;
+    use tokio::net::TcpListener;
+
+    #[derive(Clone, Default)]
+    struct MockIssuer {
+        issuer: Arc<Mutex<String>>,
+        nonce: Arc<Mutex<String>>,
+        code_challenge: Arc<Mutex<String>>,
+    }
+
+    async fn discovery(State(mock): State<MockIssuer>) -> Json<serde_json::Value> {
+        let issuer = mock.issuer.lock().unwrap().clone();
+        Json(serde_json::json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{issuer}/authorize"),
+            "token_endpoint": format!("{issuer}/token"),
+        }))
+    }
+
+    async fn token(
+        State(mock): State<MockIssuer>,
+        Form(form): Form<HashMap<String, String>>,
+    ) -> std::result::Result<Json<serde_json::Value>, axum::http::StatusCode> {
+        if form.get("code").map(String::as_str) != Some("valid-code")
+            || code_challenge(&form["code_verifier"]) != *mock.code_challenge.lock().unwrap()
+        {
+            return Err(axum::http::StatusCode::BAD_REQUEST);
+        }
+
+        let claims = serde_json::json!({
+            "iss": mock.issuer.lock().unwrap().clone(),
+            "sub": "user-1",
+            "aud": ["bluflare"],
+            "exp": Utc::now().timestamp() + 60,
+            "nonce": mock.nonce.lock().unwrap().clone(),
+            "email": "jane@example.com",
+            "email_verified": true,
+            "groups": ["bluflare-editors"],
+        });
+        let id_token = format!(
+            "{}.{}.signature",
+            URL_SAFE_NO_PAD.encode(r#"{"alg":"RS256"}"#),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+
+        Ok(Json(serde_json::json!({ "id_token": id_token })))
+    }
+
+    fn oidc_config(issuer_url: String) -> config::Oidc {
+        config::Oidc {
+            enabled: true,
+            issuer_url,
+            client_id: "bluflare".to_string(),
+            client_secret: Some("secret".to_string()),
+            redirect_url: "http://localhost/api/v1/auth/oidc/callback".to_string(),
+            post_login_redirect_url: None,
+            allowed_email_domains: vec!["example.com".to_string()],
+            groups_claim: None,
+            group_roles: BTreeMap::from([("bluflare-editors".to_string(), Role::Editor)]),
+            default_role: Some(Role::Viewer),
+        }
+    }
+
+    fn identity(email: &str, groups: &[&str]) -> OidcIdentity {
+        OidcIdentity {
+            subject: "user-1".to_string(),
+            username: email.to_string(),
+            email: Some(email.to_string()),
+            groups: groups.iter().map(|group| group.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_given_mapped_groups_when_resolve_role_return_highest_role() {
+        let mut config = oidc_config(String::new());
+        config
+            .group_roles
+            .insert("bluflare-admins".to_string(), Role::Admin);
+
+        let role = resolve_role(
+            &config,
+            &identity("jane@example.com", &["bluflare-editors", "bluflare-admins"]),
+        );
+
+        assert_eq!(role.unwrap(), Role::Admin);
+    }
+
+    #[test]
+    fn test_given_unmapped_groups_when_resolve_role_return_default_role() {
+        let role = resolve_role(
+            &oidc_config(String::new()),
+            &identity("jane@EXAMPLE.com", &["other"]),
+        );
+
+        assert_eq!(role.unwrap(), Role::Viewer);
+    }
+
+    #[test]
+    fn test_given_disallowed_domain_when_resolve_role_return_error() {
+        let role = resolve_role(
+            &oidc_config(String::new()),
+            &identity("jane@evil.com", &["bluflare-editors"]),
+        );
+
+        assert!(role.is_err());
+    }
+
+    #[test]
+    fn test_given_too_many_pending_flows_when_insert_drop_oldest() {
+        let mut flows = PendingFlows::default();
+        for i in 0..=MAX_PENDING_FLOWS {
+            flows.insert(
+                i.to_string(),
+                PendingFlow {
+                    nonce: String::new(),
+                    code_verifier: String::new(),
+                    created_at: Instant::now(),
+                },
+            );
+        }
+
+        assert_eq!(flows.by_state.len(), MAX_PENDING_FLOWS);
+        assert!(flows.remove("0").is_none());
+        assert!(flows.remove(&MAX_PENDING_FLOWS.to_string()).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_given_mock_issuer_when_exchange_return_identity() {
+        let mock = MockIssuer::default();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        *mock.issuer.lock().unwrap() = issuer.clone();
+
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/token", post(token))
+            .with_state(mock.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = OidcClient::new(&config::Auth {
+            enabled: true,
+            username: None,
+            password_hash: None,
+            cookie_expiry_minutes: None,
+            cookie_domain: None,
+            cookie_secure: None,
+            session_cleanup_interval_minutes: None,
+            oidc: Some(oidc_config(issuer.clone())),
+            login_throttle: config::LoginThrottle::default(),
+        })
+        .unwrap()
+        .unwrap();
+
+        let (url, state) = client.authorization_url().await.unwrap();
+        let url = Url::parse(&url).unwrap();
+        let params = url.query_pairs().into_owned().collect::<HashMap<_, _>>();
+        assert!(url.as_str().starts_with(&format!("{issuer}/authorize")));
+        assert_eq!(params["state"], state);
+        assert_eq!(params["code_challenge_method"], "S256");
+
+        *mock.nonce.lock().unwrap() = params["nonce"].clone();
+        *mock.code_challenge.lock().unwrap() = params["code_challenge"].clone();
+
+        assert!(
+            client
+                .exchange("valid-code", "unknown-state")
+                .await
+                .is_err()
+        );
+
+        let identity = client.exchange("valid-code", &state).await.unwrap();
+        assert_eq!(identity.subject, "user-1");
+        assert_eq!(identity.username, "jane@example.com");
+        assert_eq!(client.role(&identity).unwrap(), Role::Editor);
+
+        // The state can only be used once
+        assert!(client.exchange("valid-code", &state).await.is_err());
+    }
+}
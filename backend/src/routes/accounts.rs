@@ -0,0 +1,172 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde_json::Value;
+
+use crate::{
+    Error, Result,
+    audit::{self, Actor},
+    auth, db,
+    models::{
+        account::{Account, CreateAccount, Role, UpdateAccount},
+        audit::AuditAction,
+    },
+    state::AppState,
+};
+
+pub async fn get_accounts(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    db::get_accounts(&state.pool).await.map(Json)
+}
+
+pub async fn create_account(
+    State(state): State<AppState>,
+    actor: Actor,
+    Json(account): Json<CreateAccount>,
+) -> Result<impl IntoResponse> {
+    let username = account.username.trim();
+    if username.is_empty() {
+        return Err(Error::BadRequest("Username can't be empty".to_string()));
+    }
+
+    if db::account_username_exists(&state.pool, username).await? {
+        return Err(Error::BadRequest(format!(
+            "Account with username {username} already exists"
+        )));
+    }
+
+    let password_hash = auth::hash_password(&account.password)?;
+
+    let account = db::create_account(&state.pool, username, &password_hash, account.role).await?;
+
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::AccountCreate,
+        Some(account.id),
+        None,
+        audit::snapshot(&account),
+    )
+    .await;
+
+    Ok(Json(account))
+}
+
+pub async fn get_account(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    db::get_account(&state.pool, id)
+        .await?
+        .ok_or(Error::NotFound(format!("Account with id {id} not found")))
+        .map(Json)
+}
+
+pub async fn update_account(
+    State(state): State<AppState>,
+    actor: Actor,
+    Path(id): Path<i64>,
+    Json(account): Json<UpdateAccount>,
+) -> Result<impl IntoResponse> {
+    let removes_admin =
+        account.role.is_some_and(|role| role != Role::Admin) || account.enabled == Some(false);
+    if removes_admin {
+        ensure_other_admin(&state, id).await?;
+    }
+
+    let password_hash = account
+        .password
+        .as_deref()
+        .map(auth::hash_password)
+        .transpose()?;
+    let password_changed = password_hash.is_some();
+    let revoke_sessions = password_changed || account.enabled == Some(false);
+    let before = db::get_account(&state.pool, id).await?;
+
+    if db::update_account(
+        &state.pool,
+        id,
+        password_hash,
+        account.role,
+        account.enabled,
+    )
+    .await?
+    {
+        if revoke_sessions {
+            db::delete_account_sessions(&state.pool, id, None).await?;
+        }
+
+        // The password itself is never recorded, only that it changed
+        let mut after = db::get_account(&state.pool, id)
+            .await?
+            .as_ref()
+            .and_then(audit::snapshot);
+        if let Some(Value::Object(after)) = after.as_mut().filter(|_| password_changed) {
+            after.insert("password_changed".to_string(), true.into());
+        }
+
+        audit::record(
+            &state.pool,
+            &actor,
+            AuditAction::AccountUpdate,
+            Some(id),
+            before.as_ref().and_then(audit::snapshot),
+            after,
+        )
+        .await;
+
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound(format!("Account with id {id} not found")))
+    }
+}
+
+pub async fn delete_account(
+    State(state): State<AppState>,
+    current_account: Option<Extension<Account>>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    if current_account.is_some_and(|Extension(account)| account.id == id) {
+        return Err(Error::BadRequest(
+            "An account can't delete itself".to_string(),
+        ));
+    }
+
+    ensure_other_admin(&state, id).await?;
+
+    let before = db::get_account(&state.pool, id).await?;
+
+    if db::delete_account(&state.pool, id).await? {
+        audit::record(
+            &state.pool,
+            &actor,
+            AuditAction::AccountDelete,
+            Some(id),
+            before.as_ref().and_then(audit::snapshot),
+            None,
+        )
+        .await;
+
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound(format!("Account with id {id} not found")))
+    }
+}
+
+/// Prevents demoting, disabling or deleting the last enabled admin
+async fn ensure_other_admin(state: &AppState, id: i64) -> Result<()> {
+    let is_admin = db::get_account(&state.pool, id)
+        .await?
+        .is_some_and(|account| account.role == Role::Admin && account.enabled);
+
+    if is_admin && db::get_other_enabled_admins_count(&state.pool, id).await? == 0 {
+        return Err(Error::BadRequest(
+            "At least one enabled admin account is required".to_string(),
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+use crate::{
+    Error, Result,
+    access::{TopicAccess, TopicPermissions},
+    audit::{self, Actor},
+    db,
+    models::{
+        alert::{AlertRule, CreateAlertRule, UpdateAlertRule},
+        audit::AuditAction,
+    },
+    state::AppState,
+};
+
+const ALERT_EVENTS_LIMIT: i64 = 100;
+
+pub async fn get_topic_alert_rules(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(topic_id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    permissions
+        .require_topic(&state, topic_id, TopicAccess::View)
+        .await?;
+
+    db::get_topic_alert_rules(&state.pool, topic_id)
+        .await
+        .map(Json)
+}
+
+pub async fn create_alert_rule(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    actor: Actor,
+    Path(topic_id): Path<i64>,
+    Json(rule): Json<CreateAlertRule>,
+) -> Result<impl IntoResponse> {
+    permissions
+        .require_topic(&state, topic_id, TopicAccess::Edit)
+        .await?;
+
+    state
+        .alerts
+        .validate(Some(&rule.condition), Some(&rule.channels))
+        .await?;
+
+    let rule = db::create_alert_rule(&state.pool, topic_id, rule).await?;
+
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::AlertRuleCreate,
+        Some(rule.id),
+        None,
+        audit::snapshot(&rule),
+    )
+    .await;
+
+    Ok(Json(rule))
+}
+
+pub async fn get_alert_rule(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    require_alert_rule(&state, &permissions, id, TopicAccess::View)
+        .await
+        .map(Json)
+}
+
+pub async fn update_alert_rule(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    actor: Actor,
+    Path(id): Path<i64>,
+    Json(rule): Json<UpdateAlertRule>,
+) -> Result<impl IntoResponse> {
+    let before = require_alert_rule(&state, &permissions, id, TopicAccess::Edit).await?;
+
+    state
+        .alerts
+        .validate(rule.condition.as_ref(), rule.channels.as_deref())
+        .await?;
+
+    if db::update_alert_rule(&state.pool, id, rule).await? {
+        let after = db::get_alert_rule(&state.pool, id).await?;
+
+        audit::record(
+            &state.pool,
+            &actor,
+            AuditAction::AlertRuleUpdate,
+            Some(id),
+            audit::snapshot(&before),
+            after.as_ref().and_then(audit::snapshot),
+        )
+        .await;
+
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound(format!(
+            "Alert rule with id {id} not found"
+        )))
+    }
+}
+
+pub async fn delete_alert_rule(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    let before = require_alert_rule(&state, &permissions, id, TopicAccess::Edit).await?;
+
+    if db::delete_alert_rule(&state.pool, id).await? {
+        audit::record(
+            &state.pool,
+            &actor,
+            AuditAction::AlertRuleDelete,
+            Some(id),
+            audit::snapshot(&before),
+            None,
+        )
+        .await;
+
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound(format!(
+            "Alert rule with id {id} not found"
+        )))
+    }
+}
+
+pub async fn get_alert_events(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    require_alert_rule(&state, &permissions, id, TopicAccess::View).await?;
+
+    db::get_alert_events(&state.pool, id, ALERT_EVENTS_LIMIT)
+        .await
+        .map(Json)
+}
+
+/// Alert rule whose topic the caller has at least the `required` access to
+async fn require_alert_rule(
+    state: &AppState,
+    permissions: &TopicPermissions,
+    id: i64,
+    required: TopicAccess,
+) -> Result<AlertRule> {
+    let not_found = || Error::NotFound(format!("Alert rule with id {id} not found"));
+
+    let rule = db::get_alert_rule(&state.pool, id)
+        .await?
+        .ok_or_else(not_found)?;
+
+    permissions
+        .require_topic(state, rule.topic_id, required)
+        .await
+        .map_err(|e| match e {
+            Error::NotFound(_) => not_found(),
+            e => e,
+        })?;
+
+    Ok(rule)
+}
@@ -0,0 +1,146 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    response::IntoResponse,
+};
+
+use crate::{
+    Error, Result,
+    access::{TopicAccess, TopicPermissions},
+    db,
+    models::{
+        analysis::{
+            AnalysesQuery, Analysis, AnalysisComparison, AnalysisPage, CompareAnalysesQuery,
+            SentimentQuery,
+        },
+        usage::{LlmPurpose, UsageContext},
+    },
+    state::AppState,
+};
+
+pub async fn get_analyses(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(id): Path<i64>,
+    Query(query): Query<AnalysesQuery>,
+) -> Result<impl IntoResponse> {
+    permissions
+        .require_topic(&state, id, TopicAccess::View)
+        .await?;
+
+    let (page, per_page) = query.pagination();
+    let total = db::count_topic_analyses(&state.pool, id).await?;
+    let analyses = db::get_topic_analyses(&state.pool, id, page, per_page).await?;
+
+    Ok(Json(AnalysisPage {
+        analyses,
+        page,
+        per_page,
+        total,
+    }))
+}
+
+pub async fn get_analysis(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path((id, analysis_id)): Path<(i64, i64)>,
+) -> Result<impl IntoResponse> {
+    permissions
+        .require_topic(&state, id, TopicAccess::View)
+        .await?;
+
+    topic_analysis(&state, id, analysis_id).await.map(Json)
+}
+
+/// Sentiment of the structured analyses of a topic over time
+pub async fn get_sentiment(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(id): Path<i64>,
+    Query(query): Query<SentimentQuery>,
+) -> Result<impl IntoResponse> {
+    permissions
+        .require_topic(&state, id, TopicAccess::View)
+        .await?;
+
+    let points = db::get_topic_sentiment(
+        &state.pool,
+        id,
+        query.since.map(|since| since.naive_utc()),
+        query.until.map(|until| until.naive_utc()),
+    )
+    .await?;
+
+    Ok(Json(points))
+}
+
+pub async fn compare_analyses(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(id): Path<i64>,
+    Query(query): Query<CompareAnalysesQuery>,
+) -> Result<impl IntoResponse> {
+    compare(&state, &permissions, id, query, false)
+        .await
+        .map(Json)
+}
+
+/// Same as [`compare_analyses`], also describing how the discussion evolved with Gemini
+pub async fn summarize_analyses_comparison(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(id): Path<i64>,
+    Query(query): Query<CompareAnalysesQuery>,
+) -> Result<impl IntoResponse> {
+    compare(&state, &permissions, id, query, true)
+        .await
+        .map(Json)
+}
+
+async fn compare(
+    state: &AppState,
+    permissions: &TopicPermissions,
+    id: i64,
+    query: CompareAnalysesQuery,
+    summarize: bool,
+) -> Result<AnalysisComparison> {
+    let required = if summarize {
+        TopicAccess::Edit
+    } else {
+        TopicAccess::View
+    };
+    let topic = permissions.require_topic(state, id, required).await?;
+
+    let from = topic_analysis(state, id, query.from).await?;
+    let to = topic_analysis(state, id, query.to).await?;
+
+    let evolution = if summarize {
+        let Some(generation) = state
+            .gemini
+            .compare_analyses(
+                UsageContext {
+                    purpose: LlmPurpose::AnalysisComparison,
+                    account_id: permissions.account_id(),
+                    topic_id: Some(id),
+                },
+                &topic.subject,
+                &from,
+                &to,
+            )
+            .await?
+        else {
+            return Err(Error::GeminiDisabled);
+        };
+        Some(generation.text)
+    } else {
+        None
+    };
+
+    Ok(AnalysisComparison::new(from, to, evolution))
+}
+
+async fn topic_analysis(state: &AppState, topic_id: i64, id: i64) -> Result<Analysis> {
+    db::get_topic_analysis(&state.pool, topic_id, id)
+        .await?
+        .ok_or(Error::NotFound(format!("Analysis with id {id} not found")))
+}
@@ -0,0 +1,79 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+use crate::{
+    Error, Result,
+    audit::{self, Actor},
+    auth, db,
+    models::{account::Account, api_token::CreateApiToken, audit::AuditAction},
+    state::AppState,
+};
+
+pub async fn get_api_tokens(
+    State(state): State<AppState>,
+    account: Option<Extension<Account>>,
+) -> Result<impl IntoResponse> {
+    let Some(Extension(account)) = account else {
+        return Err(Error::AuthDisabled);
+    };
+
+    db::get_account_api_tokens(&state.pool, account.id)
+        .await
+        .map(Json)
+}
+
+pub async fn create_api_token(
+    State(state): State<AppState>,
+    account: Option<Extension<Account>>,
+    actor: Actor,
+    Json(api_token): Json<CreateApiToken>,
+) -> Result<impl IntoResponse> {
+    let Some(Extension(account)) = account else {
+        return Err(Error::AuthDisabled);
+    };
+
+    let created = auth::create_api_token(&state.pool, account.id, api_token).await?;
+
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::ApiTokenCreate,
+        Some(created.api_token.id),
+        None,
+        audit::snapshot(&created.api_token),
+    )
+    .await;
+
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+pub async fn revoke_api_token(
+    State(state): State<AppState>,
+    account: Option<Extension<Account>>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    let Some(Extension(account)) = account else {
+        return Err(Error::AuthDisabled);
+    };
+
+    if db::revoke_api_token(&state.pool, account.id, id).await? {
+        audit::record(
+            &state.pool,
+            &actor,
+            AuditAction::ApiTokenRevoke,
+            Some(id),
+            None,
+            None,
+        )
+        .await;
+
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound(format!("API token with id {id} not found")))
+    }
+}
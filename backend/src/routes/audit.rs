@@ -0,0 +1,27 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    response::IntoResponse,
+};
+
+use crate::{
+    Result, db,
+    models::audit::{AuditLogPage, AuditLogQuery},
+    state::AppState,
+};
+
+pub async fn get_audit_log(
+    State(state): State<AppState>,
+    Query(query): Query<AuditLogQuery>,
+) -> Result<impl IntoResponse> {
+    let (page, per_page) = query.pagination();
+    let total = db::count_audit_entries(&state.pool, &query).await?;
+    let entries = db::get_audit_entries(&state.pool, &query).await?;
+
+    Ok(Json(AuditLogPage {
+        entries,
+        page,
+        per_page,
+        total,
+    }))
+}
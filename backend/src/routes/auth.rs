@@ -1,16 +1,26 @@
-use argon2::{PasswordHash, PasswordVerifier, password_hash::Encoding};
-use axum::{Json, extract::State, response::IntoResponse};
-use axum_extra::extract::{
-    CookieJar,
-    cookie::{Cookie, SameSite},
+use axum::{
+    Extension, Json,
+    extract::{ConnectInfo, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
 };
-use rand_core::{OsRng, RngCore};
+use axum_extra::{TypedHeader, extract::CookieJar, headers::UserAgent};
+use chrono::Utc;
 use serde::Deserialize;
-use time::{Duration, OffsetDateTime};
+use serde_json::json;
+use std::{net::SocketAddr, time::Instant};
+use tracing::warn;
 
 use crate::{
     Error, Result,
-    state::{AppState, Session},
+    audit::{self, Actor},
+    auth, db,
+    models::{
+        account::{Account, UpdatePassword},
+        audit::AuditAction,
+        session::{Session, SessionWithCurrent},
+    },
+    state::AppState,
 };
 
 #[derive(Debug, Deserialize)]
@@ -21,6 +31,9 @@ pub struct LoginRequest {
 
 pub async fn login(
     State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    user_agent: Option<TypedHeader<UserAgent>>,
+    actor: Actor,
     jar: CookieJar,
     Json(credentials): Json<LoginRequest>,
 ) -> Result<impl IntoResponse> {
@@ -28,78 +41,224 @@ pub async fn login(
         return Err(Error::AuthDisabled);
     }
 
-    let admin_username = state
-        .config
-        .server
-        .auth
-        .username
-        .clone()
-        .ok_or(Error::AuthDisabled)?;
-    let admin_password_hash = state
-        .config
-        .server
-        .auth
-        .password_hash
-        .clone()
-        .ok_or(Error::AuthDisabled)?;
-    let cookie_expiry_minutes = state.config.server.auth.cookie_expiry_minutes.unwrap_or(30);
-
-    if credentials.username != admin_username {
-        return Err(Error::InvalidCredentials);
-    }
+    let ip = addr.ip();
+    let attempt = match state
+        .login_throttle
+        .begin(ip, &credentials.username, Instant::now())
+    {
+        Ok(attempt) => attempt,
+        Err(retry_after) => {
+            warn!(
+                "Throttled login attempt for {} from {ip}, retry in {}s",
+                credentials.username,
+                retry_after.as_secs()
+            );
+            return Err(Error::TooManyRequests(retry_after));
+        }
+    };
 
-    let Ok(()) = argon2::Argon2::default().verify_password(
-        credentials.password.as_bytes(),
-        &PasswordHash::parse(&admin_password_hash, Encoding::default()).unwrap(),
-    ) else {
+    let account = db::get_account_credentials(&state.pool, &credentials.username).await?;
+    let actor = actor.with_account(
+        account.as_ref().map(|account| account.id),
+        &credentials.username,
+    );
+    let verified = auth::verify_login(
+        &credentials.password,
+        account
+            .as_ref()
+            .filter(|account| account.enabled)
+            .map(|account| account.password_hash.as_str()),
+    );
+    let account = account.filter(|_| verified);
+
+    let Some(account) = account else {
+        let failures = attempt.failed(Instant::now());
+        warn!(
+            "Failed login for {} from {ip}, {failures} consecutive failures",
+            credentials.username
+        );
+        audit::record(
+            &state.pool,
+            &actor,
+            AuditAction::LoginFailed,
+            actor.account_id,
+            None,
+            None,
+        )
+        .await;
         return Err(Error::InvalidCredentials);
     };
 
-    let mut bytes = [0u8; 32];
-    OsRng.fill_bytes(&mut bytes);
-    let session_id = hex::encode(bytes);
-
-    let cookie = build_cookie(state.clone(), session_id.clone());
+    attempt.succeeded();
 
-    *state.session_id.write().await = Some(Session {
-        id: session_id,
-        expires_at: OffsetDateTime::now_utc() + Duration::minutes(cookie_expiry_minutes as i64),
-    });
+    db::update_account_last_login(&state.pool, account.id).await?;
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::Login,
+        Some(account.id),
+        None,
+        None,
+    )
+    .await;
 
-    Ok(jar.add(cookie))
+    auth::create_session(
+        &state,
+        jar,
+        account.id,
+        user_agent.map(|TypedHeader(user_agent)| user_agent.to_string()),
+        Some(addr.ip().to_string()),
+    )
+    .await
 }
 
 pub async fn auth_permission(
     State(state): State<AppState>,
     jar: CookieJar,
 ) -> Result<impl IntoResponse> {
-    Ok(Json(crate::auth::auth_permission(&state, &jar).await))
+    auth::auth_permission(&state, &jar).await.map(Json)
+}
+
+pub async fn get_current_account(account: Option<Extension<Account>>) -> Result<impl IntoResponse> {
+    account
+        .map(|Extension(account)| Json(account))
+        .ok_or(Error::AuthDisabled)
 }
 
-pub async fn logout(State(state): State<AppState>, jar: CookieJar) -> Result<impl IntoResponse> {
-    *state.session_id.write().await = None;
+pub async fn update_password(
+    State(state): State<AppState>,
+    account: Option<Extension<Account>>,
+    session: Option<Extension<Session>>,
+    actor: Actor,
+    Json(passwords): Json<UpdatePassword>,
+) -> Result<impl IntoResponse> {
+    let Some(Extension(account)) = account else {
+        return Err(Error::AuthDisabled);
+    };
+
+    let password_hash = db::get_account_password_hash(&state.pool, account.id)
+        .await?
+        .ok_or(Error::NotFound(format!(
+            "Account with id {} not found",
+            account.id
+        )))?;
 
-    Ok(jar.remove(build_cookie(state, "".to_string())))
+    if !auth::verify_password(&passwords.current_password, &password_hash) {
+        return Err(Error::InvalidCredentials);
+    }
+
+    let password_hash = auth::hash_password(&passwords.new_password)?;
+    db::update_account(&state.pool, account.id, Some(password_hash), None, None).await?;
+    db::delete_account_sessions(
+        &state.pool,
+        account.id,
+        session.map(|Extension(session)| session.id),
+    )
+    .await?;
+
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::PasswordUpdate,
+        Some(account.id),
+        None,
+        None,
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
 }
 
-fn build_cookie<'a>(state: AppState, session_id: String) -> Cookie<'a> {
-    let cookie_expiry_minutes = state.config.server.auth.cookie_expiry_minutes.unwrap_or(30);
-    let cookie_domain = state
-        .config
-        .server
-        .auth
-        .cookie_domain
-        .unwrap_or("localhost".to_string());
-    let cookie_secure = state.config.server.auth.cookie_secure.unwrap_or(false);
-    let expires_at = OffsetDateTime::now_utc() + Duration::minutes(cookie_expiry_minutes as i64);
-
-    Cookie::build(("session_id", session_id))
-        .path("/")
-        .http_only(true)
-        .secure(cookie_secure)
-        .domain(cookie_domain)
-        .same_site(SameSite::Strict)
-        .max_age(Duration::minutes(cookie_expiry_minutes as i64))
-        .expires(expires_at)
-        .build()
+pub async fn get_sessions(
+    State(state): State<AppState>,
+    account: Option<Extension<Account>>,
+    session: Option<Extension<Session>>,
+) -> Result<impl IntoResponse> {
+    let (Some(Extension(account)), Some(Extension(current))) = (account, session) else {
+        return Err(Error::AuthDisabled);
+    };
+
+    let sessions = db::get_account_sessions(&state.pool, account.id, Utc::now().naive_utc())
+        .await?
+        .into_iter()
+        .map(|session| SessionWithCurrent {
+            current: session.id == current.id,
+            session,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Json(sessions))
+}
+
+pub async fn delete_session(
+    State(state): State<AppState>,
+    account: Option<Extension<Account>>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    let Some(Extension(account)) = account else {
+        return Err(Error::AuthDisabled);
+    };
+
+    if db::delete_account_session(&state.pool, account.id, id).await? {
+        audit::record(
+            &state.pool,
+            &actor,
+            AuditAction::SessionRevoke,
+            Some(id),
+            None,
+            None,
+        )
+        .await;
+
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound(format!("Session with id {id} not found")))
+    }
+}
+
+/// Revokes every session of the current account except the one making the request
+pub async fn delete_other_sessions(
+    State(state): State<AppState>,
+    account: Option<Extension<Account>>,
+    session: Option<Extension<Session>>,
+    actor: Actor,
+) -> Result<impl IntoResponse> {
+    let (Some(Extension(account)), Some(Extension(session))) = (account, session) else {
+        return Err(Error::AuthDisabled);
+    };
+
+    db::delete_account_sessions(&state.pool, account.id, Some(session.id)).await?;
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::SessionRevoke,
+        None,
+        None,
+        Some(json!({ "kept_session_id": session.id })),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn logout(
+    State(state): State<AppState>,
+    session: Option<Extension<Session>>,
+    actor: Actor,
+    jar: CookieJar,
+) -> Result<impl IntoResponse> {
+    let jar = auth::delete_session(&state, jar).await?;
+
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::Logout,
+        session.map(|Extension(session)| session.id),
+        None,
+        None,
+    )
+    .await;
+
+    Ok(jar)
 }
@@ -0,0 +1,70 @@
+use axum::{
+    extract::{Path, State},
+    http::header,
+    response::IntoResponse,
+};
+
+use crate::{
+    Error, Result,
+    access::{TopicAccess, TopicPermissions},
+    db,
+    feed::{FeedFormat, TopicFeed},
+    state::AppState,
+};
+
+const FEED_POSTS_LIMIT: i64 = 50;
+
+pub async fn get_atom_feed(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(slug): Path<String>,
+) -> Result<impl IntoResponse> {
+    render_topic_feed(state, permissions, slug, FeedFormat::Atom).await
+}
+
+pub async fn get_rss_feed(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(slug): Path<String>,
+) -> Result<impl IntoResponse> {
+    render_topic_feed(state, permissions, slug, FeedFormat::Rss).await
+}
+
+pub async fn get_json_feed(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(slug): Path<String>,
+) -> Result<impl IntoResponse> {
+    render_topic_feed(state, permissions, slug, FeedFormat::Json).await
+}
+
+async fn render_topic_feed(
+    state: AppState,
+    permissions: TopicPermissions,
+    slug: String,
+    format: FeedFormat,
+) -> Result<impl IntoResponse> {
+    let not_found = || Error::NotFound(format!("Topic with slug {slug} not found"));
+
+    let topic_id = db::get_topic_id_by_slug(&state.pool, &slug)
+        .await?
+        .ok_or_else(not_found)?;
+
+    let topic = permissions
+        .require_topic(&state, topic_id, TopicAccess::View)
+        .await
+        .map_err(|e| match e {
+            Error::NotFound(_) => not_found(),
+            e => e,
+        })?;
+    let posts = db::get_latest_topic_posts(&state.pool, topic_id, FEED_POSTS_LIMIT).await?;
+
+    let body = TopicFeed {
+        topic: &topic,
+        posts: &posts,
+        home_origin: &state.config.server.cors.allowed_origin,
+    }
+    .render(format)?;
+
+    Ok(([(header::CONTENT_TYPE, format.content_type())], body))
+}
@@ -0,0 +1,9 @@
+use axum::{extract::State, http::header, response::IntoResponse};
+use prometheus::TEXT_FORMAT;
+use sqlx::SqlitePool;
+
+use crate::{Result, metrics::METRICS};
+
+pub async fn get_metrics(State(pool): State<SqlitePool>) -> Result<impl IntoResponse> {
+    Ok(([(header::CONTENT_TYPE, TEXT_FORMAT)], METRICS.render(&pool)))
+}
@@ -1,49 +1,216 @@
 use axum::{
     Router,
     middleware::from_fn_with_state,
-    routing::{delete, get, post},
+    routing::{delete, get, patch, post, put},
 };
 use tower_http::{
     services::{ServeDir, ServeFile},
     set_status::SetStatus,
 };
 
-use crate::{layers::CommonTowerLayerBuilder, state::AppState};
+use crate::{
+    auth::Requirement,
+    layers::CommonTowerLayerBuilder,
+    models::{account::Role, api_token::Scope},
+    state::AppState,
+};
 
+mod accounts;
+mod alerts;
+mod analyses;
+mod api_tokens;
+mod audit;
 mod auth;
+mod feeds;
+mod metrics;
+mod oidc;
 mod posts;
+mod questions;
+mod schedules;
+mod share_links;
+mod stats;
 mod suggest;
 mod topics;
+mod usage;
 mod users;
 
 pub fn router(state: AppState) -> Router {
-    let router = Router::new()
-        .route("/keywords/suggest", post(suggest::suggest_keywords))
-        .route("/topics", post(topics::create_topic))
-        .route(
-            "/topics/{id}",
-            delete(topics::delete_topic).patch(topics::update_topic),
-        )
-        .route("/topics/{id}/analyze", post(topics::analyze_topic))
-        .route("/auth/logout", post(auth::logout))
-        .route_layer(from_fn_with_state(
-            state.clone(),
-            crate::auth::auth_middleware,
-        ))
+    let admin_router = require(
+        Router::new()
+            .route(
+                "/accounts",
+                get(accounts::get_accounts).post(accounts::create_account),
+            )
+            .route(
+                "/accounts/{id}",
+                get(accounts::get_account)
+                    .patch(accounts::update_account)
+                    .delete(accounts::delete_account),
+            )
+            .route("/audit", get(audit::get_audit_log))
+            .route("/usage", get(usage::get_usage)),
+        &state,
+        Role::Admin,
+        None,
+    );
+
+    let topics_write_router = require(
+        Router::new()
+            .route("/topics", post(topics::create_topic))
+            .route(
+                "/topics/{id}",
+                delete(topics::delete_topic).patch(topics::update_topic),
+            )
+            .route("/topics/{id}/shares", get(topics::get_topic_shares))
+            .route(
+                "/topics/{id}/shares/{account_id}",
+                put(topics::share_topic).delete(topics::unshare_topic),
+            )
+            .route(
+                "/topics/{id}/links",
+                get(share_links::get_share_links).post(share_links::create_share_link),
+            )
+            .route(
+                "/topics/{id}/links/{link_id}",
+                delete(share_links::revoke_share_link),
+            )
+            .route("/topics/{id}/alerts", post(alerts::create_alert_rule))
+            .route(
+                "/alerts/{id}",
+                patch(alerts::update_alert_rule).delete(alerts::delete_alert_rule),
+            ),
+        &state,
+        Role::Editor,
+        Some(Scope::TopicsWrite),
+    );
+
+    let analysis_router = require(
+        Router::new()
+            .route("/keywords/suggest", post(suggest::suggest_keywords))
+            .route("/topics/{id}/analyze", post(topics::analyze_topic))
+            .route("/topics/{id}/ask", post(questions::ask_question))
+            .route(
+                "/topics/{id}/analyses/compare",
+                post(analyses::summarize_analyses_comparison),
+            )
+            .route(
+                "/topics/{id}/schedule",
+                put(schedules::upsert_schedule).delete(schedules::delete_schedule),
+            )
+            .route("/users/{did}/analyze", post(users::analyze_user)),
+        &state,
+        Role::Editor,
+        Some(Scope::AnalysisRun),
+    );
+
+    let topics_read_router = require(
+        Router::new()
+            .route("/topics/{id}/alerts", get(alerts::get_topic_alert_rules))
+            .route("/alerts/{id}", get(alerts::get_alert_rule))
+            .route("/alerts/{id}/events", get(alerts::get_alert_events)),
+        &state,
+        Role::Viewer,
+        Some(Scope::TopicsRead),
+    );
+
+    let account_router = require(
+        Router::new()
+            .route("/auth/me", get(auth::get_current_account))
+            .route("/auth/password", post(auth::update_password))
+            .route(
+                "/auth/sessions",
+                get(auth::get_sessions).delete(auth::delete_other_sessions),
+            )
+            .route("/auth/sessions/{id}", delete(auth::delete_session))
+            .route(
+                "/auth/tokens",
+                get(api_tokens::get_api_tokens).post(api_tokens::create_api_token),
+            )
+            .route("/auth/tokens/{id}", delete(api_tokens::revoke_api_token))
+            .route("/auth/logout", post(auth::logout)),
+        &state,
+        Role::Viewer,
+        None,
+    );
+
+    // Public topics can be read anonymously, others depending on the account when there's one
+    let identified_router = Router::new()
         .route("/posts/latest", get(posts::get_latest_posts))
         .route("/posts/latest/sse", get(posts::get_posts_sse))
         .route("/topics", get(topics::get_topics))
         .route("/topics/{id}", get(topics::get_topic))
         .route("/topics/{id}/posts", get(topics::get_posts))
         .route("/topics/{id}/posts/sse", get(topics::sse_posts))
+        .route("/topics/{id}/timeseries", get(topics::get_timeseries))
+        .route(
+            "/topics/{id}/sentiment",
+            get(topics::get_sentiment_timeseries),
+        )
+        .route("/topics/{id}/analyses", get(analyses::get_analyses))
+        .route(
+            "/topics/{id}/analyses/compare",
+            get(analyses::compare_analyses),
+        )
+        .route(
+            "/topics/{id}/analyses/sentiment",
+            get(analyses::get_sentiment),
+        )
+        .route(
+            "/topics/{id}/analyses/{analysis_id}",
+            get(analyses::get_analysis),
+        )
+        .route("/topics/{id}/schedule", get(schedules::get_schedule))
+        .route("/schedules", get(schedules::get_schedules))
+        .route("/users/{did}", get(users::get_user))
+        .route("/users/{did}/posts", get(users::get_user_posts))
+        .route("/users/{did}/topics", get(users::get_user_topics))
+        .route("/users/{did}/mentioned_by", get(users::get_user_mentioners))
         .route("/topics/slugs/{slug}", get(topics::get_topic_by_slug))
+        .route("/topics/{slug}/feed.atom", get(feeds::get_atom_feed))
+        .route("/topics/{slug}/feed.rss", get(feeds::get_rss_feed))
+        .route("/topics/{slug}/feed.json", get(feeds::get_json_feed))
+        .route_layer(from_fn_with_state(
+            state.clone(),
+            crate::auth::identify_middleware,
+        ));
+
+    let router = Router::new()
+        .merge(admin_router)
+        .merge(topics_write_router)
+        .merge(analysis_router)
+        .merge(topics_read_router)
+        .merge(account_router)
+        .merge(identified_router)
+        .route("/shared/{token}", get(share_links::get_shared_topic))
+        .route("/shared/{token}/posts", get(share_links::get_shared_posts))
+        .route(
+            "/shared/{token}/posts/sse",
+            get(share_links::sse_shared_posts),
+        )
+        .route(
+            "/shared/{token}/timeseries",
+            get(share_links::get_shared_timeseries),
+        )
         .route("/users/latest", get(users::get_latest_users))
+        .route("/stats", get(stats::get_stats))
         .route("/auth/login", post(auth::login))
+        .route("/auth/oidc/login", get(oidc::login))
+        .route("/auth/oidc/callback", get(oidc::callback))
         .route("/auth/permission", get(auth::auth_permission))
         .with_state(state.clone());
 
     let versioned_router = Router::new().nest("/api/v1", router);
 
+    let versioned_router = if state.config.server.metrics.enabled {
+        versioned_router.merge(
+            Router::new()
+                .route("/metrics", get(metrics::get_metrics))
+                .with_state(state.clone()),
+        )
+    } else {
+        versioned_router
+    };
+
     let router_with_frontend = if state.config.server.frontend.enabled {
         versioned_router.fallback_service(frontend_router())
     } else {
@@ -51,6 +218,7 @@ pub fn router(state: AppState) -> Router {
     };
 
     CommonTowerLayerBuilder::new(state.config.server.cors.allowed_origin)
+        .metrics(state.config.server.metrics.enabled)
         .build()
         .apply_middlewares(router_with_frontend)
 }
@@ -58,3 +226,17 @@ pub fn router(state: AppState) -> Router {
 fn frontend_router() -> ServeDir<SetStatus<ServeFile>> {
     ServeDir::new("dist").not_found_service(ServeFile::new("dist/index.html"))
 }
+
+/// Only lets through sessions and API tokens whose account has at least `role`, API tokens also
+/// needing `scope`
+fn require(
+    router: Router<AppState>,
+    state: &AppState,
+    role: Role,
+    scope: Option<Scope>,
+) -> Router<AppState> {
+    router.route_layer(from_fn_with_state(
+        (state.clone(), Requirement { role, scope }),
+        crate::auth::auth_middleware,
+    ))
+}
@@ -0,0 +1,149 @@
+use axum::{
+    extract::{ConnectInfo, Query, State},
+    response::{IntoResponse, Redirect},
+};
+use axum_extra::{
+    TypedHeader,
+    extract::{
+        CookieJar,
+        cookie::{Cookie, SameSite},
+    },
+    headers::UserAgent,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::net::SocketAddr;
+use time::Duration;
+use tracing::warn;
+
+use crate::{
+    Error, Result,
+    audit::{self, Actor},
+    auth, db,
+    models::{account::Role, audit::AuditAction},
+    oidc::OidcIdentity,
+    state::AppState,
+};
+
+const STATE_COOKIE: &str = "oidc_state";
+
+#[derive(Debug, Deserialize)]
+pub struct CallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+pub async fn login(State(state): State<AppState>, jar: CookieJar) -> Result<impl IntoResponse> {
+    let oidc = state.oidc.as_ref().ok_or(Error::AuthDisabled)?;
+
+    let (url, flow_state) = oidc.authorization_url().await?;
+
+    // Lax so the cookie is sent back on the top level redirect from the issuer
+    let cookie = Cookie::build((STATE_COOKIE, flow_state))
+        .path("/api/v1/auth/oidc")
+        .http_only(true)
+        .secure(state.config.server.auth.cookie_secure.unwrap_or(false))
+        .same_site(SameSite::Lax)
+        .max_age(Duration::minutes(10))
+        .build();
+
+    Ok((jar.add(cookie), Redirect::to(&url)))
+}
+
+pub async fn callback(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    user_agent: Option<TypedHeader<UserAgent>>,
+    actor: Actor,
+    jar: CookieJar,
+    Query(query): Query<CallbackQuery>,
+) -> Result<impl IntoResponse> {
+    let oidc = state.oidc.as_ref().ok_or(Error::AuthDisabled)?;
+
+    if let Some(error) = query.error {
+        return Err(Error::Oidc(format!(
+            "Issuer returned {error}: {}",
+            query.error_description.unwrap_or_default()
+        )));
+    }
+
+    let (Some(code), Some(flow_state)) = (query.code, query.state) else {
+        return Err(Error::Oidc("Missing code or state".to_string()));
+    };
+
+    if jar.get(STATE_COOKIE).map(|cookie| cookie.value()) != Some(flow_state.as_str()) {
+        return Err(Error::Oidc(
+            "Login state doesn't match this browser".to_string(),
+        ));
+    }
+
+    let identity = oidc.exchange(&code, &flow_state).await?;
+    let account_id = get_or_create_account(&state, &identity).await?;
+
+    db::update_account_last_login(&state.pool, account_id).await?;
+    audit::record(
+        &state.pool,
+        &actor.with_account(Some(account_id), &identity.username),
+        AuditAction::Login,
+        Some(account_id),
+        None,
+        Some(json!({ "method": "oidc" })),
+    )
+    .await;
+
+    let jar = auth::create_session(
+        &state,
+        jar.remove(Cookie::build(STATE_COOKIE).path("/api/v1/auth/oidc")),
+        account_id,
+        user_agent.map(|TypedHeader(user_agent)| user_agent.to_string()),
+        Some(addr.ip().to_string()),
+    )
+    .await?;
+
+    Ok((jar, Redirect::to(oidc.post_login_redirect_url())))
+}
+
+/// Account linked to the OIDC subject, its role being synced from the claims on every login
+async fn get_or_create_account(state: &AppState, identity: &OidcIdentity) -> Result<i64> {
+    let oidc = state.oidc.as_ref().ok_or(Error::AuthDisabled)?;
+    let role = oidc.role(identity)?;
+
+    if let Some(account) = db::get_account_by_oidc_subject(&state.pool, &identity.subject).await? {
+        if !account.enabled {
+            return Err(Error::Forbidden(format!(
+                "Account {} is disabled",
+                account.username
+            )));
+        }
+
+        // Like the account routes, never demote the last enabled admin
+        let last_admin = account.role == Role::Admin
+            && role != Role::Admin
+            && db::get_other_enabled_admins_count(&state.pool, account.id).await? == 0;
+
+        if last_admin {
+            warn!(
+                "Not demoting {} to {role:?} as it's the last enabled admin account",
+                account.username
+            );
+        } else if account.role != role {
+            db::update_account(&state.pool, account.id, None, Some(role), None).await?;
+        }
+
+        return Ok(account.id);
+    }
+
+    if db::account_username_exists(&state.pool, &identity.username).await? {
+        return Err(Error::BadRequest(format!(
+            "Account with username {} already exists and isn't linked to single sign-on",
+            identity.username
+        )));
+    }
+
+    let account =
+        db::create_oidc_account(&state.pool, &identity.username, &identity.subject, role).await?;
+
+    Ok(account.id)
+}
@@ -8,22 +8,32 @@ use axum::{
     },
 };
 use futures_util::Stream;
-use sqlx::SqlitePool;
 use std::convert::Infallible;
 
-use crate::{Result, db, models::post::PostWithAuthor, state::AppState};
+use crate::{Result, access::TopicPermissions, db, models::post::PostWithAuthor, state::AppState};
 
-pub async fn get_latest_posts(State(pool): State<SqlitePool>) -> Result<impl IntoResponse> {
-    let posts = db::get_latest_posts(&pool).await?;
+pub async fn get_latest_posts(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+) -> Result<impl IntoResponse> {
+    let topic_ids = permissions.visible_topic_ids(&state).await?;
+    let posts = db::get_latest_posts(&state.pool, &topic_ids).await?;
     Ok(Json(posts))
 }
 
+/// Streams posts of the topics visible when connecting, topics created or shared afterwards only
+/// being included once reconnected
 pub async fn get_posts_sse(
     State(state): State<AppState>,
+    permissions: TopicPermissions,
 ) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+    let topic_ids = permissions.visible_topic_ids(&state).await?;
     let mut receiver = state.get_post_stream().await;
+    let sse_client_guard = state.stats.sse_client_connected();
 
     let stream = try_stream! {
+        let _sse_client_guard = sse_client_guard;
+
         loop {
             if let Err(e) = receiver.changed().await {
                 tracing::error!(error = ?e, "Failed to get message from stream");
@@ -34,6 +44,10 @@ pub async fn get_posts_sse(
                 continue;
             };
 
+            if message.topic_ids.is_disjoint(&topic_ids) {
+                continue;
+            }
+
             yield Event::default()
                 .id(&message.post.cid)
                 .event("post")
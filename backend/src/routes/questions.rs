@@ -0,0 +1,128 @@
+use async_stream::try_stream;
+use axum::{
+    Json,
+    extract::{Path, State},
+    response::{
+        Sse,
+        sse::{Event, KeepAlive},
+    },
+};
+use futures_util::{Stream, StreamExt};
+use serde_json::json;
+use std::convert::Infallible;
+use tracing::error;
+
+use crate::{
+    Error, Result,
+    access::{TopicAccess, TopicPermissions},
+    audit::{self, Actor},
+    db,
+    gemini::Delta,
+    models::{
+        audit::AuditAction,
+        question::{AskQuestion, QuestionContext, cited_post_ids, search_query},
+        usage::{LlmPurpose, UsageContext},
+    },
+    state::AppState,
+};
+
+/// Answers a question from the posts of the topic most relevant to it, streaming a `sources`
+/// event with the posts sent to the LLM, `answer` events with the parts of the answer as they're
+/// generated, then a `done` event with the IDs of the posts it cites, or an `error` event
+pub async fn ask_question(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    actor: Actor,
+    Path(id): Path<i64>,
+    Json(ask): Json<AskQuestion>,
+) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+    let topic = permissions
+        .require_topic(&state, id, TopicAccess::Edit)
+        .await?;
+
+    let question = ask.validate().map_err(Error::BadRequest)?;
+    let query = search_query(question).ok_or_else(|| {
+        Error::BadRequest("question has no words to search posts for".to_string())
+    })?;
+
+    let config = state.gemini.questions_config();
+    let posts = db::search_topic_posts(&state.pool, id, &query, config.max_posts).await?;
+    let context = QuestionContext::new(&posts, config.max_input_tokens);
+    if context.posts.is_empty() {
+        return Err(Error::BadRequest(
+            "No posts of the topic are relevant to the question".to_string(),
+        ));
+    }
+
+    let Some(mut deltas) = state
+        .gemini
+        .answer_question(
+            UsageContext {
+                purpose: LlmPurpose::Question,
+                account_id: permissions.account_id(),
+                topic_id: Some(id),
+            },
+            &topic.subject,
+            question,
+            context.posts,
+        )
+        .await?
+    else {
+        return Err(Error::GeminiDisabled);
+    };
+
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::TopicAsk,
+        Some(id),
+        None,
+        Some(json!({
+            "question": question,
+            "post_ids": context.post_ids,
+        })),
+    )
+    .await;
+
+    let post_ids = context.post_ids;
+    let sources = posts
+        .into_iter()
+        .filter(|post| post_ids.contains(&post.post.id))
+        .collect::<Vec<_>>();
+
+    let stream = try_stream! {
+        yield Event::default().event("sources").json_data(&sources).unwrap();
+
+        let mut answer = String::new();
+        while let Some(delta) = deltas.next().await {
+            match delta {
+                Ok(Delta::Text(text)) => {
+                    yield Event::default()
+                        .event("answer")
+                        .json_data(json!({ "text": text }))
+                        .unwrap();
+                    answer.push_str(&text);
+                }
+                Ok(Delta::Usage(_)) => {}
+                Err(e) => {
+                    error!("Error answering a question about topic {id}: {e}");
+                    let message = match e {
+                        Error::LlmBlocked(reason) => {
+                            format!("Blocked by the safety filters of the model: {reason}")
+                        }
+                        _ => "LLM provider unavailable".to_string(),
+                    };
+                    yield Event::default().event("error").data(message);
+                    return;
+                }
+            }
+        }
+
+        yield Event::default()
+            .event("done")
+            .json_data(json!({ "post_ids": cited_post_ids(&answer, &post_ids) }))
+            .unwrap();
+    };
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
@@ -0,0 +1,126 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::Utc;
+
+use crate::{
+    Error, Result,
+    access::{TopicAccess, TopicPermissions},
+    audit::{self, Actor},
+    db,
+    models::{
+        audit::AuditAction,
+        schedule::{UpsertAnalysisSchedule, next_run_at},
+    },
+    state::AppState,
+};
+
+/// Schedules of the topics visible to the caller, with the status of their last run
+pub async fn get_schedules(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+) -> Result<impl IntoResponse> {
+    let topic_ids = permissions.visible_topic_ids(&state).await?;
+
+    let schedules = db::get_analysis_schedules(&state.pool)
+        .await?
+        .into_iter()
+        .filter(|schedule| topic_ids.contains(&schedule.topic_id))
+        .collect::<Vec<_>>();
+
+    Ok(Json(schedules))
+}
+
+pub async fn get_schedule(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    permissions
+        .require_topic(&state, id, TopicAccess::View)
+        .await?;
+
+    db::get_analysis_schedule(&state.pool, id)
+        .await?
+        .map(Json)
+        .ok_or(Error::NotFound(format!(
+            "Topic with id {id} has no analysis schedule"
+        )))
+}
+
+pub async fn upsert_schedule(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    actor: Actor,
+    Path(id): Path<i64>,
+    Json(schedule): Json<UpsertAnalysisSchedule>,
+) -> Result<impl IntoResponse> {
+    permissions
+        .require_topic(&state, id, TopicAccess::Edit)
+        .await?;
+
+    let timezone = schedule.validate().map_err(Error::BadRequest)?;
+    let next_run_at = next_run_at(
+        schedule.frequency,
+        schedule.time,
+        timezone,
+        Utc::now().naive_utc(),
+    );
+
+    let before = db::get_analysis_schedule(&state.pool, id).await?;
+    let after = db::upsert_analysis_schedule(
+        &state.pool,
+        id,
+        permissions.account_id(),
+        &schedule,
+        timezone,
+        next_run_at,
+    )
+    .await?;
+
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::AnalysisScheduleUpdate,
+        Some(id),
+        before.as_ref().and_then(audit::snapshot),
+        audit::snapshot(&after),
+    )
+    .await;
+
+    Ok(Json(after))
+}
+
+pub async fn delete_schedule(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    permissions
+        .require_topic(&state, id, TopicAccess::Edit)
+        .await?;
+
+    let before = db::get_analysis_schedule(&state.pool, id).await?;
+
+    if db::delete_analysis_schedule(&state.pool, id).await? {
+        audit::record(
+            &state.pool,
+            &actor,
+            AuditAction::AnalysisScheduleDelete,
+            Some(id),
+            before.as_ref().and_then(audit::snapshot),
+            None,
+        )
+        .await;
+
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound(format!(
+            "Topic with id {id} has no analysis schedule"
+        )))
+    }
+}
@@ -0,0 +1,160 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{
+        IntoResponse, Sse,
+        sse::{Event, KeepAlive},
+    },
+};
+use chrono::Utc;
+use futures_util::{Stream, StreamExt};
+use std::{convert::Infallible, time::Duration};
+
+use crate::{
+    Error, Result,
+    access::{TopicAccess, TopicPermissions},
+    audit::{self, Actor},
+    db,
+    models::{
+        audit::AuditAction,
+        share_link::{CreateShareLink, SharedTopic},
+        timeseries::TimeseriesQuery,
+    },
+    routes::topics::{topic_post_stream, topic_timeseries},
+    share_links,
+    state::AppState,
+};
+
+/// How often a live stream opened through a share link checks that it wasn't revoked or expired
+const STREAM_REVALIDATION_INTERVAL: Duration = Duration::from_secs(60);
+
+pub async fn get_share_links(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    permissions
+        .require_topic(&state, id, TopicAccess::Manage)
+        .await?;
+
+    db::get_topic_share_links(&state.pool, id).await.map(Json)
+}
+
+pub async fn create_share_link(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    actor: Actor,
+    Path(id): Path<i64>,
+    Json(share_link): Json<CreateShareLink>,
+) -> Result<impl IntoResponse> {
+    permissions
+        .require_topic(&state, id, TopicAccess::Manage)
+        .await?;
+
+    let created =
+        share_links::create_share_link(&state, id, permissions.account_id(), share_link).await?;
+
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::ShareLinkCreate,
+        Some(created.share_link.id),
+        None,
+        audit::snapshot(&created.share_link),
+    )
+    .await;
+
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+pub async fn revoke_share_link(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    actor: Actor,
+    Path((id, link_id)): Path<(i64, i64)>,
+) -> Result<impl IntoResponse> {
+    permissions
+        .require_topic(&state, id, TopicAccess::Manage)
+        .await?;
+
+    if db::revoke_share_link(&state.pool, id, link_id, Utc::now().naive_utc()).await? {
+        audit::record(
+            &state.pool,
+            &actor,
+            AuditAction::ShareLinkRevoke,
+            Some(link_id),
+            None,
+            None,
+        )
+        .await;
+
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound(format!(
+            "Share link with id {link_id} not found"
+        )))
+    }
+}
+
+pub async fn get_shared_topic(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<impl IntoResponse> {
+    let share_link = share_links::authenticate_share_link(&state, &token).await?;
+    let topic = db::get_topic(&state.pool, share_link.topic_id).await?;
+
+    Ok(Json(SharedTopic {
+        subject: topic.subject,
+        description: topic.description,
+        last_analysis: topic.last_analysis,
+        last_analysis_at: topic.last_analysis_at,
+    }))
+}
+
+pub async fn get_shared_posts(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<impl IntoResponse> {
+    let share_link = share_links::authenticate_share_link(&state, &token).await?;
+
+    db::get_topic_posts(&state.pool, share_link.topic_id)
+        .await
+        .map(Json)
+}
+
+pub async fn get_shared_timeseries(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    Query(query): Query<TimeseriesQuery>,
+) -> Result<impl IntoResponse> {
+    let share_link = share_links::authenticate_share_link(&state, &token).await?;
+
+    topic_timeseries(&state.pool, share_link.topic_id, query)
+        .await
+        .map(Json)
+}
+
+/// Live posts of the topic, the stream ending once the link is revoked or expires
+pub async fn sse_shared_posts(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+    let share_link = share_links::authenticate_share_link(&state, &token).await?;
+    let stream = topic_post_stream(&state, share_link.topic_id).await;
+
+    let revoked = async move {
+        loop {
+            tokio::time::sleep(STREAM_REVALIDATION_INTERVAL).await;
+
+            if share_links::authenticate_share_link(&state, &token)
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+    };
+
+    Ok(Sse::new(stream.take_until(Box::pin(revoked))).keep_alive(KeepAlive::default()))
+}
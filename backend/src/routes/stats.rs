@@ -0,0 +1,7 @@
+use axum::{Json, extract::State, response::IntoResponse};
+
+use crate::{Result, state::AppState};
+
+pub async fn get_stats(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    Ok(Json(state.stats.snapshot()))
+}
@@ -1,7 +1,17 @@
 use axum::{Json, extract::State};
 use serde::Deserialize;
+use serde_json::json;
+use sqlx::SqlitePool;
 
-use crate::{Error, Result, gemini::GeminiClient};
+use crate::{
+    Error, Result,
+    audit::{self, Actor},
+    gemini::GeminiClient,
+    models::{
+        audit::AuditAction,
+        usage::{LlmPurpose, UsageContext},
+    },
+};
 
 #[derive(Deserialize)]
 pub struct SuggestKeywordsRequest {
@@ -10,15 +20,33 @@ pub struct SuggestKeywordsRequest {
 }
 
 pub async fn suggest_keywords(
+    State(pool): State<SqlitePool>,
     State(gemini): State<GeminiClient>,
+    actor: Actor,
     Json(request): Json<SuggestKeywordsRequest>,
 ) -> Result<Json<Vec<String>>> {
+    let context = UsageContext {
+        purpose: LlmPurpose::Keywords,
+        account_id: actor.account_id,
+        topic_id: None,
+    };
+
     let Some(keywords) = gemini
-        .generate_keywords(&request.subject, &request.description)
+        .generate_keywords(context, &request.subject, &request.description)
         .await?
     else {
         return Err(Error::GeminiDisabled);
     };
 
+    audit::record(
+        &pool,
+        &actor,
+        AuditAction::KeywordsSuggest,
+        None,
+        None,
+        Some(json!({ "subject": request.subject, "keywords": keywords })),
+    )
+    .await;
+
     Ok(Json(keywords))
 }
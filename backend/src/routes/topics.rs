@@ -1,50 +1,80 @@
 use async_stream::try_stream;
 use axum::{
     Json,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::{
         IntoResponse, Sse,
         sse::{Event, KeepAlive},
     },
 };
-use chrono::Utc;
+use chrono::{TimeDelta, Utc};
 use futures_util::Stream;
+use serde_json::json;
 use sqlx::SqlitePool;
 use std::convert::Infallible;
 
 use crate::{
-    Error, Result, db,
-    gemini::GeminiClient,
+    Error, Result,
+    access::{TopicAccess, TopicPermissions},
+    audit::{self, Actor},
+    db,
     models::{
+        audit::AuditAction,
         post::PostWithAuthor,
-        topic::{CreateTopic, UpdateTopic, UpdateTopicAnalysis},
+        timeseries::{
+            Dimension, SentimentTimeseries, SentimentTimeseriesQuery, Timeseries, TimeseriesQuery,
+        },
+        topic::{AnalyzeTopicQuery, CreateTopic, ShareTopic, UpdateTopic},
     },
     state::AppState,
 };
 
 pub async fn get_topic_by_slug(
-    State(pool): State<SqlitePool>,
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
     Path(slug): Path<String>,
 ) -> Result<impl IntoResponse> {
-    let topic_id = db::get_topic_id_by_slug(&pool, &slug)
+    let not_found = || Error::NotFound(format!("Topic with slug {slug} not found"));
+
+    let topic_id = db::get_topic_id_by_slug(&state.pool, &slug)
         .await?
-        .ok_or(Error::NotFound(format!("Topic with slug {slug} not found")))?;
+        .ok_or_else(not_found)?;
+
+    permissions
+        .require_topic(&state, topic_id, TopicAccess::View)
+        .await
+        .map_err(|e| match e {
+            Error::NotFound(_) => not_found(),
+            e => e,
+        })?;
 
     Ok(topic_id.to_string())
 }
 
 pub async fn sse_posts(
     State(state): State<AppState>,
+    permissions: TopicPermissions,
     Path(id): Path<i64>,
 ) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
-    if !db::topic_exists(&state.pool, id).await? {
-        return Err(Error::NotFound(format!("Topic with id {id} not found")));
-    }
+    permissions
+        .require_topic(&state, id, TopicAccess::View)
+        .await?;
 
+    Ok(Sse::new(topic_post_stream(&state, id).await).keep_alive(KeepAlive::default()))
+}
+
+/// Live posts of the topic, counting the client as connected while the stream is alive
+pub async fn topic_post_stream(
+    state: &AppState,
+    id: i64,
+) -> impl Stream<Item = std::result::Result<Event, Infallible>> + use<> {
     let mut receiver = state.get_post_stream().await;
+    let sse_client_guard = state.stats.sse_client_connected();
 
     let stream = try_stream! {
+        let _sse_client_guard = sse_client_guard;
+
         loop {
             if let Err(e) = receiver.changed().await {
                 tracing::error!(error = ?e, "Failed to get message from stream");
@@ -67,75 +97,298 @@ pub async fn sse_posts(
         }
     };
 
-    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+    stream
 }
 
 pub async fn analyze_topic(
-    State(pool): State<SqlitePool>,
-    State(gemini): State<GeminiClient>,
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    actor: Actor,
     Path(id): Path<i64>,
+    Query(query): Query<AnalyzeTopicQuery>,
 ) -> Result<impl IntoResponse> {
-    let posts = db::get_topic_posts(&pool, id)
-        .await?
-        .into_iter()
-        .map(|p| p.post.text)
-        .collect::<Vec<String>>();
+    let topic = permissions
+        .require_topic(&state, id, TopicAccess::Edit)
+        .await?;
 
-    let Some(summary) = gemini.analyze_posts(&posts).await? else {
-        return Err(Error::GeminiDisabled);
-    };
+    let config = state.gemini.analysis_config();
+    let (since, until) = query
+        .window(
+            Utc::now().naive_utc(),
+            TimeDelta::hours(config.default_window_hours),
+            TimeDelta::days(config.max_window_days),
+        )
+        .map_err(Error::BadRequest)?;
 
-    let analysis = UpdateTopicAnalysis {
-        last_analysis: summary.clone(),
-        last_analysis_at: Utc::now().naive_utc(),
-    };
+    let analysis =
+        crate::analysis::analyze_topic(&state, &topic, (since, until), permissions.account_id())
+            .await?;
 
-    db::update_topic_analysis(&pool, id, analysis)
-        .await
-        .map(Json)
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::TopicAnalyze,
+        Some(id),
+        None,
+        Some(json!({
+            "analysis_id": analysis.id,
+            "since": since,
+            "until": until,
+            "matched_post_count": analysis.matched_post_count,
+            "post_count": analysis.post_count,
+        })),
+    )
+    .await;
+
+    Ok(Json(analysis))
 }
 
 pub async fn update_topic(
-    State(pool): State<SqlitePool>,
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    actor: Actor,
     Path(id): Path<i64>,
     Json(update_topic): Json<UpdateTopic>,
 ) -> Result<impl IntoResponse> {
-    db::update_topic(&pool, id, update_topic).await?;
+    let required = if update_topic.visibility.is_some() {
+        TopicAccess::Manage
+    } else {
+        TopicAccess::Edit
+    };
+    let before = permissions.require_topic(&state, id, required).await?;
+
+    db::update_topic(&state.pool, id, update_topic).await?;
+    let after = db::get_topic(&state.pool, id).await?;
+
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::TopicUpdate,
+        Some(id),
+        audit::snapshot(&before),
+        audit::snapshot(&after),
+    )
+    .await;
+
     Ok(StatusCode::NO_CONTENT)
 }
 
 pub async fn get_posts(
-    State(pool): State<SqlitePool>,
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
     Path(id): Path<i64>,
 ) -> Result<impl IntoResponse> {
-    db::get_topic_posts(&pool, id).await.map(Json)
+    permissions
+        .require_topic(&state, id, TopicAccess::View)
+        .await?;
+
+    db::get_topic_posts(&state.pool, id).await.map(Json)
 }
 
-pub async fn get_topics(State(pool): State<SqlitePool>) -> Result<impl IntoResponse> {
-    db::get_all_topics_with_post_count(&pool).await.map(Json)
+pub async fn get_topics(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+) -> Result<impl IntoResponse> {
+    let topics = db::get_all_topics_with_post_count(&state.pool)
+        .await?
+        .into_iter()
+        .filter(|topic| {
+            permissions.access(topic.id, topic.owner_id, topic.visibility) >= TopicAccess::View
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Json(topics))
 }
 
 pub async fn get_topic(
-    State(pool): State<SqlitePool>,
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
     Path(id): Path<i64>,
 ) -> Result<impl IntoResponse> {
-    db::get_topic(&pool, id).await.map(Json)
+    permissions
+        .require_topic(&state, id, TopicAccess::View)
+        .await
+        .map(Json)
 }
 
 pub async fn create_topic(
-    State(pool): State<SqlitePool>,
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    actor: Actor,
     Json(topic): Json<CreateTopic>,
 ) -> Result<impl IntoResponse> {
-    db::create_topic(&pool, topic).await.map(Json)
+    let topic = db::create_topic(&state.pool, topic, permissions.account_id()).await?;
+
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::TopicCreate,
+        Some(topic.id),
+        None,
+        audit::snapshot(&topic),
+    )
+    .await;
+
+    Ok(Json(topic))
 }
 
 pub async fn delete_topic(
-    State(pool): State<SqlitePool>,
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    actor: Actor,
     Path(id): Path<i64>,
 ) -> Result<impl IntoResponse> {
-    if db::delete_topic(&pool, id).await? {
+    let before = permissions
+        .require_topic(&state, id, TopicAccess::Manage)
+        .await?;
+
+    if db::delete_topic(&state.pool, id).await? {
+        audit::record(
+            &state.pool,
+            &actor,
+            AuditAction::TopicDelete,
+            Some(id),
+            audit::snapshot(&before),
+            None,
+        )
+        .await;
+
         Ok(StatusCode::NO_CONTENT)
     } else {
         Err(Error::NotFound(format!("Topic with id {id} not found")))
     }
 }
+
+pub async fn get_timeseries(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(id): Path<i64>,
+    Query(query): Query<TimeseriesQuery>,
+) -> Result<impl IntoResponse> {
+    permissions
+        .require_topic(&state, id, TopicAccess::View)
+        .await?;
+
+    topic_timeseries(&state.pool, id, query).await.map(Json)
+}
+
+pub async fn topic_timeseries(
+    pool: &SqlitePool,
+    id: i64,
+    query: TimeseriesQuery,
+) -> Result<Timeseries> {
+    let range = query
+        .range(Utc::now().naive_utc())
+        .map_err(Error::BadRequest)?;
+    let split_by = query.split_by.unwrap_or(Dimension::Total);
+
+    let rollups =
+        db::get_topic_post_rollups(pool, id, query.bucket, split_by, range.0, range.1).await?;
+
+    Ok(Timeseries::from_rollups(
+        id,
+        query.bucket,
+        range,
+        split_by,
+        rollups,
+    ))
+}
+
+/// Posts per local sentiment label and their average score over time
+pub async fn get_sentiment_timeseries(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(id): Path<i64>,
+    Query(query): Query<SentimentTimeseriesQuery>,
+) -> Result<impl IntoResponse> {
+    permissions
+        .require_topic(&state, id, TopicAccess::View)
+        .await?;
+
+    let range = query
+        .range(Utc::now().naive_utc())
+        .map_err(Error::BadRequest)?;
+
+    let rollups =
+        db::get_topic_sentiment_rollups(&state.pool, id, query.bucket, range.0, range.1).await?;
+
+    Ok(Json(SentimentTimeseries::from_rollups(
+        id,
+        query.bucket,
+        range,
+        rollups,
+    )))
+}
+
+pub async fn get_topic_shares(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    permissions
+        .require_topic(&state, id, TopicAccess::Manage)
+        .await?;
+
+    db::get_topic_shares(&state.pool, id).await.map(Json)
+}
+
+pub async fn share_topic(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    actor: Actor,
+    Path((id, account_id)): Path<(i64, i64)>,
+    Json(share): Json<ShareTopic>,
+) -> Result<impl IntoResponse> {
+    permissions
+        .require_topic(&state, id, TopicAccess::Manage)
+        .await?;
+
+    if db::get_account(&state.pool, account_id).await?.is_none() {
+        return Err(Error::NotFound(format!(
+            "Account with id {account_id} not found"
+        )));
+    }
+
+    db::upsert_topic_share(&state.pool, id, account_id, share.role).await?;
+
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::TopicShare,
+        Some(id),
+        None,
+        Some(json!({ "account_id": account_id, "role": share.role })),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn unshare_topic(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    actor: Actor,
+    Path((id, account_id)): Path<(i64, i64)>,
+) -> Result<impl IntoResponse> {
+    permissions
+        .require_topic(&state, id, TopicAccess::Manage)
+        .await?;
+
+    if db::delete_topic_share(&state.pool, id, account_id).await? {
+        audit::record(
+            &state.pool,
+            &actor,
+            AuditAction::TopicUnshare,
+            Some(id),
+            Some(json!({ "account_id": account_id })),
+            None,
+        )
+        .await;
+
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound(format!(
+            "Topic with id {id} isn't shared with account {account_id}"
+        )))
+    }
+}
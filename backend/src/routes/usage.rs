@@ -0,0 +1,33 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    response::IntoResponse,
+};
+use chrono::Utc;
+
+use crate::{
+    Error, Result, db,
+    models::usage::{UsageQuery, UsageReport, month_bounds},
+    state::AppState,
+};
+
+pub async fn get_usage(
+    State(state): State<AppState>,
+    Query(query): Query<UsageQuery>,
+) -> Result<impl IntoResponse> {
+    let now = Utc::now().naive_utc();
+    let until = query.until.map(|until| until.naive_utc()).unwrap_or(now);
+    let since = query
+        .since
+        .map(|since| since.naive_utc())
+        .unwrap_or(month_bounds(now).0);
+
+    if since >= until {
+        return Err(Error::BadRequest("since must be before until".to_string()));
+    }
+
+    let rows = db::get_llm_usage(&state.pool, since, until).await?;
+    let budget = state.gemini.budget(now).await?;
+
+    Ok(Json(UsageReport::new((since, until), &rows, budget)))
+}
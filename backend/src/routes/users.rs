@@ -1,9 +1,28 @@
-use axum::{Json, extract::State, response::IntoResponse};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    response::IntoResponse,
+};
 use futures_util::future::join;
 use serde::Serialize;
+use serde_json::json;
 use sqlx::SqlitePool;
+use std::collections::BTreeSet;
 
-use crate::{Result, db, models::user::User};
+use crate::{
+    Error, Result,
+    access::TopicPermissions,
+    audit::{self, Actor},
+    db,
+    models::{
+        audit::AuditAction,
+        user::{User, UserPostPage, UserPostsQuery, UserProfile},
+    },
+    state::AppState,
+};
+
+/// Authors listed by `GET /users/{did}/mentioned_by`
+const MENTIONERS_LIMIT: i64 = 50;
 
 #[derive(Debug, Serialize)]
 pub struct LatestUsers {
@@ -18,3 +37,129 @@ pub async fn get_latest_users(State(pool): State<SqlitePool>) -> Result<impl Int
         total: total?,
     }))
 }
+
+pub async fn get_user(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(did): Path<String>,
+) -> Result<impl IntoResponse> {
+    let (mut profile, _) = visible_user(&state, &permissions, &did).await?;
+
+    // Anonymous callers can only see the analyses requested while authentication was disabled
+    if permissions.account_id().is_some() || !state.config.server.auth.enabled {
+        profile.last_analysis =
+            db::get_last_user_analysis(&state.pool, profile.user.id, permissions.account_id())
+                .await?;
+    }
+
+    Ok(Json(profile))
+}
+
+pub async fn get_user_posts(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(did): Path<String>,
+    Query(query): Query<UserPostsQuery>,
+) -> Result<impl IntoResponse> {
+    let (profile, topic_ids) = visible_user(&state, &permissions, &did).await?;
+
+    let (page, per_page) = query.pagination();
+    let posts =
+        db::get_user_posts(&state.pool, profile.user.id, &topic_ids, page, per_page).await?;
+
+    Ok(Json(UserPostPage {
+        posts,
+        page,
+        per_page,
+        total: profile.post_count,
+    }))
+}
+
+pub async fn get_user_topics(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(did): Path<String>,
+) -> Result<impl IntoResponse> {
+    let (profile, topic_ids) = visible_user(&state, &permissions, &did).await?;
+
+    db::get_user_topics(&state.pool, profile.user.id, &topic_ids)
+        .await
+        .map(Json)
+}
+
+pub async fn get_user_mentioners(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    Path(did): Path<String>,
+) -> Result<impl IntoResponse> {
+    let (profile, topic_ids) = visible_user(&state, &permissions, &did).await?;
+
+    db::get_user_mentioners(&state.pool, profile.user.id, &topic_ids, MENTIONERS_LIMIT)
+        .await
+        .map(Json)
+}
+
+pub async fn analyze_user(
+    State(state): State<AppState>,
+    permissions: TopicPermissions,
+    actor: Actor,
+    Path(did): Path<String>,
+) -> Result<impl IntoResponse> {
+    let (profile, topic_ids) = visible_user(&state, &permissions, &did).await?;
+
+    let analysis =
+        crate::analysis::analyze_user(&state, &profile.user, &topic_ids, permissions.account_id())
+            .await?;
+
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::UserAnalyze,
+        Some(profile.user.id),
+        None,
+        Some(json!({
+            "did": analysis.did,
+            "matched_post_count": analysis.matched_post_count,
+            "post_count": analysis.post_count,
+        })),
+    )
+    .await;
+
+    Ok(Json(analysis))
+}
+
+/// User along with the topics visible to the caller, users who neither posted nor were mentioned
+/// in any of them being reported as not found to not leak the topics they appear in
+async fn visible_user(
+    state: &AppState,
+    permissions: &TopicPermissions,
+    did: &str,
+) -> Result<(UserProfile, BTreeSet<i64>)> {
+    let not_found = || Error::NotFound(format!("User with did {did} not found"));
+
+    let user = db::get_user_by_did(&state.pool, did)
+        .await?
+        .ok_or_else(not_found)?;
+    let topic_ids = permissions.visible_topic_ids(state).await?;
+
+    let (post_count, mention_count) = join(
+        db::count_user_posts(&state.pool, user.id, &topic_ids),
+        db::count_user_mentions(&state.pool, user.id, &topic_ids),
+    )
+    .await;
+    let (post_count, mention_count) = (post_count?, mention_count?);
+
+    if post_count == 0 && mention_count == 0 {
+        return Err(not_found());
+    }
+
+    Ok((
+        UserProfile {
+            user,
+            post_count,
+            mention_count,
+            last_analysis: None,
+        },
+        topic_ids,
+    ))
+}
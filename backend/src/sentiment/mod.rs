@@ -0,0 +1,251 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::LazyLock};
+
+/// Words of a language and their valence, from -4 (very negative) to 4 (very positive)
+struct Lexicon {
+    lang: &'static str,
+    valences: HashMap<String, f64>,
+}
+
+impl Lexicon {
+    fn parse(lang: &'static str, words: &str) -> Self {
+        let valences = words
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (word, valence) = line.rsplit_once(' ')?;
+                Some((fold(word), valence.parse().ok()?))
+            })
+            .collect();
+
+        Self { lang, valences }
+    }
+}
+
+static LEXICONS: LazyLock<[Lexicon; 2]> = LazyLock::new(|| {
+    [
+        Lexicon::parse("en", include_str!("en.txt")),
+        Lexicon::parse("fr", include_str!("fr.txt")),
+    ]
+});
+
+/// Words flipping the valence of the words following them
+const NEGATIONS: [&str; 14] = [
+    "not", "no", "never", "nobody", "nothing", "without", "ne", "pas", "jamais", "rien", "aucun",
+    "aucune", "sans", "personne",
+];
+
+/// Words strengthening the valence of the word following them
+const INTENSIFIERS: [&str; 14] = [
+    "very",
+    "really",
+    "so",
+    "extremely",
+    "absolutely",
+    "totally",
+    "super",
+    "tres",
+    "vraiment",
+    "trop",
+    "tellement",
+    "extremement",
+    "completement",
+    "hyper",
+];
+
+const EMOJIS: [(char, f64); 16] = [
+    ('😀', 2.0),
+    ('😃', 2.0),
+    ('😄', 2.0),
+    ('😊', 2.0),
+    ('😍', 3.0),
+    ('🥰', 3.0),
+    ('😂', 2.0),
+    ('❤', 3.0),
+    ('👍', 2.0),
+    ('🎉', 3.0),
+    ('😢', -2.0),
+    ('😭', -2.0),
+    ('😡', -3.0),
+    ('🤬', -3.0),
+    ('👎', -2.0),
+    ('💩', -2.0),
+];
+
+/// How much negated words count, in the opposite direction
+const NEGATION_FACTOR: f64 = -0.5;
+const INTENSIFIER_FACTOR: f64 = 1.5;
+/// Words before a valence word within which a negation applies
+const NEGATION_WINDOW: usize = 3;
+/// Normalization constant of the score, approximating the maximum expected sum of valences
+const ALPHA: f64 = 15.0;
+
+/// Threshold above or below which a score is positive or negative
+const NEUTRAL_THRESHOLD: f64 = 0.05;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum SentimentLabel {
+    Positive,
+    Neutral,
+    Negative,
+}
+
+impl SentimentLabel {
+    pub fn from_score(score: f64) -> Self {
+        if score >= NEUTRAL_THRESHOLD {
+            Self::Positive
+        } else if score <= -NEUTRAL_THRESHOLD {
+            Self::Negative
+        } else {
+            Self::Neutral
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Positive => "positive",
+            Self::Neutral => "neutral",
+            Self::Negative => "negative",
+        }
+    }
+}
+
+/// Sentiment of a post from -1 to 1, using the lexicons of its languages, or all of them when it
+/// has none, `None` when none of its languages are supported
+pub fn score(text: &str, langs: &[String]) -> Option<f64> {
+    let lexicons = LEXICONS
+        .iter()
+        .filter(|lexicon| {
+            langs.is_empty()
+                || langs
+                    .iter()
+                    .any(|lang| lang.split('-').next() == Some(lexicon.lang))
+        })
+        .collect::<Vec<_>>();
+
+    if lexicons.is_empty() {
+        return None;
+    }
+
+    let valence = |token: &str| {
+        lexicons
+            .iter()
+            .find_map(|lexicon| lexicon.valences.get(token).copied())
+    };
+
+    let tokens = tokenize(text);
+    let mut sum = 0.0;
+
+    for (i, token) in tokens.iter().enumerate() {
+        let next_has_valence = tokens
+            .get(i + 1)
+            .is_some_and(|next| valence(next).is_some());
+        if INTENSIFIERS.contains(&token.as_str()) && next_has_valence {
+            continue;
+        }
+
+        let Some(mut token_valence) = valence(token) else {
+            continue;
+        };
+
+        if i > 0 && INTENSIFIERS.contains(&tokens[i - 1].as_str()) {
+            token_valence *= INTENSIFIER_FACTOR;
+        }
+
+        if tokens[i.saturating_sub(NEGATION_WINDOW)..i]
+            .iter()
+            .any(|previous| NEGATIONS.contains(&previous.as_str()) || previous.ends_with("n't"))
+        {
+            token_valence *= NEGATION_FACTOR;
+        }
+
+        sum += token_valence;
+    }
+
+    sum += text
+        .chars()
+        .filter_map(|c| EMOJIS.iter().find(|(emoji, _)| *emoji == c))
+        .map(|(_, valence)| valence)
+        .sum::<f64>();
+
+    Some(sum / (sum * sum + ALPHA).sqrt())
+}
+
+/// Lowercased words without accents, French elisions such as `l'` or `qu'` being removed
+fn tokenize(text: &str) -> Vec<String> {
+    fold(&text.replace('’', "'"))
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .map(|word| match word.split_once('\'') {
+            Some((elision, rest))
+                if !rest.is_empty()
+                    && matches!(
+                        elision,
+                        "l" | "d" | "j" | "m" | "n" | "s" | "t" | "c" | "qu"
+                    ) =>
+            {
+                rest
+            }
+            _ => word,
+        })
+        .map(|word| word.trim_matches('\''))
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Lowercases and removes the accents of French letters
+fn fold(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'à' | 'â' | 'ä' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'î' | 'ï' => 'i',
+            'ô' | 'ö' => 'o',
+            'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn langs(langs: &[&str]) -> Vec<String> {
+        langs.iter().map(|lang| lang.to_string()).collect()
+    }
+
+    #[test]
+    fn test_given_english_post_when_score_return_sentiment_with_negation_and_intensifiers() {
+        let score = |text| score(text, &langs(&["en"])).unwrap();
+
+        assert!(score("This release is really great, I love it 🎉") > 0.7);
+        assert!(score("What a terrible, useless update") < -0.5);
+        assert!(score("This is not good") < 0.0);
+        assert!(score("very good") > score("good"));
+        assert_eq!(score("The meeting is at noon"), 0.0);
+        assert_eq!(
+            SentimentLabel::from_score(score("The meeting is at noon")),
+            SentimentLabel::Neutral
+        );
+    }
+
+    #[test]
+    fn test_given_french_post_when_score_return_sentiment_ignoring_accents_and_elisions() {
+        let score = |text| score(text, &langs(&["fr-CA"])).unwrap();
+
+        assert!(score("C'est génial, vraiment magnifique !") > 0.5);
+        assert!(score("Quelle déception, c'est nul") < -0.5);
+        assert!(score("Je ne suis pas content") < 0.0);
+        assert!(score("l'horreur") < 0.0);
+    }
+
+    #[test]
+    fn test_given_unsupported_or_missing_langs_when_score_return_none_or_all_lexicons() {
+        assert_eq!(score("素晴らしい", &langs(&["ja"])), None);
+        assert!(score("super génial", &[]).unwrap() > 0.0);
+    }
+}
@@ -0,0 +1,67 @@
+use chrono::{TimeDelta, Utc};
+
+use crate::{
+    Error, Result,
+    auth::{hash_token, random_token},
+    db,
+    models::share_link::{CreateShareLink, CreatedShareLink, ShareLink},
+    state::AppState,
+};
+
+const SHARE_LINK_PREFIX: &str = "bfs_";
+
+pub async fn create_share_link(
+    state: &AppState,
+    topic_id: i64,
+    created_by: Option<i64>,
+    share_link: CreateShareLink,
+) -> Result<CreatedShareLink> {
+    if share_link.name.trim().is_empty() {
+        return Err(Error::BadRequest(
+            "Share link name can't be empty".to_string(),
+        ));
+    }
+
+    if share_link.expires_in_days.is_some_and(|days| days <= 0) {
+        return Err(Error::BadRequest(
+            "Share link expiry must be a positive number of days".to_string(),
+        ));
+    }
+
+    let token = format!("{SHARE_LINK_PREFIX}{}", random_token());
+
+    let expires_at = share_link
+        .expires_in_days
+        .map(|days| Utc::now().naive_utc() + TimeDelta::days(days));
+
+    let created = db::create_share_link(
+        &state.pool,
+        topic_id,
+        created_by,
+        share_link.name.trim(),
+        &hash_token(&token),
+        expires_at,
+    )
+    .await?;
+
+    Ok(CreatedShareLink {
+        share_link: created,
+        path: format!("/api/v1/shared/{token}"),
+        token,
+    })
+}
+
+/// Share link of the token which is neither revoked nor expired, reported as not found otherwise
+pub async fn authenticate_share_link(state: &AppState, token: &str) -> Result<ShareLink> {
+    let now = Utc::now().naive_utc();
+
+    let share_link = db::get_valid_share_link(&state.pool, &hash_token(token), now)
+        .await?
+        .ok_or(Error::NotFound(
+            "Share link not found, revoked or expired".to_string(),
+        ))?;
+
+    db::update_share_link_last_used(&state.pool, share_link.id, now).await?;
+
+    Ok(share_link)
+}
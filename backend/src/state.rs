@@ -4,7 +4,6 @@ use std::{
     collections::{BTreeMap, BTreeSet},
     sync::Arc,
 };
-use time::OffsetDateTime;
 use tokio::sync::{
     RwLock,
     watch::{Receiver, Sender, channel},
@@ -12,9 +11,17 @@ use tokio::sync::{
 use tracing::error;
 
 use crate::{
-    Result, config, db,
+    Result,
+    alerts::AlertEngine,
+    auth, config, db,
     gemini::GeminiClient,
-    models::post::{Post, PostWithAuthor},
+    models::{
+        post::{Post, PostWithAuthor},
+        timeseries::Bucket,
+    },
+    oidc::OidcClient,
+    stats::IngestStats,
+    throttle::LoginThrottle,
 };
 
 #[derive(Clone)]
@@ -40,25 +47,31 @@ impl From<StreamPost> for PostWithAuthor {
     }
 }
 
-#[derive(Clone)]
-pub struct Session {
-    pub id: String,
-    pub expires_at: OffsetDateTime,
-}
+type PostStreams = Arc<RwLock<(Sender<Option<StreamPost>>, Receiver<Option<StreamPost>>)>>;
 
 #[derive(Clone)]
 pub struct AppState {
     pub pool: SqlitePool,
     pub gemini: GeminiClient,
+    pub alerts: AlertEngine,
+    pub stats: IngestStats,
+    pub oidc: Option<OidcClient>,
+    pub login_throttle: LoginThrottle,
     pub config: config::Config,
-    pub post_streams: Arc<RwLock<(Sender<Option<StreamPost>>, Receiver<Option<StreamPost>>)>>,
-    pub session_id: Arc<RwLock<Option<Session>>>,
+    pub post_streams: PostStreams,
 }
 
 impl AppState {
     pub async fn new(config: config::Config) -> Result<Self> {
         let pool = db::new(&config.database.url).await?;
-        let gemini = GeminiClient::new(&config.gemini)?;
+        auth::bootstrap_admin(&pool, &config.server.auth).await?;
+        let gemini = GeminiClient::new(&config.gemini, pool.clone())?;
+        let alerts = AlertEngine::new(
+            &config.alerts,
+            config.timeseries.retention(Bucket::Minute),
+            pool.clone(),
+        )?;
+        let oidc = OidcClient::new(&config.server.auth)?;
 
         let (sender, receiver) = channel(None);
         let post_streams = Arc::new(RwLock::new((sender, receiver)));
@@ -66,9 +79,12 @@ impl AppState {
         Ok(Self {
             pool,
             gemini,
+            alerts,
+            stats: IngestStats::default(),
+            oidc,
+            login_throttle: LoginThrottle::new(config.server.auth.login_throttle.clone()),
             config,
             post_streams,
-            session_id: Arc::new(RwLock::new(None)),
         })
     }
 
@@ -0,0 +1,358 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicI64, AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::jetstream::message::EventKind;
+
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+const LATENCY_SAMPLES: usize = 1024;
+
+#[derive(Default)]
+struct EventCounters {
+    commit_create: AtomicU64,
+    commit_update: AtomicU64,
+    commit_delete: AtomicU64,
+    identity: AtomicU64,
+    account: AtomicU64,
+}
+
+impl EventCounters {
+    fn get(&self, kind: EventKind) -> &AtomicU64 {
+        match kind {
+            EventKind::CommitCreate => &self.commit_create,
+            EventKind::CommitUpdate => &self.commit_update,
+            EventKind::CommitDelete => &self.commit_delete,
+            EventKind::Identity => &self.identity,
+            EventKind::Account => &self.account,
+        }
+    }
+}
+
+/// Samples of a monotonic counter over the last [`RATE_WINDOW`], used to compute a per second rate
+#[derive(Default)]
+struct RateWindow {
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl RateWindow {
+    fn sample(&mut self, at: Instant, total: u64) {
+        self.samples.push_back((at, total));
+
+        while self
+            .samples
+            .get(1)
+            .is_some_and(|(oldest, _)| at.duration_since(*oldest) >= RATE_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    fn per_second(&self) -> f64 {
+        let (Some((first_at, first)), Some((last_at, last))) =
+            (self.samples.front(), self.samples.back())
+        else {
+            return 0.0;
+        };
+
+        let elapsed = last_at.duration_since(*first_at).as_secs_f64();
+        if elapsed == 0.0 {
+            return 0.0;
+        }
+
+        last.saturating_sub(*first) as f64 / elapsed
+    }
+}
+
+#[derive(Default)]
+struct LatencyWindow {
+    count: u64,
+    samples: VecDeque<Duration>,
+}
+
+impl LatencyWindow {
+    fn record(&mut self, latency: Duration) {
+        self.count += 1;
+        if self.samples.len() == LATENCY_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(latency);
+    }
+
+    fn summary(&self) -> LatencySummary {
+        let mut samples = self.samples.iter().copied().collect::<Vec<_>>();
+        samples.sort();
+
+        let percentile = |p: f64| {
+            samples
+                .get(((samples.len() as f64 * p).ceil() as usize).saturating_sub(1))
+                .map(as_millis)
+        };
+
+        LatencySummary {
+            count: self.count,
+            avg_ms: (!samples.is_empty())
+                .then(|| as_millis(&samples.iter().sum::<Duration>()) / samples.len() as f64),
+            p50_ms: percentile(0.5),
+            p95_ms: percentile(0.95),
+            max_ms: samples.last().map(as_millis),
+        }
+    }
+}
+
+fn as_millis(duration: &Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+struct Inner {
+    started_at: DateTime<Utc>,
+    started: Instant,
+    messages: AtomicU64,
+    parse_failures: AtomicU64,
+    matched_posts: AtomicU64,
+    events: EventCounters,
+    last_event_time_us: AtomicI64,
+    sse_clients: AtomicI64,
+    message_rate: Mutex<RateWindow>,
+    matched_post_rate: Mutex<RateWindow>,
+    did_resolution: Mutex<LatencyWindow>,
+    database_writes: Mutex<LatencyWindow>,
+}
+
+/// In memory statistics about the ingestion of the Jetstream firehose, reset on restart
+#[derive(Clone)]
+pub struct IngestStats {
+    inner: Arc<Inner>,
+}
+
+impl Default for IngestStats {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                started_at: Utc::now(),
+                started: Instant::now(),
+                messages: AtomicU64::default(),
+                parse_failures: AtomicU64::default(),
+                matched_posts: AtomicU64::default(),
+                events: EventCounters::default(),
+                last_event_time_us: AtomicI64::default(),
+                sse_clients: AtomicI64::default(),
+                message_rate: Mutex::default(),
+                matched_post_rate: Mutex::default(),
+                did_resolution: Mutex::default(),
+                database_writes: Mutex::default(),
+            }),
+        }
+    }
+}
+
+impl IngestStats {
+    pub fn record_event(&self, kind: EventKind, time_us: i64) {
+        self.inner.messages.fetch_add(1, Ordering::Relaxed);
+        self.inner.events.get(kind).fetch_add(1, Ordering::Relaxed);
+        self.inner
+            .last_event_time_us
+            .fetch_max(time_us, Ordering::Relaxed);
+    }
+
+    pub fn record_parse_failure(&self) {
+        self.inner.messages.fetch_add(1, Ordering::Relaxed);
+        self.inner.parse_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_matched_post(&self) {
+        self.inner.matched_posts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_did_resolution(&self, latency: Duration) {
+        self.inner.did_resolution.lock().unwrap().record(latency);
+    }
+
+    pub fn record_database_write(&self, latency: Duration) {
+        self.inner.database_writes.lock().unwrap().record(latency);
+    }
+
+    /// Counts a connected SSE client until the returned guard is dropped
+    pub fn sse_client_connected(&self) -> SseClientGuard {
+        self.inner.sse_clients.fetch_add(1, Ordering::Relaxed);
+        SseClientGuard {
+            stats: self.clone(),
+        }
+    }
+
+    fn sample_rates(&self) {
+        let now = Instant::now();
+
+        self.inner
+            .message_rate
+            .lock()
+            .unwrap()
+            .sample(now, self.inner.messages.load(Ordering::Relaxed));
+        self.inner
+            .matched_post_rate
+            .lock()
+            .unwrap()
+            .sample(now, self.inner.matched_posts.load(Ordering::Relaxed));
+    }
+
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let inner = &self.inner;
+        let last_event_time_us = inner.last_event_time_us.load(Ordering::Relaxed);
+        let last_event_at = (last_event_time_us > 0)
+            .then(|| DateTime::from_timestamp_micros(last_event_time_us))
+            .flatten();
+
+        StatsSnapshot {
+            started_at: inner.started_at,
+            uptime_seconds: inner.started.elapsed().as_secs(),
+            firehose: FirehoseStats {
+                messages_total: inner.messages.load(Ordering::Relaxed),
+                messages_per_second: inner.message_rate.lock().unwrap().per_second(),
+                parse_failures: inner.parse_failures.load(Ordering::Relaxed),
+                events: EventStats {
+                    commit_create: inner.events.commit_create.load(Ordering::Relaxed),
+                    commit_update: inner.events.commit_update.load(Ordering::Relaxed),
+                    commit_delete: inner.events.commit_delete.load(Ordering::Relaxed),
+                    identity: inner.events.identity.load(Ordering::Relaxed),
+                    account: inner.events.account.load(Ordering::Relaxed),
+                },
+                last_event_at,
+                lag_seconds: last_event_at.map(|last_event_at| {
+                    (Utc::now() - last_event_at).num_milliseconds() as f64 / 1000.0
+                }),
+            },
+            matches: MatchStats {
+                posts_total: inner.matched_posts.load(Ordering::Relaxed),
+                posts_per_second: inner.matched_post_rate.lock().unwrap().per_second(),
+            },
+            did_resolution: inner.did_resolution.lock().unwrap().summary(),
+            database_writes: inner.database_writes.lock().unwrap().summary(),
+            sse_clients: inner.sse_clients.load(Ordering::Relaxed),
+        }
+    }
+}
+
+pub struct SseClientGuard {
+    stats: IngestStats,
+}
+
+impl Drop for SseClientGuard {
+    fn drop(&mut self) {
+        self.stats.inner.sse_clients.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatsSnapshot {
+    pub started_at: DateTime<Utc>,
+    pub uptime_seconds: u64,
+    pub firehose: FirehoseStats,
+    pub matches: MatchStats,
+    pub did_resolution: LatencySummary,
+    pub database_writes: LatencySummary,
+    pub sse_clients: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FirehoseStats {
+    pub messages_total: u64,
+    pub messages_per_second: f64,
+    pub parse_failures: u64,
+    pub events: EventStats,
+    pub last_event_at: Option<DateTime<Utc>>,
+    /// Time between now and the `time_us` of the last event received from Jetstream
+    pub lag_seconds: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EventStats {
+    pub commit_create: u64,
+    pub commit_update: u64,
+    pub commit_delete: u64,
+    pub identity: u64,
+    pub account: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MatchStats {
+    pub posts_total: u64,
+    pub posts_per_second: f64,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub avg_ms: Option<f64>,
+    pub p50_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+}
+
+pub fn start_sampler(stats: IngestStats) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+
+        loop {
+            interval.tick().await;
+            stats.sample_rates();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_given_samples_when_per_second_return_rate_over_window() {
+        let start = Instant::now();
+        let mut window = RateWindow::default();
+
+        window.sample(start, 0);
+        window.sample(start + Duration::from_secs(5), 50);
+        window.sample(start + Duration::from_secs(10), 200);
+
+        assert_eq!(window.per_second(), 20.0);
+    }
+
+    #[test]
+    fn test_given_old_samples_when_sample_drop_samples_outside_window() {
+        let start = Instant::now();
+        let mut window = RateWindow::default();
+
+        window.sample(start, 0);
+        window.sample(start + Duration::from_secs(30), 1000);
+        window.sample(start + Duration::from_secs(90), 1600);
+
+        assert_eq!(window.samples.len(), 2);
+        assert_eq!(window.per_second(), 10.0);
+    }
+
+    #[test]
+    fn test_given_latencies_when_summary_return_percentiles() {
+        let mut window = LatencyWindow::default();
+        for ms in 1..=100 {
+            window.record(Duration::from_millis(ms));
+        }
+
+        let summary = window.summary();
+        assert_eq!(summary.count, 100);
+        assert_eq!(summary.p50_ms, Some(50.0));
+        assert_eq!(summary.p95_ms, Some(95.0));
+        assert_eq!(summary.max_ms, Some(100.0));
+        assert_eq!(summary.avg_ms, Some(50.5));
+    }
+
+    #[test]
+    fn test_given_no_latencies_when_summary_return_empty_summary() {
+        let summary = LatencyWindow::default().summary();
+        assert_eq!(summary.count, 0);
+        assert_eq!(summary.avg_ms, None);
+    }
+}
@@ -0,0 +1,381 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::config;
+
+/// Oldest entries are dropped once the map grows past this size
+const MAX_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    Ip(IpAddr),
+    Username(String),
+}
+
+#[derive(Debug, Default)]
+struct Attempts {
+    failures: u32,
+    last_failure: Option<Instant>,
+    blocked_until: Option<Instant>,
+    /// Attempts whose password is being verified
+    in_flight: u32,
+    /// Position of the latest change of the entry in `Entries::order`
+    touched: u64,
+}
+
+/// Attempts by key, along with the order they were last changed in so the oldest ones can be
+/// dropped without scanning the whole map
+#[derive(Debug, Default)]
+struct Entries {
+    by_key: HashMap<Key, Attempts>,
+    order: VecDeque<(u64, Key)>,
+    next_touch: u64,
+}
+
+impl Entries {
+    fn touch(&mut self, key: &Key) {
+        if let Some(attempts) = self.by_key.get_mut(key) {
+            attempts.touched = self.next_touch;
+            self.order.push_back((self.next_touch, key.clone()));
+            self.next_touch += 1;
+        }
+    }
+
+    /// Drops the entries whose failures are forgotten, and the oldest ones past `MAX_ENTRIES`,
+    /// from the front of `order` only
+    fn prune(&mut self, now: Instant, forget_after: Duration) {
+        while let Some((touched, key)) = self.order.front() {
+            let over_capacity =
+                self.by_key.len() > MAX_ENTRIES || self.order.len() > 2 * MAX_ENTRIES;
+
+            let remove = match self.by_key.get(key) {
+                // Changed since, the entry is further back in `order`
+                Some(attempts) if attempts.touched != *touched => false,
+                // Touched again once the verification is over
+                Some(attempts) if attempts.in_flight > 0 => {
+                    if !over_capacity {
+                        break;
+                    }
+                    false
+                }
+                Some(attempts) => {
+                    let forgotten = attempts
+                        .last_failure
+                        .is_none_or(|last_failure| now - last_failure >= forget_after);
+                    if !over_capacity && !forgotten {
+                        break;
+                    }
+                    true
+                }
+                None => false,
+            };
+
+            match self.order.pop_front() {
+                Some((_, key)) if remove => {
+                    self.by_key.remove(&key);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Tracks failed logins per IP and per username, blocking further attempts with an exponential delay
+/// and locking them out after too many failures, before any password hash is verified
+#[derive(Clone)]
+pub struct LoginThrottle {
+    config: config::LoginThrottle,
+    entries: Arc<Mutex<Entries>>,
+}
+
+/// Login attempt reserved by `LoginThrottle::begin`, released when dropped if it's neither
+/// `failed` nor `succeeded`
+pub struct LoginAttempt {
+    throttle: LoginThrottle,
+    keys: [Key; 2],
+    settled: bool,
+}
+
+impl LoginAttempt {
+    /// Records the failure, returning the number of consecutive failures for the username
+    pub fn failed(mut self, now: Instant) -> u32 {
+        self.settled = true;
+        self.throttle.record_failure(&self.keys, now)
+    }
+
+    pub fn succeeded(mut self) {
+        self.settled = true;
+
+        let mut entries = self.throttle.entries.lock().unwrap();
+        for key in &self.keys {
+            entries.by_key.remove(key);
+        }
+    }
+}
+
+impl Drop for LoginAttempt {
+    fn drop(&mut self) {
+        if self.settled {
+            return;
+        }
+
+        let mut entries = self.throttle.entries.lock().unwrap();
+        for key in &self.keys {
+            if let Some(attempts) = entries.by_key.get_mut(key) {
+                attempts.in_flight = attempts.in_flight.saturating_sub(1);
+            }
+            entries.touch(key);
+        }
+    }
+}
+
+impl LoginThrottle {
+    pub fn new(config: config::LoginThrottle) -> Self {
+        Self {
+            config,
+            entries: Arc::default(),
+        }
+    }
+
+    /// Reserves an attempt from this IP for this username, or returns the time to wait before the
+    /// next one is allowed. Attempts being verified count as failures, so concurrent requests can't
+    /// get past the free attempts, and only one at a time is allowed once they're used up
+    pub fn begin(
+        &self,
+        ip: IpAddr,
+        username: &str,
+        now: Instant,
+    ) -> Result<LoginAttempt, Duration> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.prune(now, self.forget_after());
+
+        let keys = keys(ip, username);
+
+        let retry_after = keys
+            .iter()
+            .filter_map(|key| {
+                let attempts = entries.by_key.get(key)?;
+
+                match attempts.blocked_until {
+                    Some(blocked_until) if blocked_until > now => Some(blocked_until - now),
+                    _ if attempts.in_flight > 0
+                        && attempts.failures + attempts.in_flight >= self.config.free_attempts =>
+                    {
+                        Some(Duration::from_secs(self.config.base_delay_seconds.max(1)))
+                    }
+                    _ => None,
+                }
+            })
+            .max();
+
+        if let Some(retry_after) = retry_after {
+            return Err(retry_after);
+        }
+
+        for key in &keys {
+            entries.by_key.entry(key.clone()).or_default().in_flight += 1;
+            entries.touch(key);
+        }
+
+        Ok(LoginAttempt {
+            throttle: self.clone(),
+            keys,
+            settled: false,
+        })
+    }
+
+    fn record_failure(&self, keys: &[Key; 2], now: Instant) -> u32 {
+        let mut entries = self.entries.lock().unwrap();
+
+        let failures = keys
+            .iter()
+            .map(|key| {
+                let attempts = entries.by_key.entry(key.clone()).or_default();
+                attempts.in_flight = attempts.in_flight.saturating_sub(1);
+
+                if attempts
+                    .last_failure
+                    .is_some_and(|last_failure| now - last_failure >= self.forget_after())
+                {
+                    attempts.failures = 0;
+                }
+
+                attempts.failures += 1;
+                attempts.last_failure = Some(now);
+                attempts.blocked_until = self.block_duration(attempts.failures).map(|d| now + d);
+                attempts.failures
+            })
+            .last()
+            .unwrap_or_default();
+
+        for key in keys {
+            entries.touch(key);
+        }
+        entries.prune(now, self.forget_after());
+
+        failures
+    }
+
+    /// No delay for the first free attempts, then doubling delays until the lockout threshold
+    fn block_duration(&self, failures: u32) -> Option<Duration> {
+        let config = &self.config;
+
+        if failures >= config.lockout_threshold {
+            return Some(Duration::from_secs(config.lockout_minutes * 60));
+        }
+
+        let exponent = failures.checked_sub(config.free_attempts)?;
+        let delay = config
+            .base_delay_seconds
+            .saturating_mul(2u64.saturating_pow(exponent))
+            .min(config.max_delay_seconds);
+
+        Some(Duration::from_secs(delay))
+    }
+
+    /// Failures older than a lockout are forgotten
+    fn forget_after(&self) -> Duration {
+        Duration::from_secs(self.config.lockout_minutes * 60)
+    }
+}
+
+fn keys(ip: IpAddr, username: &str) -> [Key; 2] {
+    [Key::Ip(ip), Key::Username(username.to_lowercase())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    const IP: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    fn throttle() -> LoginThrottle {
+        LoginThrottle::new(config::LoginThrottle {
+            free_attempts: 3,
+            base_delay_seconds: 1,
+            max_delay_seconds: 60,
+            lockout_threshold: 10,
+            lockout_minutes: 15,
+        })
+    }
+
+    fn retry_after(
+        throttle: &LoginThrottle,
+        ip: IpAddr,
+        username: &str,
+        now: Instant,
+    ) -> Option<Duration> {
+        throttle.begin(ip, username, now).err()
+    }
+
+    fn fail(throttle: &LoginThrottle, ip: IpAddr, username: &str, now: Instant) -> u32 {
+        throttle.record_failure(&keys(ip, username), now)
+    }
+
+    #[test]
+    fn test_given_free_attempts_when_begin_return_attempt() {
+        let throttle = throttle();
+        let now = Instant::now();
+
+        for _ in 0..2 {
+            fail(&throttle, IP, "admin", now);
+        }
+
+        assert_eq!(retry_after(&throttle, IP, "admin", now), None);
+    }
+
+    #[test]
+    fn test_given_repeated_failures_when_begin_return_exponential_delay() {
+        let throttle = throttle();
+        let now = Instant::now();
+
+        for _ in 0..5 {
+            fail(&throttle, IP, "admin", now);
+        }
+
+        assert_eq!(
+            retry_after(&throttle, IP, "admin", now),
+            Some(Duration::from_secs(4))
+        );
+        assert_eq!(
+            retry_after(&throttle, IP, "admin", now + Duration::from_secs(4)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_given_failures_from_other_ip_when_begin_return_username_lockout() {
+        let throttle = throttle();
+        let now = Instant::now();
+
+        for i in 0..10 {
+            fail(
+                &throttle,
+                IpAddr::V4(Ipv4Addr::new(10, 0, 0, i)),
+                "Admin",
+                now,
+            );
+        }
+
+        assert_eq!(
+            retry_after(&throttle, IP, "admin", now),
+            Some(Duration::from_secs(15 * 60))
+        );
+        assert_eq!(retry_after(&throttle, IP, "other", now), None);
+    }
+
+    #[test]
+    fn test_given_success_when_begin_return_attempt() {
+        let throttle = throttle();
+        let now = Instant::now();
+
+        for _ in 0..5 {
+            fail(&throttle, IP, "admin", now);
+        }
+        throttle
+            .begin(IP, "admin", now + Duration::from_secs(4))
+            .unwrap()
+            .succeeded();
+
+        assert_eq!(retry_after(&throttle, IP, "admin", now), None);
+    }
+
+    #[test]
+    fn test_given_attempts_in_flight_when_begin_return_delay_past_free_attempts() {
+        let throttle = throttle();
+        let now = Instant::now();
+
+        let attempts = (0..3)
+            .map(|_| throttle.begin(IP, "admin", now).unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(throttle.begin(IP, "admin", now).is_err());
+
+        drop(attempts);
+        assert!(throttle.begin(IP, "admin", now).is_ok());
+    }
+
+    #[test]
+    fn test_given_too_many_usernames_when_fail_drop_oldest() {
+        let throttle = throttle();
+        let now = Instant::now();
+
+        for i in 0..=MAX_ENTRIES {
+            fail(&throttle, IP, &format!("user-{i}"), now);
+        }
+
+        let entries = throttle.entries.lock().unwrap();
+        assert_eq!(entries.by_key.len(), MAX_ENTRIES);
+        assert!(
+            !entries
+                .by_key
+                .contains_key(&Key::Username("user-0".to_string()))
+        );
+        assert!(entries.by_key.contains_key(&Key::Ip(IP)));
+    }
+}
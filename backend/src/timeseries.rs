@@ -0,0 +1,42 @@
+use chrono::Utc;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::{db, models::timeseries::Bucket, state::AppState};
+
+const BUCKETS: [Bucket; 4] = [
+    Bucket::Minute,
+    Bucket::FiveMinutes,
+    Bucket::Hour,
+    Bucket::Day,
+];
+
+/// Deletes the rollups older than the retention of their bucket every `cleanup_interval_minutes`
+pub fn start_rollup_cleanup(state: AppState) {
+    let config = state.config.timeseries.clone();
+
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(config.cleanup_interval_minutes * 60));
+
+        loop {
+            interval.tick().await;
+
+            let now = Utc::now().naive_utc();
+
+            for bucket in BUCKETS {
+                let Some(retention) = config.retention(bucket) else {
+                    continue;
+                };
+
+                match db::delete_topic_post_rollups_before(&state.pool, bucket, now - retention)
+                    .await
+                {
+                    Ok(0) => {}
+                    Ok(count) => info!("Deleted {count} {} rollups", bucket.as_str()),
+                    Err(err) => error!("Error deleting {} rollups: {err}", bucket.as_str()),
+                }
+            }
+        }
+    });
+}
@@ -5,6 +5,7 @@ import {
 } from "@tanstack/react-query";
 
 import config from "@/lib/config";
+import { csrfHeaders } from "@/lib/csrf";
 
 export const useLogin = () => {
   const queryClient = useQueryClient();
@@ -40,6 +41,7 @@ export const useLogout = () => {
         `${config.rest_server_base_url}/auth/logout`,
         {
           method: "POST",
+          headers: csrfHeaders(),
           credentials: "include",
         },
       );
@@ -1,5 +1,6 @@
 import { useMutation } from "@tanstack/react-query";
 import config from "@/lib/config";
+import { csrfHeaders } from "@/lib/csrf";
 
 export const useMutationSuggestKeywords = () =>
   useMutation({
@@ -15,6 +16,7 @@ export const useMutationSuggestKeywords = () =>
         {
           method: "POST",
           headers: {
+            ...csrfHeaders(),
             "Content-Type": "application/json",
           },
           body: JSON.stringify({ subject, description }),
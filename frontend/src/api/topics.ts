@@ -5,6 +5,7 @@ import {
 } from "@tanstack/react-query";
 
 import config from "@/lib/config";
+import { csrfHeaders } from "@/lib/csrf";
 import { useSSE } from "@/api/sse";
 import { Post } from "@/api/posts";
 
@@ -42,6 +43,7 @@ export const useMutateTopic = (id: number) => {
         {
           method: "PATCH",
           headers: {
+            ...csrfHeaders(),
             "Content-Type": "application/json",
           },
           body: JSON.stringify(update),
@@ -104,6 +106,7 @@ export const useCreateTopic = () => {
       const response = await fetch(`${config.rest_server_base_url}/topics`, {
         method: "POST",
         headers: {
+          ...csrfHeaders(),
           "Content-Type": "application/json",
         },
         body: JSON.stringify(topic),
@@ -127,6 +130,7 @@ export const useDeleteTopic = () =>
         `${config.rest_server_base_url}/topics/${id}`,
         {
           method: "DELETE",
+          headers: csrfHeaders(),
           credentials: "include",
         },
       );
@@ -145,6 +149,7 @@ export const useAnalyzeTopic = () => {
         `${config.rest_server_base_url}/topics/${id}/analyze`,
         {
           method: "POST",
+          headers: csrfHeaders(),
           credentials: "include",
         },
       );
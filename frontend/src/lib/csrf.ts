@@ -0,0 +1,10 @@
+// The CSRF token is set as a cookie at login and must be sent back as a header
+// on every mutating request authenticated with the session cookie
+export const csrfHeaders = (): Record<string, string> => {
+  const token = document.cookie
+    .split("; ")
+    .find((cookie) => cookie.startsWith("csrf_token="))
+    ?.split("=")[1];
+
+  return token ? { "X-CSRF-Token": token } : {};
+};